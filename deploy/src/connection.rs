@@ -0,0 +1,140 @@
+use n64_types::{Command, FrameDecoder, MessageType};
+use std::io::{self, Read, Write};
+
+// The EverDrive only receives whole blocks, the N64 skips the padding while decoding
+const USB_BLOCK_SIZE: usize = 512;
+
+pub struct Message {
+    pub message_type: MessageType,
+    pub payload: Vec<u8>,
+}
+
+// Framed messages over the EverDrive USB or anything else that reads and writes bytes
+pub struct Connection<T> {
+    port: T,
+    decoder: FrameDecoder,
+    buffer: [u8; USB_BLOCK_SIZE],
+    start: usize,
+    end: usize,
+    errors: usize,
+}
+
+impl<T: Read + Write> Connection<T> {
+    pub fn new(port: T) -> Self {
+        Self {
+            port,
+            decoder: FrameDecoder::new(),
+            buffer: [0; USB_BLOCK_SIZE],
+            start: 0,
+            end: 0,
+            errors: 0,
+        }
+    }
+
+    // Number of frames dropped because of a bad crc
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+
+    pub fn send(&mut self, command: Command) -> io::Result<()> {
+        let mut block = [0; USB_BLOCK_SIZE];
+
+        command
+            .encode(&mut block)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, format!("{:?}", err)))?;
+
+        self.port.write_all(&block)
+    }
+
+    // Blocks until a whole frame has arrived, corrupted frames are counted and skipped
+    pub fn receive(&mut self) -> io::Result<Message> {
+        loop {
+            if self.start == self.end {
+                self.start = 0;
+                self.end = self.port.read(&mut self.buffer)?;
+
+                if self.end == 0 {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+            }
+
+            while self.start < self.end {
+                let byte = self.buffer[self.start];
+                self.start += 1;
+
+                match self.decoder.push(byte) {
+                    Some(Ok(frame)) => {
+                        return Ok(Message {
+                            message_type: frame.message_type,
+                            payload: frame.payload.to_vec(),
+                        })
+                    }
+                    Some(Err(_)) => self.errors += 1,
+                    None => (),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use n64_types::{encode_frame, FRAME_HEADER_SIZE, FRAME_MAX_SIZE};
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn loopback() {
+        let (host, mut device) = UnixStream::pair().unwrap();
+        let mut connection = Connection::new(host);
+
+        connection
+            .send(Command::SetVariable { id: 2, value: 0.5 })
+            .unwrap();
+        connection.send(Command::StepFrame).unwrap();
+
+        // Read like the N64 does, one block at a time
+        let mut decoder = FrameDecoder::new();
+        let mut block = [0; USB_BLOCK_SIZE];
+        let mut commands = Vec::new();
+
+        for _ in 0..2 {
+            device.read_exact(&mut block).unwrap();
+
+            for byte in block {
+                if let Some(Ok(frame)) = decoder.push(byte) {
+                    commands.extend(Command::decode(&frame));
+                }
+            }
+        }
+
+        assert_eq!(
+            commands,
+            [
+                Command::SetVariable { id: 2, value: 0.5 },
+                Command::StepFrame
+            ]
+        );
+
+        let mut frame = [0; FRAME_MAX_SIZE];
+
+        let len = encode_frame(MessageType::Print, b"hello", &mut frame).unwrap();
+        frame[FRAME_HEADER_SIZE] ^= 0xff;
+        device.write_all(&frame[..len]).unwrap();
+
+        let len = encode_frame(MessageType::Print, b"hello", &mut frame).unwrap();
+        device.write_all(&frame[..len]).unwrap();
+
+        let len = encode_frame(MessageType::Crash, &[1, 2, 3], &mut frame).unwrap();
+        device.write_all(&frame[..len]).unwrap();
+
+        let message = connection.receive().unwrap();
+        assert_eq!(message.message_type, MessageType::Print);
+        assert_eq!(message.payload, b"hello");
+        assert_eq!(connection.errors(), 1);
+
+        let message = connection.receive().unwrap();
+        assert_eq!(message.message_type, MessageType::Crash);
+        assert_eq!(message.payload, [1, 2, 3]);
+    }
+}
@@ -1,5 +1,12 @@
-use crate::profiler::N64Profiler;
-use n64_types::{ProfilerMessageBuffer, MESSAGE_MAGIC_PRINT, MESSAGE_MAGIC_PROFILER};
+use crate::{
+    connection::Connection,
+    profiler::N64Profiler,
+    screenshot::{Screenshot, ScreenshotReceiver, SCREENSHOT_DIR},
+    symbolicate::Symbolicator,
+};
+use n64_types::{
+    Command, CrashMessageBuffer, MessageType, ProfilerMessageBuffer, ARCHIVE_ROM_OFFSET_LOCATION,
+};
 use serialport::SerialPort;
 use std::{
     collections::HashMap,
@@ -8,12 +15,44 @@ use std::{
     fs,
     io::{self, Write},
     mem::size_of,
-    process::{self, Command},
-    time::Duration,
+    path::Path,
+    process,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use zerocopy::LayoutVerified;
 
+mod connection;
 mod profiler;
+mod screenshot;
+mod symbolicate;
+
+const GAME_ELF: &str = "target/mips-nintendo64-none/release/game";
+
+// The boot code checksums the first megabyte of the program, so the assets go after it
+const ROM_CHECKSUM_END: usize = 0x10_1000;
+
+// The EverDrive transfers the rom in blocks
+const ROM_BLOCK_SIZE: usize = 512;
+
+// How long to wait for messages before sending the commands typed in the meantime
+const RECEIVE_TIMEOUT: Duration = Duration::from_millis(50);
+
+fn append_assets(rom: &mut Vec<u8>, archive: &[u8]) {
+    let offset =
+        (rom.len().max(ROM_CHECKSUM_END) + ROM_BLOCK_SIZE - 1) / ROM_BLOCK_SIZE * ROM_BLOCK_SIZE;
+
+    rom.resize(offset, 0);
+    rom.extend_from_slice(archive);
+    rom.resize(
+        (rom.len() + ROM_BLOCK_SIZE - 1) / ROM_BLOCK_SIZE * ROM_BLOCK_SIZE,
+        0,
+    );
+
+    rom[ARCHIVE_ROM_OFFSET_LOCATION..ARCHIVE_ROM_OFFSET_LOCATION + 4]
+        .copy_from_slice(&(offset as u32).to_be_bytes());
+}
 
 fn write_cmd(port: &mut dyn SerialPort, cmd: u8, addr: u32, len: u32, arg: u32) {
     let len = len / 512;
@@ -65,6 +104,164 @@ fn find_everdrive() -> Box<dyn SerialPort> {
     process::exit(1);
 }
 
+fn print_backtrace(symbolicator: Option<&Symbolicator>, backtrace: &[(u32, bool)]) {
+    println!("Backtrace:");
+
+    if backtrace.is_empty() {
+        println!("  No return addresses found on the stack");
+    }
+
+    for (i, &(address, is_return_address)) in backtrace.iter().enumerate() {
+        let Some(symbolicator) = symbolicator else {
+            println!("{:4}: {:#010x}", i, address);
+            continue;
+        };
+
+        // Look up the call instead of the instruction after its delay slot
+        let lookup = if is_return_address {
+            address.wrapping_sub(8)
+        } else {
+            address
+        };
+
+        for (j, frame) in symbolicator.resolve(lookup as u64).iter().enumerate() {
+            let function = frame.function.as_deref().unwrap_or("??");
+
+            if j == 0 {
+                println!("{:4}: {:#010x} - {}", i, address, function);
+            } else {
+                println!("                    {} (inlined)", function);
+            }
+
+            if let Some(location) = &frame.location {
+                println!("                      at {}", location);
+            }
+        }
+    }
+}
+
+fn parse_command(line: &str) -> Option<Command> {
+    let mut parts = line.split_whitespace();
+
+    Some(match parts.next()? {
+        "pause" => Command::Pause,
+        "resume" => Command::Resume,
+        "step" => Command::StepFrame,
+        "set" => Command::SetVariable {
+            id: parts.next()?.parse().ok()?,
+            value: parts.next()?.parse().ok()?,
+        },
+        "screenshot" => Command::RequestScreenshot,
+        _ => return None,
+    })
+}
+
+// Commands are typed on stdin while the game runs
+fn read_commands() -> Receiver<Command> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for line in io::stdin().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            match parse_command(&line) {
+                Some(command) => {
+                    if sender.send(command).is_err() {
+                        break;
+                    }
+                }
+                None => println!(
+                    "Unknown command: {}, expected pause, resume, step, set <id> <value> or screenshot",
+                    line.trim()
+                ),
+            }
+        }
+    });
+
+    receiver
+}
+
+fn handle_crash(
+    payload: &[u8],
+    symbolicator: Option<&Symbolicator>,
+    backtrace: &mut Vec<(u32, bool)>,
+) {
+    let Some(crash_message) = LayoutVerified::<&[u8], CrashMessageBuffer>::new_unaligned(payload)
+    else {
+        return;
+    };
+    let crash_message = crash_message.into_ref();
+
+    if crash_message.index == 0 {
+        backtrace.clear();
+    }
+
+    if crash_message.index < crash_message.count {
+        backtrace.push((
+            crash_message.get_address_from_be(),
+            crash_message.is_return_address != 0,
+        ));
+    }
+
+    if crash_message.index + 1 >= crash_message.count {
+        print_backtrace(symbolicator, backtrace);
+    }
+}
+
+// A message holds as many scopes as fit, each with its index in the frame
+fn handle_profiler(payload: &[u8], profiler: &mut N64Profiler, scope_names: &HashMap<i16, String>) {
+    for chunk in payload.chunks_exact(size_of::<ProfilerMessageBuffer>()) {
+        let profiler_message =
+            LayoutVerified::<&[u8], ProfilerMessageBuffer>::new_unaligned(chunk).unwrap();
+        let profiler_message = profiler_message.into_ref();
+
+        let index = i16::from_be(profiler_message.index);
+        let count = i16::from_be(profiler_message.count);
+
+        let scope = profiler_message.get_scope_from_be();
+
+        if index == 0 {
+            puffin::GlobalProfiler::lock().new_frame();
+        }
+
+        profiler.submit_scope(scope, scope_names);
+
+        if index == count - 1 {
+            profiler.flush_frame();
+        }
+    }
+}
+
+fn handle_screenshot(payload: &[u8], receiver: &mut ScreenshotReceiver) {
+    for screenshot in receiver.receive(payload, Instant::now()) {
+        save_screenshot(&screenshot);
+    }
+}
+
+// Partial screenshots are saved too, with the missing pixels left black
+fn save_screenshot(screenshot: &Screenshot) {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_millis())
+        .unwrap_or(0);
+
+    let path = Path::new(SCREENSHOT_DIR).join(format!("n64_screenshot_{}.png", time));
+
+    if screenshot.missing_pixels() > 0 {
+        println!(
+            "Screenshot is missing {} pixels",
+            screenshot.missing_pixels()
+        );
+    }
+
+    match screenshot.save_png(&path) {
+        Ok(()) => println!("Saved {}", path.display()),
+        Err(err) => println!("Screenshot Error: {}", err),
+    }
+}
+
 fn fetch_scope_names() -> HashMap<i16, String> {
     let file = fs::read_to_string("scope_names.txt").unwrap();
 
@@ -91,7 +288,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         env::set_current_dir("../")?;
     }
 
-    assert!(Command::new("cargo")
+    assert!(process::Command::new("cargo")
         .args([
             "n64",
             "build",
@@ -106,11 +303,28 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let scope_names = fetch_scope_names();
 
+    let symbolicator = match Symbolicator::load(GAME_ELF) {
+        Ok(symbolicator) => Some(symbolicator),
+        Err(err) => {
+            println!("Unable to load symbols from {}: {}", GAME_ELF, err);
+            None
+        }
+    };
+
     let mut ed = find_everdrive();
 
     println!("Found EverDrive");
 
-    let rom = fs::read("target/mips-nintendo64-none/release/game.n64").unwrap();
+    let mut rom = fs::read("target/mips-nintendo64-none/release/game.n64").unwrap();
+    let assets = fs::read("game/assets.larc").unwrap();
+
+    append_assets(&mut rom, &assets);
+
+    println!(
+        "Rom: {} KB, assets: {} KB",
+        rom.len() / 1024,
+        assets.len() / 1024
+    );
 
     {
         write_cmd(&mut *ed, b'W', 0x10000000, rom.len() as u32, 0);
@@ -118,46 +332,51 @@ fn main() -> Result<(), Box<dyn Error>> {
         write_cmd(&mut *ed, b's', 0, 0, 0);
     }
 
+    ed.set_timeout(RECEIVE_TIMEOUT)?;
+
+    let mut connection = Connection::new(ed);
+    let commands = read_commands();
+
     let mut profiler = N64Profiler::default();
+    let mut backtrace = Vec::new();
+    let mut screenshots = ScreenshotReceiver::default();
+    let mut errors = 0;
 
     loop {
-        let mut buf = [0; 32];
+        for command in commands.try_iter() {
+            connection.send(command)?;
+        }
 
-        assert_eq!(ed.read(&mut buf[0..1]).unwrap(), 1);
+        if let Some(screenshot) = screenshots.expire(Instant::now()) {
+            save_screenshot(&screenshot);
+        }
+
+        let message = match connection.receive() {
+            Ok(message) => message,
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        if connection.errors() != errors {
+            errors = connection.errors();
+            println!("Dropped {} corrupted messages", errors);
+        }
 
-        if buf[0] == MESSAGE_MAGIC_PRINT {
-            assert_eq!(ed.read(&mut buf[1..32]).unwrap(), 31);
-            for b in &buf[1..32] {
-                print!("{}", *b as char);
+        match message.message_type {
+            MessageType::Print => {
+                print!("{}", String::from_utf8_lossy(&message.payload));
                 io::stdout().flush().ok();
             }
-        }
-        if buf[0] == MESSAGE_MAGIC_PROFILER {
-            assert_eq!(
-                ed.read(&mut buf[1..size_of::<ProfilerMessageBuffer>()])
-                    .unwrap(),
-                size_of::<ProfilerMessageBuffer>() - 1
-            );
-            let profiler_message = LayoutVerified::<&[u8], ProfilerMessageBuffer>::new_unaligned(
-                &buf[..size_of::<ProfilerMessageBuffer>()],
-            )
-            .unwrap();
-            let profiler_message = profiler_message.into_ref();
-
-            let index = i16::from_be(profiler_message.index);
-            let count = i16::from_be(profiler_message.count);
-
-            let scope = profiler_message.get_scope_from_be();
-
-            if index == 0 {
-                puffin::GlobalProfiler::lock().new_frame();
+            MessageType::Crash => {
+                handle_crash(&message.payload, symbolicator.as_ref(), &mut backtrace);
             }
-
-            profiler.submit_scope(scope, &scope_names);
-
-            if index == count - 1 {
-                profiler.flush_frame();
+            MessageType::Profiler => {
+                handle_profiler(&message.payload, &mut profiler, &scope_names);
+            }
+            MessageType::Screenshot => {
+                handle_screenshot(&message.payload, &mut screenshots);
             }
+            _ => (),
         }
     }
 }
@@ -0,0 +1,233 @@
+use n64_types::ScreenshotMessageHeader;
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    mem::size_of,
+    path::Path,
+    time::{Duration, Instant},
+};
+use zerocopy::LayoutVerified;
+
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+// The N64 sends a message every few milliseconds, a screenshot that stops this long is given up on
+const SCREENSHOT_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub struct Screenshot {
+    pub width: usize,
+    pub height: usize,
+    // Big endian 5551
+    pixels: Vec<u16>,
+    received: usize,
+}
+
+impl Screenshot {
+    // Pixels from dropped messages stay black
+    pub fn missing_pixels(&self) -> usize {
+        self.pixels.len() - self.received
+    }
+
+    // Same format as the emulator screenshots, so they can be compared directly
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let channel = |value: u16| ((value & 0b11111) * 255 / 31) as u8;
+
+        self.pixels
+            .iter()
+            .flat_map(|&pixel| {
+                [
+                    channel(pixel >> 11),
+                    channel(pixel >> 6),
+                    channel(pixel >> 1),
+                    0xff,
+                ]
+            })
+            .collect()
+    }
+
+    pub fn save_png(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = BufWriter::new(File::create(path)?);
+
+        let mut encoder = png::Encoder::new(file, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.to_rgba()))
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))
+    }
+}
+
+// Collects the screenshot messages the N64 sends in pixel order
+#[derive(Default)]
+pub struct ScreenshotReceiver {
+    current: Option<Screenshot>,
+    last_message: Option<Instant>,
+}
+
+impl ScreenshotReceiver {
+    // Returns the screenshots that are done, either because their last message has arrived or
+    // because the next screenshot started before that
+    pub fn receive(&mut self, payload: &[u8], now: Instant) -> Vec<Screenshot> {
+        let mut done = Vec::new();
+
+        let (header, data) =
+            payload.split_at(size_of::<ScreenshotMessageHeader>().min(payload.len()));
+        let Some(header) = LayoutVerified::new_unaligned(header) else {
+            return done;
+        };
+        let header: &ScreenshotMessageHeader = header.into_ref();
+
+        let width = header.get_width_from_be() as usize;
+        let height = header.get_height_from_be() as usize;
+        let offset = header.get_offset_from_be() as usize;
+
+        let restart = match &self.current {
+            Some(screenshot) => {
+                offset == 0 || screenshot.width != width || screenshot.height != height
+            }
+            None => true,
+        };
+
+        if restart {
+            done.extend(self.current.take().filter(|partial| partial.received > 0));
+
+            self.current = Some(Screenshot {
+                width,
+                height,
+                pixels: vec![0; width * height],
+                received: 0,
+            });
+        }
+
+        self.last_message = Some(now);
+
+        let Some(screenshot) = self.current.as_mut() else {
+            return done;
+        };
+
+        let end = (offset + data.len() / 2).min(screenshot.pixels.len());
+
+        if offset < end {
+            for (pixel, bytes) in screenshot.pixels[offset..end]
+                .iter_mut()
+                .zip(data.chunks_exact(2))
+            {
+                *pixel = u16::from_be_bytes([bytes[0], bytes[1]]);
+            }
+
+            screenshot.received += end - offset;
+        }
+
+        if end == screenshot.pixels.len() {
+            done.extend(self.current.take());
+        }
+
+        done
+    }
+
+    // Returns the partial screenshot when no message has arrived for a while
+    pub fn expire(&mut self, now: Instant) -> Option<Screenshot> {
+        let last_message = self.last_message?;
+
+        if now.duration_since(last_message) < SCREENSHOT_TIMEOUT {
+            return None;
+        }
+
+        self.last_message = None;
+        self.current.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use n64_types::SCREENSHOT_PIXELS_PER_MESSAGE;
+    use zerocopy::AsBytes;
+
+    const WIDTH: usize = 32;
+    const HEIGHT: usize = 20;
+
+    fn test_pixels() -> Vec<u16> {
+        (0..WIDTH * HEIGHT)
+            .map(|i| if i % 3 == 0 { 0xf801 } else { 0x07c1 })
+            .collect()
+    }
+
+    // The payloads the N64 sends for a screenshot
+    fn messages(pixels: &[u16]) -> Vec<Vec<u8>> {
+        pixels
+            .chunks(SCREENSHOT_PIXELS_PER_MESSAGE)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let header = ScreenshotMessageHeader {
+                    width: (WIDTH as u16).to_be(),
+                    height: (HEIGHT as u16).to_be(),
+                    offset: ((i * SCREENSHOT_PIXELS_PER_MESSAGE) as u32).to_be(),
+                };
+
+                let mut payload = header.as_bytes().to_vec();
+                payload.extend(chunk.iter().flat_map(|pixel| pixel.to_be_bytes()));
+                payload
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reassembles_screenshot() {
+        let now = Instant::now();
+        let mut receiver = ScreenshotReceiver::default();
+        let mut result = Vec::new();
+
+        for payload in messages(&test_pixels()) {
+            assert!(result.is_empty());
+            result = receiver.receive(&payload, now);
+        }
+
+        let screenshot = result.pop().unwrap();
+        let rgba = screenshot.to_rgba();
+
+        assert_eq!(screenshot.missing_pixels(), 0);
+        assert_eq!(rgba.len(), WIDTH * HEIGHT * 4);
+        assert_eq!(&rgba[0..8], &[0xff, 0, 0, 0xff, 0, 0xff, 0, 0xff]);
+    }
+
+    #[test]
+    fn next_screenshot_returns_partial() {
+        let now = Instant::now();
+        let messages = messages(&test_pixels());
+        let mut receiver = ScreenshotReceiver::default();
+
+        assert!(receiver.receive(&messages[0], now).is_empty());
+
+        let partial = receiver.receive(&messages[0], now);
+
+        assert_eq!(partial.len(), 1);
+        assert_eq!(
+            partial[0].missing_pixels(),
+            WIDTH * HEIGHT - SCREENSHOT_PIXELS_PER_MESSAGE
+        );
+    }
+
+    #[test]
+    fn timeout_returns_partial() {
+        let now = Instant::now();
+        let messages = messages(&test_pixels());
+        let mut receiver = ScreenshotReceiver::default();
+
+        assert!(receiver.receive(&messages[0], now).is_empty());
+        assert!(receiver.expire(now + SCREENSHOT_TIMEOUT / 2).is_none());
+
+        let partial = receiver.expire(now + SCREENSHOT_TIMEOUT).unwrap();
+
+        assert_eq!(
+            partial.missing_pixels(),
+            WIDTH * HEIGHT - SCREENSHOT_PIXELS_PER_MESSAGE
+        );
+        assert!(receiver.expire(now + 2 * SCREENSHOT_TIMEOUT).is_none());
+    }
+}
@@ -0,0 +1,88 @@
+use addr2line::{
+    demangle_auto,
+    gimli::{EndianRcSlice, RunTimeEndian},
+    object::{self, Object, ObjectSymbol, SymbolKind},
+    Context,
+};
+use std::{error::Error, fs, path::Path};
+
+pub struct Frame {
+    pub function: Option<String>,
+    pub location: Option<String>,
+}
+
+// Resolves addresses from crash reports with the DWARF line info of the game elf, falling back to
+// the symbol table for code without debug info
+pub struct Symbolicator {
+    context: Context<EndianRcSlice<RunTimeEndian>>,
+    // Sorted by address
+    symbols: Vec<(u64, u64, String)>,
+}
+
+impl Symbolicator {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let data = fs::read(path)?;
+        let object = object::File::parse(&*data)?;
+        let context = Context::new(&object)?;
+
+        let mut symbols = object
+            .symbols()
+            .filter(|symbol| symbol.kind() == SymbolKind::Text)
+            .filter_map(|symbol| {
+                let name = demangle_auto(symbol.name().ok()?.into(), None);
+                Some((symbol.address(), symbol.size(), name.into_owned()))
+            })
+            .collect::<Vec<_>>();
+
+        symbols.sort_by_key(|(address, _, _)| *address);
+
+        Ok(Self { context, symbols })
+    }
+
+    fn symbol(&self, address: u64) -> Option<&str> {
+        let index = self
+            .symbols
+            .partition_point(|(start, _, _)| *start <= address)
+            .checked_sub(1)?;
+
+        let (start, size, name) = &self.symbols[index];
+
+        if address < start + (*size).max(1) {
+            Some(name)
+        } else {
+            None
+        }
+    }
+
+    // Inlined functions come first and the function they were inlined into last
+    pub fn resolve(&self, address: u64) -> Vec<Frame> {
+        let mut result = Vec::new();
+
+        if let Ok(mut frames) = self.context.find_frames(address).skip_all_loads() {
+            while let Ok(Some(frame)) = frames.next() {
+                result.push(Frame {
+                    function: frame
+                        .function
+                        .and_then(|function| function.demangle().ok())
+                        .map(|name| name.into_owned()),
+                    location: frame.location.map(|location| {
+                        format!(
+                            "{}:{}",
+                            location.file.unwrap_or("??"),
+                            location.line.unwrap_or(0)
+                        )
+                    }),
+                });
+            }
+        }
+
+        if result.is_empty() {
+            result.push(Frame {
+                function: self.symbol(address).map(str::to_string),
+                location: None,
+            });
+        }
+
+        result
+    }
+}
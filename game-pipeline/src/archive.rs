@@ -0,0 +1,157 @@
+use crate::{
+    lz,
+    utils::{write_binary_file_if_changed, write_file_if_changed},
+};
+use n64_types::{archive_data_start, archive_header, ArchiveEntry, ARCHIVE_ALIGN};
+use std::env;
+
+const ARCHIVE_FILE: &str = "assets.larc";
+
+#[rustfmt::skip]
+macro_rules! ASSET_TEMPLATE { () => {
+r##"static {ident}: Asset = Asset::new(&crate::assets::ARCHIVE, {index});
+"##
+}; }
+
+#[rustfmt::skip]
+macro_rules! ASSETS_TEMPLATE { () => {
+r##"// This file is generated
+
+#![cfg_attr(rustfmt, rustfmt::skip)]
+
+use n64::Archive;
+
+pub static ARCHIVE: Archive = Archive::new({path:?});
+"##
+}; }
+
+fn ratio(stored_len: usize, len: usize) -> f32 {
+    if len == 0 {
+        100.0
+    } else {
+        100.0 * stored_len as f32 / len as f32
+    }
+}
+
+fn report<'a>(name: &str, entries: impl Iterator<Item = &'a ArchiveEntry>) {
+    let (len, stored_len) = entries.fold((0, 0), |(len, stored_len), e| {
+        (len + e.len as usize, stored_len + e.stored_len as usize)
+    });
+
+    println!(
+        "cargo:warning={}: {} KB compressed to {} KB ({:.0}%)",
+        name,
+        len / 1024,
+        stored_len / 1024,
+        ratio(stored_len, len),
+    );
+}
+
+// Only used to report the compression ratios at build time
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AssetKind {
+    Texture,
+    Map,
+    Model,
+    Sound,
+    Replay,
+}
+
+impl AssetKind {
+    const ALL: [AssetKind; 5] = [
+        AssetKind::Texture,
+        AssetKind::Map,
+        AssetKind::Model,
+        AssetKind::Sound,
+        AssetKind::Replay,
+    ];
+}
+
+// Asset data that is loaded when it is used instead of being compiled into the binary
+#[derive(Default)]
+pub struct ArchiveBuilder {
+    entries: Vec<(AssetKind, ArchiveEntry)>,
+    data: Vec<u8>,
+}
+
+impl ArchiveBuilder {
+    // Returns the declaration of a static Asset for the data
+    pub fn add(&mut self, kind: AssetKind, ident: &str, data: &[u8]) -> String {
+        let index = self.entries.len();
+
+        // Data that does not get smaller is stored as is
+        let compressed = lz::compress(data);
+        let stored = if compressed.len() < data.len() {
+            &compressed[..]
+        } else {
+            data
+        };
+
+        println!(
+            "Asset {}: {} -> {} bytes ({:.0}%)",
+            ident,
+            data.len(),
+            stored.len(),
+            ratio(stored.len(), data.len()),
+        );
+
+        // Offsets are from the start of the data until the directory size is known
+        self.entries.push((
+            kind,
+            ArchiveEntry {
+                offset: self.data.len() as u32,
+                len: data.len() as u32,
+                stored_len: stored.len() as u32,
+            },
+        ));
+
+        self.data.extend_from_slice(stored);
+        self.data.resize(
+            (self.data.len() + ARCHIVE_ALIGN - 1) / ARCHIVE_ALIGN * ARCHIVE_ALIGN,
+            0,
+        );
+
+        format!(ASSET_TEMPLATE!(), ident = ident, index = index)
+    }
+
+    pub fn write(self) {
+        let data_start = archive_data_start(self.entries.len() as u32);
+
+        let mut archive = Vec::with_capacity(data_start + self.data.len());
+        archive.extend_from_slice(&archive_header(self.entries.len() as u32));
+
+        for (_, entry) in &self.entries {
+            let entry = ArchiveEntry {
+                offset: entry.offset + data_start as u32,
+                ..*entry
+            };
+
+            archive.extend_from_slice(&entry.to_bytes());
+        }
+
+        archive.resize(data_start, 0);
+        archive.extend_from_slice(&self.data);
+
+        for kind in AssetKind::ALL {
+            report(
+                &format!("{:?} assets", kind),
+                self.entries
+                    .iter()
+                    .filter(|(k, _)| *k == kind)
+                    .map(|(_, e)| e),
+            );
+        }
+
+        report("Assets", self.entries.iter().map(|(_, e)| e));
+
+        let path = env::current_dir().unwrap().join(ARCHIVE_FILE);
+
+        write_binary_file_if_changed(&path, &archive).unwrap();
+
+        write_file_if_changed(
+            env::current_dir().unwrap().join("src").join("assets.rs"),
+            format!(ASSETS_TEMPLATE!(), path = path),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{
+    archive::{ArchiveBuilder, AssetKind},
+    image::{load_png, Image},
+    utils::write_file_if_changed,
+};
+use n64_types::{tmem_line_width, tmem_page_rows, TMEM_SIZE};
+use std::{env, ffi::OsStr, fs, path::Path};
+
+// Transparent border around every image so bilinear filtering does not bleed between them
+const PADDING: i32 = 1;
+
+#[rustfmt::skip]
+macro_rules! ATLAS_TEMPLATE { () => {
+r##"{asset}pub static {name}: StaticTexture = StaticTexture::from_asset({width}, {height}, &{name}_DATA);
+"##
+}; }
+
+#[rustfmt::skip]
+macro_rules! RECT_TEMPLATE { () => {
+r##"pub const {name}: TextureRect = TextureRect::new({x}, {y}, {width}, {height});
+"##
+}; }
+
+#[rustfmt::skip]
+macro_rules! ATLASES_TEMPLATE { () => {
+r##"// This file is generated
+
+#![cfg_attr(rustfmt, rustfmt::skip)]
+
+use n64::{{gfx::{{StaticTexture, TextureRect}}, Asset}};
+
+{atlases}"##
+}; }
+
+struct PackedImage {
+    name: String,
+    x: i32,
+    y: i32,
+    image: Image,
+}
+
+// Shelf packer, places images in rows and returns the height and the position of every image.
+// With page_rows set no row crosses a page boundary
+fn pack_shelves(
+    images: &[(String, Image)],
+    width: i32,
+    page_rows: Option<i32>,
+) -> (i32, Vec<(i32, i32)>) {
+    let mut positions = Vec::with_capacity(images.len());
+
+    let mut x = 0;
+    let mut y = 0;
+    let mut shelf_height = 0;
+
+    for (_, image) in images {
+        let slot_width = image.width + 2 * PADDING;
+        let slot_height = image.height + 2 * PADDING;
+
+        if x + slot_width > width {
+            x = 0;
+            y += shelf_height;
+            shelf_height = 0;
+        }
+
+        // Images are sorted by height, so the first one decides the height of the shelf
+        if let Some(rows) = page_rows {
+            if x == 0 && y % rows + slot_height > rows {
+                y = (y / rows + 1) * rows;
+            }
+        }
+
+        positions.push((x + PADDING, y + PADDING));
+
+        x += slot_width;
+        shelf_height = shelf_height.max(slot_height);
+    }
+
+    (y + shelf_height, positions)
+}
+
+// Atlases that do not fit in tmem are split into pages that do, the width is picked to waste as
+// little space as possible at the end of the pages
+fn pack(mut images: Vec<(String, Image)>) -> (i32, i32, Vec<PackedImage>) {
+    images.sort_by(|(a_name, a), (b_name, b)| b.height.cmp(&a.height).then(a_name.cmp(b_name)));
+
+    let area: i32 = images
+        .iter()
+        .map(|(_, image)| (image.width + 2 * PADDING) * (image.height + 2 * PADDING))
+        .sum();
+
+    let max_width = images
+        .iter()
+        .map(|(_, image)| image.width + 2 * PADDING)
+        .max()
+        .unwrap_or(0);
+
+    let max_height = images
+        .iter()
+        .map(|(_, image)| image.height + 2 * PADDING)
+        .max()
+        .unwrap_or(0);
+
+    // Power of two width keeps rows a whole number of tmem lines
+    let width = ((area as f32).sqrt() as u32)
+        .max(max_width as u32)
+        .next_power_of_two() as i32;
+
+    let (mut width, mut height, mut positions) = {
+        let (height, positions) = pack_shelves(&images, width, None);
+        (width, height, positions)
+    };
+
+    if 2 * tmem_line_width(width) * height > TMEM_SIZE {
+        let paged = (tmem_line_width(max_width.max(1))..=TMEM_SIZE / 2)
+            .step_by(4)
+            .filter(|&width| tmem_page_rows(width) >= max_height)
+            .map(|width| {
+                let (height, positions) = pack_shelves(&images, width, Some(tmem_page_rows(width)));
+                (width, height, positions)
+            })
+            .min_by_key(|(width, height, _)| (width * height, *width))
+            .expect("Image in atlas does not fit in tmem");
+
+        (width, height, positions) = paged;
+    }
+
+    let packed = images
+        .into_iter()
+        .zip(positions)
+        .map(|((name, image), (x, y))| PackedImage { name, x, y, image })
+        .collect();
+
+    (width, height, packed)
+}
+
+fn parse_atlas(archive: &mut ArchiveBuilder, dir: &Path, atlases: &mut String) {
+    let name = dir.file_name().unwrap().to_string_lossy();
+
+    let mut images = Vec::new();
+
+    for path in fs::read_dir(dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension() == Some(OsStr::new("png")))
+    {
+        if let Some(image_name) = path.file_stem().map(|n| n.to_string_lossy().to_string()) {
+            images.push((image_name, load_png(path.as_path(), false, None).unwrap()));
+        }
+    }
+
+    let (width, height, packed) = pack(images);
+
+    let mut data = vec![0; (2 * width * height) as usize];
+
+    for packed_image in packed.iter() {
+        let image = &packed_image.image;
+        let row_size = (2 * image.width) as usize;
+
+        for row in 0..image.height {
+            let src = (row * image.width * 2) as usize;
+            let dst = (((packed_image.y + row) * width + packed_image.x) * 2) as usize;
+
+            data[dst..dst + row_size].copy_from_slice(&image.data[src..src + row_size]);
+        }
+    }
+
+    let atlas_name = format!("{}_ATLAS", name.to_uppercase());
+    let asset = archive.add(AssetKind::Texture, &format!("{}_DATA", atlas_name), &data);
+
+    atlases.push_str(&format!(
+        ATLAS_TEMPLATE!(),
+        asset = asset,
+        name = atlas_name,
+        width = width,
+        height = height,
+    ));
+
+    let mut rects = packed;
+    rects.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for rect in rects {
+        atlases.push_str(&format!(
+            RECT_TEMPLATE!(),
+            name = rect.name.to_uppercase(),
+            x = rect.x,
+            y = rect.y,
+            width = rect.image.width,
+            height = rect.image.height
+        ));
+    }
+}
+
+pub(crate) fn parse(archive: &mut ArchiveBuilder) {
+    let mut atlases = String::new();
+
+    let mut dirs = fs::read_dir("atlases")
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+
+    dirs.sort();
+
+    for dir in dirs {
+        println!("rerun-if-changed={}", dir.to_string_lossy());
+        parse_atlas(archive, &dir, &mut atlases);
+    }
+
+    let atlases = format!(ATLASES_TEMPLATE!(), atlases = atlases);
+
+    write_file_if_changed(
+        env::current_dir().unwrap().join("src").join("atlases.rs"),
+        atlases,
+    )
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn images(count: usize, size: i32) -> Vec<(String, Image)> {
+        (0..count)
+            .map(|i| {
+                let image = Image {
+                    width: size,
+                    height: size,
+                    data: vec![0; (2 * size * size) as usize],
+                };
+                (format!("image_{}", i), image)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn small_atlas_is_one_page() {
+        let (width, height, packed) = pack(images(4, 8));
+
+        assert_eq!(packed.len(), 4);
+        assert!(2 * tmem_line_width(width) * height <= TMEM_SIZE);
+    }
+
+    #[test]
+    fn large_atlas_is_split_into_pages() {
+        // Like the font, 96 glyphs of 16x16
+        let (width, height, packed) = pack(images(96, 16));
+        let rows = tmem_page_rows(width);
+
+        assert_eq!(packed.len(), 96);
+        assert!(2 * tmem_line_width(width) * height > TMEM_SIZE);
+
+        for image in &packed {
+            let top = image.y - PADDING;
+            let bottom = image.y + image.image.height + PADDING;
+            assert_eq!(
+                top / rows,
+                (bottom - 1) / rows,
+                "{} crosses a page",
+                image.name
+            );
+            assert!(image.x + image.image.width + PADDING <= width);
+        }
+
+        for (i, a) in packed.iter().enumerate() {
+            for b in &packed[i + 1..] {
+                let overlap_x = a.x < b.x + b.image.width && b.x < a.x + a.image.width;
+                let overlap_y = a.y < b.y + b.image.height && b.y < a.y + a.image.height;
+                assert!(!(overlap_x && overlap_y));
+            }
+        }
+    }
+}
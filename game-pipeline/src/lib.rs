@@ -1,15 +1,25 @@
-use std::path::Path;
+use archive::ArchiveBuilder;
 
+pub mod archive;
+pub mod atlases;
 pub mod image;
+pub mod lz;
 pub mod maps;
 pub mod models;
+pub mod replays;
 pub mod sounds;
 pub mod textures;
 pub mod utils;
 
-pub fn run(out_dir: &Path) {
-    textures::parse();
-    maps::parse(out_dir);
-    sounds::parse();
-    models::parse();
+pub fn run() {
+    let mut archive = ArchiveBuilder::default();
+
+    atlases::parse(&mut archive);
+    textures::parse(&mut archive);
+    maps::parse(&mut archive);
+    sounds::parse(&mut archive);
+    models::parse(&mut archive);
+    replays::parse(&mut archive);
+
+    archive.write();
 }
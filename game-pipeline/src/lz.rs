@@ -0,0 +1,135 @@
+use n64_types::{LZ_MAX_OFFSET, LZ_MIN_MATCH};
+
+const HASH_BITS: u32 = 16;
+
+fn hash(bytes: &[u8]) -> usize {
+    let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    (value.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+}
+
+fn write_length(out: &mut Vec<u8>, mut len: usize) {
+    while len >= 255 {
+        out.push(255);
+        len -= 255;
+    }
+
+    out.push(len as u8);
+}
+
+fn write_sequence(out: &mut Vec<u8>, literals: &[u8], m: Option<(usize, usize)>) {
+    let match_code = m.map(|(_, len)| len - LZ_MIN_MATCH).unwrap_or(0);
+
+    out.push(((literals.len().min(15) as u8) << 4) | match_code.min(15) as u8);
+
+    if literals.len() >= 15 {
+        write_length(out, literals.len() - 15);
+    }
+
+    out.extend_from_slice(literals);
+
+    if let Some((offset, _)) = m {
+        out.extend_from_slice(&(offset as u16).to_be_bytes());
+
+        if match_code >= 15 {
+            write_length(out, match_code - 15);
+        }
+    }
+}
+
+// Greedy matching against the last position with the same hash, decoded with n64_types::LzDecoder
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() / 2);
+    let mut table = vec![usize::MAX; 1 << HASH_BITS];
+
+    let mut pos = 0;
+    let mut literal_start = 0;
+
+    while pos + LZ_MIN_MATCH <= input.len() {
+        let h = hash(&input[pos..]);
+        let candidate = table[h];
+        table[h] = pos;
+
+        let found = candidate != usize::MAX
+            && pos - candidate <= LZ_MAX_OFFSET
+            && input[candidate..candidate + LZ_MIN_MATCH] == input[pos..pos + LZ_MIN_MATCH];
+
+        if !found {
+            pos += 1;
+            continue;
+        }
+
+        let mut len = LZ_MIN_MATCH;
+
+        while pos + len < input.len() && input[candidate + len] == input[pos + len] {
+            len += 1;
+        }
+
+        write_sequence(
+            &mut out,
+            &input[literal_start..pos],
+            Some((pos - candidate, len)),
+        );
+
+        for skipped in pos + 1..(pos + len).min(input.len() + 1 - LZ_MIN_MATCH) {
+            table[hash(&input[skipped..])] = skipped;
+        }
+
+        pos += len;
+        literal_start = pos;
+    }
+
+    if literal_start < input.len() {
+        write_sequence(&mut out, &input[literal_start..], None);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compress_roundtrip() {
+        let mut input = b"loka loka loka n64 ".repeat(40);
+        input.extend((0..2000u32).map(|i| (i * 7 % 251) as u8));
+        input.extend([0; 700]);
+
+        for data in [&input[..], &input[..3], &[][..], &input[..300]] {
+            let compressed = compress(data);
+            let mut out = vec![0; data.len()];
+
+            // Feed it in small pieces like the streaming loader does
+            let mut decoder = n64_types::LzDecoder::new(&mut out);
+            for chunk in compressed.chunks(7) {
+                decoder.feed(chunk).unwrap();
+            }
+            decoder.finish().unwrap();
+
+            assert_eq!(out, data);
+        }
+
+        assert!(compress(&input).len() < input.len() / 2);
+    }
+
+    #[test]
+    fn compress_texture() {
+        // 32x32 rgba5551 sprite, opaque circle on a transparent background
+        let texels = (0..32 * 32).flat_map(|i| {
+            let (x, y) = (i % 32 - 16, i / 32 - 16);
+            let texel: u16 = if x * x + y * y < 12 * 12 { 0xf801 } else { 0 };
+            texel.to_be_bytes()
+        });
+        let texture = texels.collect::<Vec<u8>>();
+
+        let compressed = compress(&texture);
+        let mut out = vec![0; texture.len()];
+
+        let mut decoder = n64_types::LzDecoder::new(&mut out);
+        decoder.feed(&compressed).unwrap();
+        decoder.finish().unwrap();
+
+        assert_eq!(out, texture);
+        assert!(compressed.len() < texture.len() / 4);
+    }
+}
@@ -1,31 +1,53 @@
-use crate::utils::{write_binary_file_if_changed, write_file_if_changed};
+use crate::{
+    archive::{ArchiveBuilder, AssetKind},
+    utils::write_file_if_changed,
+};
 use assert_into::AssertInto;
 use blend::{Blend, Instance};
+use gltf::animation::{util::ReadOutputs, Interpolation, Property};
 use meshopt::{generate_vertex_remap, remap_index_buffer, remap_vertex_buffer};
-use n64_math::{vec2, Vec2, Vec3};
-use std::{env, ffi::OsStr, fs, path::Path};
+use n64_math::{vec2, Mat4, Quat, Vec2, Vec3};
+use std::{env, ffi::OsStr, fs};
 use zerocopy::AsBytes;
 
+const ANIMATION_FPS: f32 = 15.0;
+
 #[rustfmt::skip]
 macro_rules! MODEL_TEMPLATE { () => {
 r##"pub static {name}: StaticModelData = StaticModelData {{
-    verts: include_bytes_align_as!(Vec3, {verts_path:?}),
-    uvs: include_bytes_align_as!(Vec2, {uvs_path:?}),
-    colors: include_bytes_align_as!(u32, {colors_path:?}),
-    indices: include_bytes_align_as!(u8, {indices_path:?}),
+    verts: StaticData::Asset(&{name}_VERTS),
+    uvs: StaticData::Asset(&{name}_UVS),
+    colors: StaticData::Asset(&{name}_COLORS),
+    normals: StaticData::Asset(&{name}_NORMALS),
+    indices: StaticData::Asset(&{name}_INDICES),
     size: const_vec2!([{model_width}_f32, {model_height}_f32]),
+    animations: &[
+{animations}    ],
 }};
 "##
 }; }
 
+#[rustfmt::skip]
+macro_rules! ANIMATION_TEMPLATE { () => {
+r##"        StaticAnimationData {{
+            name: {animation_name:?},
+            fps: {fps}_f32,
+            frame_count: {frame_count},
+            verts: StaticData::Asset(&{animation_ident}_VERTS),
+            normals: StaticData::Asset(&{animation_ident}_NORMALS),
+        }},
+"##
+}; }
+
 #[rustfmt::skip]
 macro_rules! MODELS_TEMPLATE { () => {
 r##"// This file is generated
 
 #![cfg_attr(rustfmt, rustfmt::skip)]
+#![allow(unused_imports)]
 
-use crate::model::StaticModelData;
-use n64::include_bytes_align_as;
+use crate::model::{{StaticAnimationData, StaticModelData}};
+use n64::{{Asset, StaticData}};
 use n64_math::{{Vec2, Vec3, const_vec2}};
 
 {models}"##
@@ -36,8 +58,41 @@ struct Model {
     verts: Vec<[f32; 3]>,
     uvs: Vec<[f32; 2]>,
     colors: Vec<u32>,
+    normals: Vec<[f32; 3]>,
     indices: Vec<u8>,
     size: Vec2,
+    animations: Vec<Animation>,
+}
+
+// Vertex animation baked at ANIMATION_FPS, verts and normals hold frame_count frames after each other
+#[derive(Debug)]
+struct Animation {
+    name: String,
+    frame_count: usize,
+    verts: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+}
+
+fn compute_smooth_normals(verts: &[[f32; 3]], indices: &[u8]) -> Vec<[f32; 3]> {
+    let mut normals = vec![Vec3::ZERO; verts.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        let v0 = Vec3::from(verts[triangle[0] as usize]);
+        let v1 = Vec3::from(verts[triangle[1] as usize]);
+        let v2 = Vec3::from(verts[triangle[2] as usize]);
+
+        // Not normalized so that larger faces contribute more
+        let face_normal = (v1 - v0).cross(v2 - v0);
+
+        for index in triangle {
+            normals[*index as usize] += face_normal;
+        }
+    }
+
+    normals
+        .iter()
+        .map(|n| n.normalize_or_zero().to_array())
+        .collect()
 }
 
 fn parse_model(mesh: Instance) -> Option<Model> {
@@ -139,12 +194,16 @@ fn parse_model(mesh: Instance) -> Option<Model> {
         vert[2] -= offset.z;
     }
 
+    let normals = compute_smooth_normals(&verts, &indices);
+
     Some(Model {
         verts,
         uvs,
         colors,
+        normals,
         indices,
         size: vec2(max_x - min_x, max_y - min_y),
+        animations: Vec::new(),
     })
 }
 
@@ -161,7 +220,281 @@ fn byteswap_u32_slice(data: &[u8]) -> Vec<u8> {
     res
 }
 
-fn parse_gltf_model(mesh: &gltf::Mesh, buffers: &[gltf::buffer::Data]) -> Option<Model> {
+struct Channel {
+    node: usize,
+    property: Property,
+    interpolation: Interpolation,
+    inputs: Vec<f32>,
+    outputs: Vec<Vec<f32>>,
+}
+
+impl Channel {
+    fn sample(&self, time: f32) -> Vec<f32> {
+        let next = self.inputs.iter().position(|input| *input > time);
+
+        let (a, b, t) = match next {
+            Some(0) => (0, 0, 0.0),
+            Some(next) => {
+                let start = self.inputs[next - 1];
+                let end = self.inputs[next];
+                (next - 1, next, (time - start) / (end - start))
+            }
+            None => (self.inputs.len() - 1, self.inputs.len() - 1, 0.0),
+        };
+
+        if self.interpolation == Interpolation::Step {
+            return self.outputs[a].clone();
+        }
+
+        if self.property == Property::Rotation {
+            let a = Quat::from_slice(&self.outputs[a]);
+            let b = Quat::from_slice(&self.outputs[b]);
+            return a.slerp(b, t).normalize().to_array().to_vec();
+        }
+
+        self.outputs[a]
+            .iter()
+            .zip(self.outputs[b].iter())
+            .map(|(a, b)| a + (b - a) * t)
+            .collect()
+    }
+}
+
+fn read_channels(animation: &gltf::Animation, buffers: &[gltf::buffer::Data]) -> Vec<Channel> {
+    let mut channels = Vec::new();
+
+    for channel in animation.channels() {
+        let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+
+        let inputs = reader.read_inputs().unwrap().collect::<Vec<_>>();
+        let interpolation = channel.sampler().interpolation();
+
+        // Cubic spline keys are stored as in tangent, value, out tangent. Only the values are used.
+        let values_per_key = if interpolation == Interpolation::CubicSpline {
+            3
+        } else {
+            1
+        };
+
+        let values = match reader.read_outputs().unwrap() {
+            ReadOutputs::Translations(t) => t.map(|v| v.to_vec()).collect::<Vec<_>>(),
+            ReadOutputs::Rotations(r) => r.into_f32().map(|v| v.to_vec()).collect(),
+            ReadOutputs::Scales(s) => s.map(|v| v.to_vec()).collect(),
+            ReadOutputs::MorphTargetWeights(w) => {
+                let weights = w.into_f32().collect::<Vec<_>>();
+                let targets = weights.len() / (values_per_key * inputs.len());
+                weights.chunks(targets).map(|w| w.to_vec()).collect()
+            }
+        };
+
+        let outputs = values
+            .chunks(values_per_key)
+            .map(|v| v[values_per_key / 2].clone())
+            .collect();
+
+        channels.push(Channel {
+            node: channel.target().node().index(),
+            property: channel.target().property(),
+            interpolation,
+            inputs,
+            outputs,
+        });
+    }
+
+    channels
+}
+
+fn global_transform(node: usize, parents: &[Option<usize>], locals: &[(Vec3, Quat, Vec3)]) -> Mat4 {
+    let (translation, rotation, scale) = locals[node];
+    let local = Mat4::from_scale_rotation_translation(scale, rotation, translation);
+
+    match parents[node] {
+        Some(parent) => global_transform(parent, parents, locals) * local,
+        None => local,
+    }
+}
+
+// Morph targets and skinning are evaluated here so the runtime only has to pick a frame
+fn parse_gltf_animations(
+    document: &gltf::Document,
+    mesh: &gltf::Mesh,
+    primitive: &gltf::Primitive,
+    buffers: &[gltf::buffer::Data],
+    verts: &[[f32; 3]],
+    normals: Option<&[[f32; 3]]>,
+    indices: &[u8],
+    offset: Vec3,
+) -> Vec<Animation> {
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+    let mesh_node = document
+        .nodes()
+        .find(|node| node.mesh().map(|m| m.index()) == Some(mesh.index()));
+
+    let mut parents = vec![None; document.nodes().count()];
+    for node in document.nodes() {
+        for child in node.children() {
+            parents[child.index()] = Some(node.index());
+        }
+    }
+
+    let base_locals = document
+        .nodes()
+        .map(|node| {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            (
+                Vec3::from(translation),
+                Quat::from_slice(&rotation),
+                Vec3::from(scale),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let morph_targets = reader
+        .read_morph_targets()
+        .map(|(positions, normals, _)| {
+            (
+                positions.map(|p| p.collect::<Vec<_>>()),
+                normals.map(|n| n.collect::<Vec<_>>()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let base_weights = mesh_node
+        .as_ref()
+        .and_then(|node| node.weights())
+        .or_else(|| mesh.weights())
+        .map(|w| w.to_vec())
+        .unwrap_or_else(|| vec![0.0; morph_targets.len()]);
+
+    let skin = mesh_node.as_ref().and_then(|node| node.skin()).map(|skin| {
+        let joints = skin.joints().map(|j| j.index()).collect::<Vec<_>>();
+        let inverse_bind_matrices = skin
+            .reader(|buffer| Some(&buffers[buffer.index()]))
+            .read_inverse_bind_matrices()
+            .map(|m| m.map(|m| Mat4::from_cols_array_2d(&m)).collect::<Vec<_>>())
+            .unwrap_or_else(|| vec![Mat4::IDENTITY; joints.len()]);
+        let vertex_joints = reader
+            .read_joints(0)
+            .unwrap()
+            .into_u16()
+            .collect::<Vec<_>>();
+        let vertex_weights = reader
+            .read_weights(0)
+            .unwrap()
+            .into_f32()
+            .collect::<Vec<_>>();
+        (joints, inverse_bind_matrices, vertex_joints, vertex_weights)
+    });
+
+    let mut animations = Vec::new();
+
+    for (index, animation) in document.animations().enumerate() {
+        let channels = read_channels(&animation, buffers);
+
+        let duration = channels
+            .iter()
+            .filter_map(|c| c.inputs.last().copied())
+            .fold(0.0, f32::max);
+
+        let frame_count = ((duration * ANIMATION_FPS).ceil() as usize).max(1);
+
+        let mut frame_verts = Vec::with_capacity(frame_count * verts.len());
+        let mut frame_normals = Vec::with_capacity(frame_count * verts.len());
+
+        for frame in 0..frame_count {
+            let time = frame as f32 / ANIMATION_FPS;
+
+            let mut locals = base_locals.clone();
+            let mut weights = base_weights.clone();
+
+            for channel in channels.iter() {
+                let value = channel.sample(time);
+
+                match channel.property {
+                    Property::Translation => locals[channel.node].0 = Vec3::from_slice(&value),
+                    Property::Rotation => locals[channel.node].1 = Quat::from_slice(&value),
+                    Property::Scale => locals[channel.node].2 = Vec3::from_slice(&value),
+                    Property::MorphTargetWeights => {
+                        if mesh_node.as_ref().map(|n| n.index()) == Some(channel.node) {
+                            weights = value;
+                        }
+                    }
+                }
+            }
+
+            let joint_matrices = skin.as_ref().map(|(joints, inverse_bind_matrices, _, _)| {
+                joints
+                    .iter()
+                    .zip(inverse_bind_matrices.iter())
+                    .map(|(joint, inverse_bind)| {
+                        global_transform(*joint, &parents, &locals) * *inverse_bind
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+            let start = frame_verts.len();
+
+            for (i, vert) in verts.iter().enumerate() {
+                // Verts are already centered, animation is evaluated in the original space
+                let mut pos = Vec3::from(*vert) + offset;
+                let mut normal = Vec3::from(normals.map(|n| n[i]).unwrap_or_default());
+
+                for ((target_positions, target_normals), weight) in
+                    morph_targets.iter().zip(weights.iter())
+                {
+                    if let Some(target_positions) = target_positions {
+                        pos += *weight * Vec3::from(target_positions[i]);
+                    }
+                    if let Some(target_normals) = target_normals {
+                        normal += *weight * Vec3::from(target_normals[i]);
+                    }
+                }
+
+                if let (Some((_, _, vertex_joints, vertex_weights)), Some(joint_matrices)) =
+                    (&skin, &joint_matrices)
+                {
+                    let skin_matrix = vertex_joints[i]
+                        .iter()
+                        .zip(vertex_weights[i].iter())
+                        .fold(Mat4::ZERO, |m, (joint, weight)| {
+                            m + *weight * joint_matrices[*joint as usize]
+                        });
+
+                    pos = skin_matrix.transform_point3(pos);
+                    normal = skin_matrix.transform_vector3(normal);
+                }
+
+                frame_verts.push((pos - offset).to_array());
+                frame_normals.push(normal.normalize_or_zero().to_array());
+            }
+
+            if normals.is_none() {
+                let computed = compute_smooth_normals(&frame_verts[start..], indices);
+                frame_normals.truncate(start);
+                frame_normals.extend(computed);
+            }
+        }
+
+        animations.push(Animation {
+            name: animation
+                .name()
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| format!("animation_{}", index)),
+            frame_count,
+            verts: frame_verts,
+            normals: frame_normals,
+        });
+    }
+
+    animations
+}
+
+fn parse_gltf_model(
+    document: &gltf::Document,
+    mesh: &gltf::Mesh,
+    buffers: &[gltf::buffer::Data],
+) -> Option<Model> {
     if mesh.primitives().count() > 1 {
         panic!("Only one primitive per gltf file is supported");
     }
@@ -183,6 +516,9 @@ fn parse_gltf_model(mesh: &gltf::Mesh, buffers: &[gltf::buffer::Data]) -> Option
                 ((c[0] as u32) << 24) | ((c[1] as u32) << 16) | ((c[2] as u32) << 8) | (c[3] as u32)
             })
             .collect::<Vec<_>>();
+        let normals = reader
+            .read_normals()
+            .map(|normals| normals.collect::<Vec<_>>());
         let indices = reader
             .read_indices()
             .unwrap()
@@ -205,19 +541,34 @@ fn parse_gltf_model(mesh: &gltf::Mesh, buffers: &[gltf::buffer::Data]) -> Option
             vert[2] -= offset.z;
         }
 
+        let animations = parse_gltf_animations(
+            document,
+            mesh,
+            &primitive,
+            buffers,
+            &verts,
+            normals.as_deref(),
+            &indices,
+            offset,
+        );
+
+        let normals = normals.unwrap_or_else(|| compute_smooth_normals(&verts, &indices));
+
         return Some(Model {
             verts,
             uvs,
             colors,
+            normals,
             indices,
             size: Vec2::new(size.max[0] - size.min[0], size.max[1] - size.min[1]),
+            animations,
         });
     }
 
     None
 }
 
-pub(crate) fn parse() {
+pub(crate) fn parse(archive: &mut ArchiveBuilder) {
     let mut models = String::new();
 
     for path in fs::read_dir("models")
@@ -236,10 +587,9 @@ pub(crate) fn parse() {
                     let data = obj.get("data");
 
                     let name = format!("{}", file_name);
-                    let out_base_path = path.canonicalize().unwrap().with_file_name(&name);
 
                     if let Some(model) = parse_model(data) {
-                        output_model(&mut models, &name, &out_base_path, &model);
+                        output_model(archive, &mut models, &name, &model);
                     }
                     break;
                 }
@@ -257,13 +607,12 @@ pub(crate) fn parse() {
 
         if let Some(file_name) = path.file_stem().map(|n| n.to_string_lossy()) {
             let name = format!("{}", file_name);
-            let out_base_path = path.canonicalize().unwrap().with_file_name(&name);
 
             let (gltf, buffers, _) = gltf::import(&path).unwrap();
 
             for mesh in gltf.meshes() {
-                if let Some(model) = parse_gltf_model(&mesh, &buffers) {
-                    output_model(&mut models, &name, &out_base_path, &model);
+                if let Some(model) = parse_gltf_model(&gltf, &mesh, &buffers) {
+                    output_model(archive, &mut models, &name, &model);
                     break;
                 }
             }
@@ -279,26 +628,51 @@ pub(crate) fn parse() {
     .unwrap();
 }
 
-fn output_model(models: &mut String, name: &str, out_base_path: &Path, model: &Model) {
-    let verts_path = out_base_path.with_extension("nvert");
-    let uvs_path = out_base_path.with_extension("nuv");
-    let colors_path = out_base_path.with_extension("ncol");
-    let indices_path = out_base_path.with_extension("nind");
+fn output_model(archive: &mut ArchiveBuilder, models: &mut String, name: &str, model: &Model) {
+    let name = name.to_uppercase();
+
+    let buffers = [
+        ("VERTS", byteswap_u32_slice(model.verts.as_bytes())),
+        ("UVS", byteswap_u32_slice(model.uvs.as_bytes())),
+        ("COLORS", byteswap_u32_slice(model.colors.as_bytes())),
+        ("NORMALS", byteswap_u32_slice(model.normals.as_bytes())),
+        ("INDICES", model.indices.as_bytes().to_vec()),
+    ];
 
-    write_binary_file_if_changed(&verts_path, byteswap_u32_slice(model.verts.as_bytes())).unwrap();
-    write_binary_file_if_changed(&uvs_path, byteswap_u32_slice(model.uvs.as_bytes())).unwrap();
-    write_binary_file_if_changed(&colors_path, byteswap_u32_slice(model.colors.as_bytes()))
-        .unwrap();
-    write_binary_file_if_changed(&indices_path, model.indices.as_bytes()).unwrap();
+    for (suffix, data) in &buffers {
+        models.push_str(&archive.add(AssetKind::Model, &format!("{}_{}", name, suffix), data));
+    }
+
+    let mut animations = String::new();
+
+    for (index, animation) in model.animations.iter().enumerate() {
+        let animation_ident = format!("{}_ANIM_{}", name, index);
+
+        models.push_str(&archive.add(
+            AssetKind::Model,
+            &format!("{}_VERTS", animation_ident),
+            &byteswap_u32_slice(animation.verts.as_bytes()),
+        ));
+        models.push_str(&archive.add(
+            AssetKind::Model,
+            &format!("{}_NORMALS", animation_ident),
+            &byteswap_u32_slice(animation.normals.as_bytes()),
+        ));
+
+        animations.push_str(&format!(
+            ANIMATION_TEMPLATE!(),
+            animation_name = animation.name,
+            fps = ANIMATION_FPS,
+            frame_count = animation.frame_count,
+            animation_ident = animation_ident,
+        ));
+    }
 
     models.push_str(&format!(
         MODEL_TEMPLATE!(),
-        name = name.to_uppercase(),
-        verts_path = verts_path,
-        uvs_path = uvs_path,
-        colors_path = colors_path,
-        indices_path = indices_path,
+        name = name,
         model_width = model.size.x,
         model_height = model.size.y,
+        animations = animations,
     ));
 }
@@ -0,0 +1,69 @@
+use crate::{
+    archive::{ArchiveBuilder, AssetKind},
+    utils::write_file_if_changed,
+};
+use std::{env, ffi::OsStr, fs};
+
+#[rustfmt::skip]
+macro_rules! REPLAY_TEMPLATE { () => {
+r##"    ({name:?}, StaticData::Asset(&{ident})),
+"##
+}; }
+
+#[rustfmt::skip]
+macro_rules! REPLAYS_TEMPLATE { () => {
+r##"// This file is generated
+
+#![cfg_attr(rustfmt, rustfmt::skip)]
+
+use n64::StaticData;
+
+// Unused when there are no replays
+#[allow(unused_imports)]
+use n64::Asset;
+
+{assets}
+pub static REPLAYS: &[(&str, StaticData)] = &[
+{replays}];
+
+pub fn find(name: &str) -> Option<StaticData> {{
+    REPLAYS.iter().find(|(n, _)| *n == name).map(|(_, data)| *data)
+}}
+"##
+}; }
+
+// Replays recorded on the emulator are put in the asset archive so the N64 can play them back
+pub(crate) fn parse(archive: &mut ArchiveBuilder) {
+    let mut assets = String::new();
+    let mut replays = String::new();
+
+    let mut paths = fs::read_dir("replays")
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension() == Some(OsStr::new("lrec")))
+        .collect::<Vec<_>>();
+
+    paths.sort();
+
+    for path in paths {
+        println!("rerun-if-changed={}", path.to_string_lossy());
+
+        if let Some(name) = path.file_stem().map(|n| n.to_string_lossy()) {
+            let ident = format!("{}_REPLAY", name.to_uppercase());
+            let data = fs::read(&path).unwrap();
+
+            assets.push_str(&archive.add(AssetKind::Replay, &ident, &data));
+            replays.push_str(&format!(REPLAY_TEMPLATE!(), name = name, ident = ident));
+        }
+    }
+
+    let replays = format!(REPLAYS_TEMPLATE!(), assets = assets, replays = replays);
+
+    write_file_if_changed(
+        env::current_dir().unwrap().join("src").join("replays.rs"),
+        replays,
+    )
+    .unwrap();
+}
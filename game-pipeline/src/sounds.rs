@@ -1,4 +1,7 @@
-use crate::utils::{write_binary_file_if_changed, write_file_if_changed};
+use crate::{
+    archive::{ArchiveBuilder, AssetKind},
+    utils::write_file_if_changed,
+};
 use itertools::Itertools;
 use std::{env, ffi::OsStr, fs, path::Path};
 use zerocopy::AsBytes;
@@ -38,7 +41,7 @@ fn load_wav(path: impl AsRef<Path>) -> Vec<i16> {
 
 #[rustfmt::skip]
 macro_rules! SOUND_TEMPLATE { () => {
-r##"pub static {name}: StaticSoundData = StaticSoundData {{ data: include_bytes_align_as!(i16, {path:?}) }};
+r##"{asset}pub static {name}: StaticSoundData = StaticSoundData {{ data: StaticData::Asset(&{name}_DATA) }};
 "##
 }; }
 
@@ -49,12 +52,12 @@ r##"// This file is generated
 #![cfg_attr(rustfmt, rustfmt::skip)]
 
 use crate::sound::StaticSoundData;
-use n64::include_bytes_align_as;
+use n64::{Asset, StaticData};
 
 {sounds}"##
 }; }
 
-pub(crate) fn parse() {
+pub(crate) fn parse(archive: &mut ArchiveBuilder) {
     let mut sounds = String::new();
 
     for path in fs::read_dir("sounds")
@@ -64,16 +67,12 @@ pub(crate) fn parse() {
         .filter(|path| path.extension() == Some(OsStr::new("wav")))
     {
         if let Some(name) = path.file_stem().map(|n| n.to_string_lossy()) {
-            let out_path = path.canonicalize().unwrap().with_extension("nsnd");
+            let name = name.to_uppercase();
             let wav = load_wav(&path);
 
-            write_binary_file_if_changed(&out_path, wav.as_bytes()).unwrap();
+            let asset = archive.add(AssetKind::Sound, &format!("{}_DATA", name), wav.as_bytes());
 
-            sounds.push_str(&format!(
-                SOUND_TEMPLATE!(),
-                name = name.to_uppercase(),
-                path = out_path,
-            ));
+            sounds.push_str(&format!(SOUND_TEMPLATE!(), asset = asset, name = name));
         }
     }
 
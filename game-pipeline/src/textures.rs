@@ -1,9 +1,13 @@
-use crate::{image::load_png, utils::write_binary_file_if_changed, utils::write_file_if_changed};
+use crate::{
+    archive::{ArchiveBuilder, AssetKind},
+    image::load_png,
+    utils::write_file_if_changed,
+};
 use std::{env, ffi::OsStr, fs};
 
 #[rustfmt::skip]
 macro_rules! TEXTURE_TEMPLATE { () => {
-r##"pub static {name}: StaticTexture = StaticTexture::from_static({width}, {height}, include_bytes_align_as!(TextureAlignment, {path:?}));
+r##"{asset}pub static {name}: StaticTexture = StaticTexture::from_asset({width}, {height}, &{name}_DATA);
 "##
 }; }
 
@@ -12,34 +16,34 @@ macro_rules! TEXTURES_TEMPLATE { () => {
 r##"// This file is generated
 
 #![cfg_attr(rustfmt, rustfmt::skip)]
+#![allow(unused_imports)]
 
-use n64::gfx::{{StaticTexture, TextureAlignment}};
-use n64::include_bytes_align_as;
+use n64::{{gfx::StaticTexture, Asset}};
 
 {textures}"##
 }; }
 
-pub(crate) fn parse() {
+pub(crate) fn parse(archive: &mut ArchiveBuilder) {
     let mut textures = String::new();
 
+    // Most textures are packed into atlases so the directory is optional
     for path in fs::read_dir("textures")
-        .unwrap()
+        .into_iter()
+        .flatten()
         .filter_map(|e| e.ok())
         .map(|e| e.path())
         .filter(|path| path.extension() == Some(OsStr::new("png")))
     {
-        if let Some(name) = path.file_stem().map(|n| n.to_string_lossy()) {
-            let out_path = path.canonicalize().unwrap().with_extension("ntex");
+        if let Some(name) = path.file_stem().map(|n| n.to_string_lossy().to_uppercase()) {
             let image = load_png(path.as_path(), false, None).unwrap();
-
-            write_binary_file_if_changed(&out_path, &image.data).unwrap();
+            let asset = archive.add(AssetKind::Texture, &format!("{}_DATA", name), &image.data);
 
             textures.push_str(&format!(
                 TEXTURE_TEMPLATE!(),
-                name = name.to_uppercase(),
+                asset = asset,
+                name = name,
                 width = image.width,
                 height = image.height,
-                path = out_path
             ));
         }
     }
@@ -1,11 +1,16 @@
-use n64::{Controllers, VideoMode};
+use n64::{Controller, VideoMode};
 use n64_math::Vec2;
 
 pub const SPEED: f32 = 16.0 / 240.0;
 
+#[derive(Copy, Clone)]
 pub struct Camera {
     pub pos: Vec2,
+    prev_pos: Vec2,
     pub speed: Vec2,
+    // How far between the last two simulation steps this camera renders, entities are drawn at the
+    // same point with Movable::interpolated_pos
+    pub alpha: f32,
     dpad_pressed_last_frame: bool,
     debug_camera: bool,
 }
@@ -14,13 +19,26 @@ impl Camera {
     pub fn new(start_pos: Vec2) -> Self {
         Self {
             pos: start_pos,
+            prev_pos: start_pos,
             speed: Vec2::new(0.0, SPEED),
+            alpha: 1.0,
             dpad_pressed_last_frame: false,
             debug_camera: false,
         }
     }
 
-    pub fn update(&mut self, controllers: &Controllers, dt: f32, video_mode: &VideoMode) {
+    // Camera to render with, between the last two simulated positions
+    pub fn interpolated(&self, alpha: f32) -> Camera {
+        Camera {
+            pos: self.prev_pos.lerp(self.pos, alpha),
+            alpha,
+            ..*self
+        }
+    }
+
+    pub fn update(&mut self, controller: &Controller, dt: f32, video_mode: &VideoMode) {
+        self.prev_pos = self.pos;
+
         if !self.debug_camera {
             self.pos.y -= self.speed.y * dt;
 
@@ -31,45 +49,45 @@ impl Camera {
             }
         }
 
-        if controllers.c_up() {
+        if controller.c_up() {
             self.debug_camera = true;
             self.pos.y -= 10.0 / video_mode.height() as f32;
         }
 
-        if controllers.c_down() {
+        if controller.c_down() {
             self.debug_camera = true;
             self.pos.y += 10.0 / video_mode.height() as f32;
         }
 
-        if controllers.c_left() {
+        if controller.c_left() {
             self.debug_camera = true;
             self.pos.x -= 10.0 / video_mode.width() as f32;
         }
 
-        if controllers.c_right() {
+        if controller.c_right() {
             self.debug_camera = true;
             self.pos.x += 10.0 / video_mode.width() as f32;
         }
 
-        self.dpad_pressed_last_frame = if controllers.up() {
+        self.dpad_pressed_last_frame = if controller.up() {
             self.debug_camera = true;
             if !self.dpad_pressed_last_frame {
                 self.pos.y -= 1.0 / video_mode.height() as f32;
             }
             true
-        } else if controllers.down() {
+        } else if controller.down() {
             self.debug_camera = true;
             if !self.dpad_pressed_last_frame {
                 self.pos.y += 1.0 / video_mode.height() as f32;
             }
             true
-        } else if controllers.left() {
+        } else if controller.left() {
             self.debug_camera = true;
             if !self.dpad_pressed_last_frame {
                 self.pos.x -= 1.0 / video_mode.width() as f32;
             }
             true
-        } else if controllers.right() {
+        } else if controller.right() {
             self.debug_camera = true;
             if !self.dpad_pressed_last_frame {
                 self.pos.x += 1.0 / video_mode.width() as f32;
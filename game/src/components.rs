@@ -1,3 +1,4 @@
+pub mod animation_player;
 pub mod box_drawable;
 pub mod diver_ai;
 pub mod enemy;
@@ -6,6 +7,7 @@ pub mod keep_on_screen;
 pub mod mesh_drawable;
 pub mod missile;
 pub mod movable;
+pub mod particle_emitter;
 pub mod pickup;
 pub mod player;
 pub mod print_position;
@@ -0,0 +1,107 @@
+use crate::{
+    ecs::{query::query, world::World},
+    model::AnimationData,
+};
+use alloc::vec::Vec;
+use game_derive::SparseComponent;
+
+// Plays vertex animations baked by the pipeline, mesh_drawable picks the current frame
+#[derive(SparseComponent)]
+pub struct AnimationPlayer {
+    pub animations: Vec<AnimationData<'static>>,
+    pub animation: usize,
+    pub time: f32,
+    pub speed: f32,
+    pub looping: bool,
+}
+
+impl AnimationPlayer {
+    pub fn new(animations: Vec<AnimationData<'static>>) -> Self {
+        Self {
+            animations,
+            animation: 0,
+            time: 0.0,
+            speed: 1.0,
+            looping: true,
+        }
+    }
+
+    pub fn play(&mut self, animation: usize, looping: bool) {
+        self.animation = animation;
+        self.time = 0.0;
+        self.looping = looping;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match self.current() {
+            Some(animation) => !self.looping && self.time >= animation.duration(),
+            None => true,
+        }
+    }
+
+    pub fn current(&self) -> Option<&AnimationData<'static>> {
+        self.animations.get(self.animation)
+    }
+
+    pub fn current_frame(&self) -> Option<(&[[f32; 3]], &[[f32; 3]])> {
+        let animation = self.current()?;
+
+        let frame = ((self.time * animation.fps) as usize).min(animation.frame_count - 1);
+
+        Some((animation.frame_verts(frame), animation.frame_normals(frame)))
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        let duration = match self.current() {
+            Some(animation) => animation.duration(),
+            None => return,
+        };
+
+        self.time += self.speed * dt;
+
+        if self.time >= duration {
+            self.time = if self.looping {
+                self.time % duration
+            } else {
+                duration
+            };
+        }
+    }
+}
+
+pub fn update(world: &mut World, dt: f32) {
+    n64::scope!("animation_player::update");
+
+    for (_e, player) in query::<(AnimationPlayer,)>(&mut world.components) {
+        player.advance(dt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{borrow::Cow, vec};
+
+    #[test]
+    fn advancing_changes_frame() {
+        let animation = AnimationData {
+            name: "move",
+            fps: 10.0,
+            frame_count: 2,
+            verts: Cow::Owned(vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]]),
+            normals: Cow::Owned(vec![[0.0, 0.0, 1.0], [0.0, 1.0, 0.0]]),
+        };
+
+        let mut player = AnimationPlayer::new(vec![animation]);
+
+        assert_eq!(player.current_frame().unwrap().0, [[0.0, 0.0, 0.0]]);
+
+        player.advance(0.15);
+        assert_eq!(player.current_frame().unwrap().0, [[1.0, 0.0, 0.0]]);
+        assert_eq!(player.current_frame().unwrap().1, [[0.0, 1.0, 0.0]]);
+
+        // Loops back to the first frame
+        player.advance(0.1);
+        assert_eq!(player.current_frame().unwrap().0, [[0.0, 0.0, 0.0]]);
+    }
+}
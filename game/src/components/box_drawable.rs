@@ -23,8 +23,10 @@ pub fn draw(world: &mut World, cb: &mut CommandBuffer, video_mode: VideoMode, ca
     {
         let half_size = size.size / 2.0;
 
-        let upper_left = movable.pos - half_size;
-        let lower_right = movable.pos + half_size;
+        let pos = movable.interpolated_pos(camera.alpha);
+
+        let upper_left = pos - half_size;
+        let lower_right = pos + half_size;
 
         let screen_size = Vec2::new(video_mode.width() as f32, video_mode.height() as f32);
 
@@ -1,8 +1,10 @@
 use super::{
+    animation_player::AnimationPlayer,
     diver_ai::DiverAi,
     health::{self, Health},
     mesh_drawable::MeshDrawable,
-    movable::Movable,
+    movable::{self, Movable},
+    particle_emitter::{spawn_particle_effect, EXPLOSION_EFFECT},
     player::{self, Player},
     remove_when_below::RemoveWhenBelow,
     shadow::Shadow,
@@ -15,7 +17,7 @@ use super::{
 };
 use crate::{
     ecs::{entity::EntitySystem, storage::Storage, world::World},
-    model::ModelData,
+    model::AnimatedModelData,
     sound_mixer::SoundMixer,
     sounds::EXPLOSION_0,
 };
@@ -30,10 +32,7 @@ pub struct Enemy {}
 pub fn add_enemy_spawner(entities: &mut EntitySystem, pos: Vec2, spawner_data: SpawnerData) {
     entities
         .spawn()
-        .add(Movable {
-            pos,
-            speed: Vec2::ZERO,
-        })
+        .add(Movable::new(pos, Vec2::ZERO))
         .add(spawner_data.size())
         .add(Spawner { data: spawner_data });
 }
@@ -107,16 +106,23 @@ pub fn spawn_boss(
     entities: &mut EntitySystem,
     movable: Movable,
     size: Size,
-    model: ModelData<'static>,
+    model: AnimatedModelData<'static>,
 ) {
+    let animation_player = if model.animations.is_empty() {
+        None
+    } else {
+        Some(AnimationPlayer::new(model.animations))
+    };
+
     entities
         .spawn()
         .add(movable)
         .add(size)
         .add(MeshDrawable {
-            model,
+            model: model.model,
             rot: Quat::IDENTITY,
         })
+        .add_optional(animation_player)
         .add(Shadow)
         .add(Health {
             health: 10000,
@@ -135,7 +141,10 @@ pub fn spawn_boss(
         .add(RemoveWhenBelow);
 }
 
-pub fn update(world: &mut World, sound_mixer: &mut SoundMixer) {
+// Returns true if an enemy exploded
+pub fn update(world: &mut World, sound_mixer: &mut SoundMixer) -> bool {
+    let mut exploded = false;
+
     let (enemy, movable, health, size, player, weapon) =
         world
             .components
@@ -143,9 +152,15 @@ pub fn update(world: &mut World, sound_mixer: &mut SoundMixer) {
 
     for entity in enemy.entities() {
         if !health::is_alive(health, *entity) {
-            sound_mixer.play_sound(EXPLOSION_0.as_sound_data());
+            if let Ok(sound) = EXPLOSION_0.as_sound_data() {
+                sound_mixer.play_sound(sound);
+            }
+            if let Some(pos) = movable::pos(movable, *entity) {
+                spawn_particle_effect(&mut world.entities, pos, &EXPLOSION_EFFECT);
+            }
             player::add_score(player, 1000);
             world.entities.despawn(*entity);
+            exploded = true;
         }
 
         weapon::fire(
@@ -160,4 +175,6 @@ pub fn update(world: &mut World, sound_mixer: &mut SoundMixer) {
             WeaponTarget::Player,
         );
     }
+
+    exploded
 }
@@ -1,4 +1,4 @@
-use super::{health::Health, movable::Movable};
+use super::{animation_player::AnimationPlayer, health::Health, movable::Movable};
 use crate::{
     camera::Camera,
     ecs::{query::query, world::World},
@@ -11,11 +11,11 @@ use n64::{
         color_combiner_mode::{
             AAlphaSrc, ASrc, BAlphaSrc, BSrc, CAlphaSrc, CSrc, ColorCombinerMode, DAlphaSrc, DSrc,
         },
-        CommandBuffer, Pipeline,
+        CommandBuffer, CullMode, DirectionalLight, Lights, Pipeline,
     },
     VideoMode,
 };
-use n64_math::{vec3, Mat4, Quat};
+use n64_math::{const_vec3, vec3, Mat4, Quat};
 
 #[derive(SparseComponent)]
 pub struct MeshDrawable {
@@ -27,9 +27,23 @@ static MESH_PIPELINE: Pipeline = Pipeline {
     color_combiner_mode: ColorCombinerMode::simple(ASrc::Zero, BSrc::Zero, CSrc::Zero, DSrc::Shade),
     z_compare: true,
     z_update: true,
+    cull_mode: CullMode::Back,
     ..Pipeline::default()
 };
 
+static LIGHTS: Lights = Lights {
+    ambient_color: 0x606060ff,
+    directional: [
+        Some(DirectionalLight {
+            direction: const_vec3!([0.5, 0.5, -1.0]),
+            color: 0xc0c0c0ff,
+        }),
+        None,
+        None,
+        None,
+    ],
+};
+
 pub fn draw(world: &mut World, cb: &mut CommandBuffer, video_mode: VideoMode, camera: &Camera) {
     n64::scope!("mesh_drawable::draw");
 
@@ -54,8 +68,14 @@ pub fn draw(world: &mut World, cb: &mut CommandBuffer, video_mode: VideoMode, ca
 
     let proj = post_transform * proj * pre_transform;
 
-    for (_e, mesh_drawable, movable, health) in
-        query::<(MeshDrawable, Movable, Option<Health>)>(&mut world.components)
+    cb.set_lights(&LIGHTS);
+
+    for (_e, mesh_drawable, movable, health, animation_player) in query::<(
+        MeshDrawable,
+        Movable,
+        Option<Health>,
+        Option<AnimationPlayer>,
+    )>(&mut world.components)
     {
         let mut pipeline = MESH_PIPELINE;
 
@@ -77,22 +97,24 @@ pub fn draw(world: &mut World, cb: &mut CommandBuffer, video_mode: VideoMode, ca
 
         cb.set_pipeline(&pipeline);
 
-        let transform = proj
-            * Mat4::from_rotation_translation(
-                mesh_drawable.rot,
-                vec3(
-                    movable.pos.x - camera.pos.x,
-                    movable.pos.y - camera.pos.y,
-                    -1.0,
-                ),
-            );
-
-        cb.add_mesh_indexed(
-            &mesh_drawable.model.verts,
+        let pos = movable.interpolated_pos(camera.alpha) - camera.pos;
+
+        let transform =
+            proj * Mat4::from_rotation_translation(mesh_drawable.rot, vec3(pos.x, pos.y, -1.0));
+
+        let (verts, normals) = animation_player
+            .and_then(|player| player.current_frame())
+            .unwrap_or((&*mesh_drawable.model.verts, &*mesh_drawable.model.normals));
+
+        cb.add_lit_mesh_indexed(
+            verts,
             &mesh_drawable.model.uvs,
             &mesh_drawable.model.colors,
+            normals,
             &mesh_drawable.model.indices,
             &transform.to_cols_array_2d(),
+            &Mat4::from_quat(mesh_drawable.rot).to_cols_array_2d(),
+            Some(mesh_drawable.model.size),
         );
     }
 }
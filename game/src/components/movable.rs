@@ -8,12 +8,37 @@ use n64_math::Vec2;
 pub struct Movable {
     pub pos: Vec2,
     pub speed: Vec2,
+    // Position before the last simulation step, used to interpolate rendering
+    pub prev_pos: Vec2,
+}
+
+impl Movable {
+    pub const fn new(pos: Vec2, speed: Vec2) -> Self {
+        Self {
+            pos,
+            speed,
+            prev_pos: pos,
+        }
+    }
+
+    // Position to draw at, between the last two simulated positions like the render camera
+    pub fn interpolated_pos(&self, alpha: f32) -> Vec2 {
+        self.prev_pos.lerp(self.pos, alpha)
+    }
 }
 
 pub fn pos(storage: &<Movable as Component>::Storage, entity: Entity) -> Option<Vec2> {
     storage.lookup(entity).map(|c| c.pos)
 }
 
+// Called before every simulation step, anything that moves an entity during the step is
+// interpolated from here
+pub fn store_prev_pos(world: &mut World) {
+    for (_e, movable) in query::<(Movable,)>(&mut world.components) {
+        movable.prev_pos = movable.pos;
+    }
+}
+
 pub fn simulate(world: &mut World, dt: f32) {
     for (_e, movable) in query::<(Movable,)>(&mut world.components) {
         movable.pos += dt * movable.speed;
@@ -0,0 +1,281 @@
+use super::movable::Movable;
+use crate::{
+    atlases::{PARTICLES_ATLAS, PARTICLE_SOFT},
+    camera::Camera,
+    ecs::{entity::EntitySystem, query::query, world::World},
+};
+use alloc::vec::Vec;
+use core::f32::consts::PI;
+use game_derive::SparseComponent;
+use n64::{
+    gfx::{
+        color_combiner_mode::{
+            AAlphaSrc, ASrc, BAlphaSrc, BSrc, CAlphaSrc, CSrc, ColorCombinerMode, DAlphaSrc, DSrc,
+        },
+        CommandBuffer, Pipeline, Sprite,
+    },
+    VideoMode,
+};
+use n64_math::{random_f32, vec2, Vec2};
+
+// Every emitter owns a fixed pool of particles and the total number of live particles is capped
+pub const MAX_PARTICLES_PER_EMITTER: usize = 32;
+pub const MAX_PARTICLES: usize = 256;
+
+static PARTICLE_PIPELINE: Pipeline = Pipeline {
+    color_combiner_mode: ColorCombinerMode::one(
+        ASrc::Texel,
+        BSrc::Zero,
+        CSrc::Primitive,
+        DSrc::Zero,
+        AAlphaSrc::TexelAlpha,
+        BAlphaSrc::Zero,
+        CAlphaSrc::PrimitiveAlpha,
+        DAlphaSrc::Zero,
+    ),
+    blend: true,
+    ..Pipeline::default()
+};
+
+pub struct ParticleEffect {
+    // Particles emitted when the emitter is spawned
+    pub burst: u32,
+    // Particles per second until duration has passed
+    pub rate: f32,
+    pub duration: f32,
+    pub lifetime_min: f32,
+    pub lifetime_max: f32,
+    // Radians, 0 is up on screen. Spread is the full angle of the cone around direction
+    pub direction: f32,
+    pub spread: f32,
+    pub speed_min: f32,
+    pub speed_max: f32,
+    pub drag: f32,
+    pub gravity: Vec2,
+    // Interpolated linearly over the lifetime of each particle
+    pub color_start: u32,
+    pub color_end: u32,
+    pub size_start: f32,
+    pub size_end: f32,
+}
+
+pub static EXPLOSION_EFFECT: ParticleEffect = ParticleEffect {
+    burst: 24,
+    rate: 0.0,
+    duration: 0.0,
+    lifetime_min: 0.3,
+    lifetime_max: 0.7,
+    direction: 0.0,
+    spread: 2.0 * PI,
+    speed_min: 0.05,
+    speed_max: 0.25,
+    drag: 3.0,
+    gravity: Vec2::ZERO,
+    color_start: 0xffe080ff,
+    color_end: 0x80200000,
+    size_start: 0.03,
+    size_end: 0.06,
+};
+
+pub static HIT_EFFECT: ParticleEffect = ParticleEffect {
+    burst: 6,
+    rate: 0.0,
+    duration: 0.0,
+    lifetime_min: 0.1,
+    lifetime_max: 0.25,
+    direction: 0.0,
+    spread: 2.0 * PI,
+    speed_min: 0.1,
+    speed_max: 0.3,
+    drag: 6.0,
+    gravity: Vec2::ZERO,
+    color_start: 0xffffc0ff,
+    color_end: 0xff800000,
+    size_start: 0.015,
+    size_end: 0.005,
+};
+
+#[derive(Copy, Clone, Default)]
+struct Particle {
+    pos: Vec2,
+    prev_pos: Vec2,
+    speed: Vec2,
+    age: f32,
+    lifetime: f32,
+}
+
+#[derive(SparseComponent)]
+pub struct ParticleEmitter {
+    effect: &'static ParticleEffect,
+    time: f32,
+    // Fraction of a particle left over from the rate, carried to the next update
+    emitted: f32,
+    burst_emitted: bool,
+    particles: [Particle; MAX_PARTICLES_PER_EMITTER],
+    particle_count: usize,
+}
+
+impl ParticleEmitter {
+    pub fn new(effect: &'static ParticleEffect) -> Self {
+        Self {
+            effect,
+            time: 0.0,
+            emitted: 0.0,
+            burst_emitted: false,
+            particles: [Particle::default(); MAX_PARTICLES_PER_EMITTER],
+            particle_count: 0,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.time > self.effect.duration && self.particle_count == 0
+    }
+
+    fn emit(&mut self, pos: Vec2, budget: &mut usize) {
+        if self.particle_count >= MAX_PARTICLES_PER_EMITTER || *budget == 0 {
+            return;
+        }
+
+        let effect = self.effect;
+        let random_range = |min: f32, max: f32| min + (max - min) * random_f32();
+
+        let angle = effect.direction + effect.spread * (random_f32() - 0.5);
+        let speed = random_range(effect.speed_min, effect.speed_max);
+
+        self.particles[self.particle_count] = Particle {
+            pos,
+            prev_pos: pos,
+            speed: speed * vec2(libm::sinf(angle), -libm::cosf(angle)),
+            age: 0.0,
+            lifetime: random_range(effect.lifetime_min, effect.lifetime_max),
+        };
+
+        self.particle_count += 1;
+        *budget -= 1;
+    }
+}
+
+fn lerp_color(start: u32, end: u32, t: f32) -> u32 {
+    let mut color = 0;
+
+    for shift in [24, 16, 8, 0] {
+        let a = ((start >> shift) & 0xff) as f32;
+        let b = ((end >> shift) & 0xff) as f32;
+        color |= ((a + (b - a) * t) as u32 & 0xff) << shift;
+    }
+
+    color
+}
+
+pub fn spawn_particle_effect(
+    entities: &mut EntitySystem,
+    pos: Vec2,
+    effect: &'static ParticleEffect,
+) {
+    entities
+        .spawn()
+        .add(Movable::new(pos, Vec2::ZERO))
+        .add(ParticleEmitter::new(effect));
+}
+
+pub fn update(world: &mut World, dt: f32) {
+    n64::scope!("particle_emitter::update");
+
+    let mut live_particles = 0;
+
+    for (_e, emitter) in query::<(ParticleEmitter,)>(&mut world.components) {
+        let mut i = 0;
+
+        while i < emitter.particle_count {
+            let particle = &mut emitter.particles[i];
+
+            particle.age += dt;
+
+            if particle.age >= particle.lifetime {
+                emitter.particle_count -= 1;
+                emitter.particles[i] = emitter.particles[emitter.particle_count];
+                continue;
+            }
+
+            particle.speed += dt * emitter.effect.gravity;
+            particle.speed *= libm::fmaxf(0.0, 1.0 - emitter.effect.drag * dt);
+            particle.prev_pos = particle.pos;
+            particle.pos += dt * particle.speed;
+
+            i += 1;
+        }
+
+        live_particles += emitter.particle_count;
+    }
+
+    let mut budget = MAX_PARTICLES.saturating_sub(live_particles);
+
+    for (e, emitter, movable) in query::<(ParticleEmitter, Movable)>(&mut world.components) {
+        if !emitter.burst_emitted {
+            for _ in 0..emitter.effect.burst {
+                emitter.emit(movable.pos, &mut budget);
+            }
+
+            emitter.burst_emitted = true;
+        }
+
+        emitter.time += dt;
+
+        if emitter.time <= emitter.effect.duration {
+            emitter.emitted += dt * emitter.effect.rate;
+
+            while emitter.emitted >= 1.0 {
+                emitter.emit(movable.pos, &mut budget);
+                emitter.emitted -= 1.0;
+            }
+        }
+
+        if emitter.is_finished() {
+            world.entities.despawn(e);
+        }
+    }
+}
+
+pub fn draw(world: &mut World, cb: &mut CommandBuffer, video_mode: VideoMode, camera: &Camera) {
+    n64::scope!("particle_emitter::draw");
+
+    let screen_size = vec2(video_mode.width() as f32, video_mode.height() as f32);
+
+    let atlas_size = vec2(PARTICLES_ATLAS.width as f32, PARTICLES_ATLAS.height as f32);
+    let uv_upper_left = vec2(PARTICLE_SOFT.x as f32, PARTICLE_SOFT.y as f32) / atlas_size;
+    let uv_lower_right =
+        uv_upper_left + vec2(PARTICLE_SOFT.width as f32, PARTICLE_SOFT.height as f32) / atlas_size;
+
+    let mut sprites = Vec::with_capacity(MAX_PARTICLES_PER_EMITTER);
+
+    cb.set_pipeline(&PARTICLE_PIPELINE.with_texture(PARTICLES_ATLAS.as_texture().ok()));
+
+    for (_e, emitter) in query::<(ParticleEmitter,)>(&mut world.components) {
+        let effect = emitter.effect;
+
+        sprites.clear();
+        sprites.extend(
+            emitter.particles[..emitter.particle_count]
+                .iter()
+                .map(|particle| {
+                    let t = particle.age / particle.lifetime;
+                    let size = effect.size_start + (effect.size_end - effect.size_start) * t;
+
+                    // Size is relative to the screen width to keep particles square
+                    Sprite::new(
+                        (particle.prev_pos.lerp(particle.pos, camera.alpha) - camera.pos)
+                            * screen_size,
+                        Vec2::splat(size * screen_size.x),
+                    )
+                    .with_uv(uv_upper_left, uv_lower_right)
+                    .with_tint(Some(lerp_color(
+                        effect.color_start,
+                        effect.color_end,
+                        t,
+                    )))
+                }),
+        );
+
+        cb.add_sprite_batch(&sprites);
+    }
+}
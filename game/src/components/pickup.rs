@@ -27,17 +27,19 @@ pub struct Pickup;
 pub fn spawn_pickup(entities: &mut EntitySystem, start_pos: Vec2) -> Entity {
     entities
         .spawn()
-        .add(Movable {
-            pos: start_pos,
-            speed: Vec2::new(0.0, 0.0),
-        })
+        .add(Movable::new(start_pos, Vec2::ZERO))
         .add(Size {
             size: WEAPON_PICKUP.size,
         })
-        .add(MeshDrawable {
-            model: WEAPON_PICKUP.as_model_data(),
-            rot: Quat::IDENTITY,
-        })
+        .add_optional(
+            WEAPON_PICKUP
+                .as_model_data()
+                .ok()
+                .map(|model| MeshDrawable {
+                    model,
+                    rot: Quat::IDENTITY,
+                }),
+        )
         .add(Pickup)
         .add(RemoveWhenBelow)
         .entity()
@@ -69,7 +71,9 @@ pub fn update(world: &mut World, sound_mixer: &mut SoundMixer, camera: &Camera)
                     let player_bb = Aabb2::from_center_size(player_movable.pos, player_size.size);
 
                     if pickup_bb.collides(&player_bb) {
-                        sound_mixer.play_sound(PICKUP_1.as_sound_data());
+                        if let Ok(sound) = PICKUP_1.as_sound_data() {
+                            sound_mixer.play_sound(sound);
+                        }
                         let weapon_index = random_u32() % WeaponType::COUNT as u32;
                         player_weapon.last_shoot_time = i64::MIN / 2;
                         player_weapon.weapon_type =
@@ -23,7 +23,7 @@ use crate::{
 };
 use core::f32::consts::PI;
 use game_derive::SparseComponent;
-use n64::{gfx::CommandBuffer, Controllers, VideoMode};
+use n64::{gfx::CommandBuffer, Controller, VideoMode};
 use n64_math::{const_vec2, vec2, Quat, Vec2, Vec3};
 
 const PLAYER_START_POS: Vec2 = const_vec2!([0.5, 0.8]);
@@ -37,15 +37,12 @@ pub struct Player {
 pub fn spawn_player(entities: &mut EntitySystem, start_pos: Vec2) -> Entity {
     entities
         .spawn()
-        .add(Movable {
-            pos: start_pos + PLAYER_START_POS,
-            speed: Vec2::new(0.0, 0.0),
-        })
+        .add(Movable::new(start_pos + PLAYER_START_POS, Vec2::ZERO))
         .add(Size { size: SHIP_3.size })
-        .add(MeshDrawable {
-            model: SHIP_3.as_model_data(),
+        .add_optional(SHIP_3.as_model_data().ok().map(|model| MeshDrawable {
+            model,
             rot: Quat::IDENTITY,
-        })
+        }))
         .add(Shadow)
         .add(Health {
             health: 10000,
@@ -81,7 +78,7 @@ pub fn draw_player_weapon(world: &mut World, cb: &mut CommandBuffer, video_mode:
 
 pub fn update(
     world: &mut World,
-    controllers: &Controllers,
+    controller: &Controller,
     sound_mixer: &mut SoundMixer,
     camera: &Camera,
 ) {
@@ -91,8 +88,8 @@ pub fn update(
             .get::<(Player, Movable, Size, MeshDrawable, Weapon, Enemy)>();
 
     for entity in player.entities() {
-        let controller_x = controllers.x();
-        let controller_y = controllers.y();
+        let controller_x = controller.x();
+        let controller_y = controller.y();
 
         let mut controller_dir = Vec2::new(0.0, 0.0);
 
@@ -118,7 +115,7 @@ pub fn update(
             m.speed = SHIP_SPEED * controller_dir - camera.speed;
         }
 
-        if controllers.z() {
+        if controller.z() {
             weapon::fire(
                 &mut world.entities,
                 *entity,
@@ -2,6 +2,7 @@ use super::{
     enemy::Enemy,
     health::{self, Health},
     movable::Movable,
+    particle_emitter::{spawn_particle_effect, HIT_EFFECT},
     player::Player,
     size::Size,
     weapon::WeaponTarget,
@@ -53,7 +54,10 @@ pub fn update(world: &mut World, sound_mixer: &mut SoundMixer, camera: &Camera,
                         let enemy_bb = Aabb2::from_center_size(m2.pos, s2.size);
 
                         if projectile_bb.collides(&enemy_bb) {
-                            sound_mixer.play_sound(HIT_1.as_sound_data());
+                            if let Ok(sound) = HIT_1.as_sound_data() {
+                                sound_mixer.play_sound(sound);
+                            }
+                            spawn_particle_effect(&mut world.entities, m.pos, &HIT_EFFECT);
                             health::damage(health, *enemy_entity, p1.damage);
                             delete = true;
                         }
@@ -69,7 +73,10 @@ pub fn update(world: &mut World, sound_mixer: &mut SoundMixer, camera: &Camera,
                         let player_bb = Aabb2::from_center_size(m2.pos, s2.size);
 
                         if projectile_bb.collides(&player_bb) {
-                            sound_mixer.play_sound(HIT_1.as_sound_data());
+                            if let Ok(sound) = HIT_1.as_sound_data() {
+                                sound_mixer.play_sound(sound);
+                            }
+                            spawn_particle_effect(&mut world.entities, m.pos, &HIT_EFFECT);
                             health::damage(health, *player_entity, p1.damage);
                             delete = true;
                         }
@@ -87,7 +94,9 @@ pub fn update(world: &mut World, sound_mixer: &mut SoundMixer, camera: &Camera,
                             let projectile_bb_2 = Aabb2::from_center_size(m2.pos, s2.size);
 
                             if projectile_bb.collides(&projectile_bb_2) {
-                                sound_mixer.play_sound(HIT_1.as_sound_data());
+                                if let Ok(sound) = HIT_1.as_sound_data() {
+                                    sound_mixer.play_sound(sound);
+                                }
                                 health::damage(health, e2, p1.damage);
                                 health::damage(health, e1, p2.damage);
                             }
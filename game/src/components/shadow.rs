@@ -8,7 +8,7 @@ use game_derive::SparseComponent;
 use n64::{
     gfx::{
         color_combiner_mode::{ColorCombinerMode, DSrc},
-        CommandBuffer, Pipeline,
+        CommandBuffer, CullMode, Pipeline,
     },
     VideoMode,
 };
@@ -23,6 +23,7 @@ static SHADOW_PIPELINE: Pipeline = Pipeline {
     blend: true,
     z_update: false,
     z_compare: false,
+    cull_mode: CullMode::Back,
     ..Pipeline::default()
 };
 
@@ -55,14 +56,12 @@ pub fn draw(world: &mut World, cb: &mut CommandBuffer, video_mode: VideoMode, ca
     for (_e, _shadow, mesh_drawable, movable) in
         query::<(Shadow, MeshDrawable, Movable)>(&mut world.components)
     {
+        let pos = movable.interpolated_pos(camera.alpha) - camera.pos;
+
         let transform = proj
             * Mat4::from_rotation_translation(
                 mesh_drawable.rot,
-                vec3(
-                    movable.pos.x - camera.pos.x - 0.06,
-                    movable.pos.y - camera.pos.y + 0.12,
-                    -1.1,
-                ),
+                vec3(pos.x - 0.06, pos.y + 0.12, -1.1),
             );
 
         cb.add_mesh_indexed(
@@ -71,6 +70,7 @@ pub fn draw(world: &mut World, cb: &mut CommandBuffer, video_mode: VideoMode, ca
             &mesh_drawable.model.colors,
             &mesh_drawable.model.indices,
             &transform.to_cols_array_2d(),
+            Some(mesh_drawable.model.size),
         );
     }
 }
@@ -2,7 +2,7 @@ use super::{movable::Movable, size::Size};
 use crate::{
     camera::Camera,
     ecs::{entity::EntitySystem, query::query, world::World},
-    model::{ModelData, StaticModelData},
+    model::{AnimatedModelData, StaticModelData},
 };
 use game_derive::SparseComponent;
 use n64::gfx::{StaticTexture, Texture};
@@ -31,8 +31,12 @@ impl SpawnerData {
     }
 }
 
-pub type SpawnerWithModelFunc =
-    fn(entities: &mut EntitySystem, movable: Movable, size: Size, model: ModelData<'static>);
+pub type SpawnerWithModelFunc = fn(
+    entities: &mut EntitySystem,
+    movable: Movable,
+    size: Size,
+    model: AnimatedModelData<'static>,
+);
 pub type SpawnerWithTextureFunc =
     fn(entities: &mut EntitySystem, movable: Movable, size: Size, texture: Texture<'static>);
 
@@ -53,13 +57,17 @@ pub fn update(world: &mut World, camera: &Camera) {
                     spawner_func,
                     model,
                 } => {
-                    spawner_func(&mut world.entities, *movable, *size, model.as_model_data());
+                    if let Ok(model) = model.as_animated_model_data() {
+                        spawner_func(&mut world.entities, *movable, *size, model);
+                    }
                 }
                 SpawnerData::SpawnerWithTexture {
                     spawner_func,
                     texture,
                 } => {
-                    spawner_func(&mut world.entities, *movable, *size, texture.as_texture());
+                    if let Ok(texture) = texture.as_texture() {
+                        spawner_func(&mut world.entities, *movable, *size, texture);
+                    }
                 }
             }
             world.entities.despawn(e);
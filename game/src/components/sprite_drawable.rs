@@ -9,7 +9,7 @@ use n64::{
         color_combiner_mode::{
             AAlphaSrc, ASrc, BAlphaSrc, BSrc, CAlphaSrc, CSrc, ColorCombinerMode, DAlphaSrc, DSrc,
         },
-        CommandBuffer, Pipeline, Texture,
+        CommandBuffer, Pipeline, Sprite, Texture,
     },
     VideoMode,
 };
@@ -32,11 +32,6 @@ pub fn draw(world: &mut World, cb: &mut CommandBuffer, video_mode: VideoMode, ca
     for (_e, sprite_drawable, movable, size, health) in
         query::<(SpriteDrawable, Movable, Size, Option<Health>)>(&mut world.components)
     {
-        let half_size = size.size / 2.0;
-
-        let upper_left = movable.pos - half_size;
-        let lower_right = movable.pos + half_size;
-
         let screen_size = Vec2::new(video_mode.width() as f32, video_mode.height() as f32);
 
         let mut pipeline = SPRITE_PIPELINE.with_texture(Some(sprite_drawable.texture));
@@ -59,9 +54,9 @@ pub fn draw(world: &mut World, cb: &mut CommandBuffer, video_mode: VideoMode, ca
 
         cb.set_pipeline(&pipeline);
 
-        cb.add_textured_rect(
-            (upper_left - camera.pos) * screen_size,
-            (lower_right - camera.pos) * screen_size,
-        );
+        cb.add_sprite(&Sprite::new(
+            (movable.interpolated_pos(camera.alpha) - camera.pos) * screen_size,
+            size.size * screen_size,
+        ));
     }
 }
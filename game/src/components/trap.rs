@@ -61,19 +61,16 @@ fn shoot_bullet(entities: &mut EntitySystem, target_type: WeaponTarget, pos: Vec
     let speed = dir * 0.30;
     entities
         .spawn()
-        .add(Movable {
-            pos: pos + offset,
-            speed,
-        })
+        .add(Movable::new(pos + offset, speed))
         .add(Size { size: BULLET.size })
         .add(Health {
             health: 5,
             damaged_this_frame: true,
         })
-        .add(MeshDrawable {
-            model: BULLET.as_model_data(),
+        .add_optional(BULLET.as_model_data().ok().map(|model| MeshDrawable {
+            model,
             rot: Quat::from_axis_angle(Vec3::Z, angle + PI / 2.0),
-        })
+        }))
         .add(Projectile {
             target_type,
             damage: 50 + (n64_math::random_f32() * 20.0) as i32,
@@ -108,19 +105,16 @@ pub fn shoot_missile(
 
     entities
         .spawn()
-        .add(Movable {
-            pos: pos + offset,
-            speed: speed + speed_offset,
-        })
+        .add(Movable::new(pos + offset, speed + speed_offset))
         .add(Size { size: MISSILE.size })
         .add(Health {
             health: 15,
             damaged_this_frame: true,
         })
-        .add(MeshDrawable {
-            model: MISSILE.as_model_data(),
+        .add_optional(MISSILE.as_model_data().ok().map(|model| MeshDrawable {
+            model,
             rot: Quat::IDENTITY,
-        })
+        }))
         .add(Projectile {
             target_type,
             damage: 100 + (n64_math::random_f32() * 50.0) as i32,
@@ -79,19 +79,16 @@ pub fn shoot_bullet(
 
     entities
         .spawn()
-        .add(Movable {
-            pos: pos + offset,
-            speed: speed + speed_offset,
-        })
+        .add(Movable::new(pos + offset, speed + speed_offset))
         .add(Size { size: BULLET.size })
         .add(Health {
             health: 5,
             damaged_this_frame: true,
         })
-        .add(MeshDrawable {
-            model: BULLET.as_model_data(),
+        .add_optional(BULLET.as_model_data().ok().map(|model| MeshDrawable {
+            model,
             rot: Quat::IDENTITY,
-        })
+        }))
         .add(Projectile {
             target_type,
             damage: 50 + (n64_math::random_f32() * 20.0) as i32,
@@ -127,19 +124,16 @@ pub fn shoot_missile(
 
     entities
         .spawn()
-        .add(Movable {
-            pos: pos + offset,
-            speed: speed + speed_offset,
-        })
+        .add(Movable::new(pos + offset, speed + speed_offset))
         .add(Size { size: MISSILE.size })
         .add(Health {
             health: 15,
             damaged_this_frame: true,
         })
-        .add(MeshDrawable {
-            model: MISSILE.as_model_data(),
+        .add_optional(MISSILE.as_model_data().ok().map(|model| MeshDrawable {
+            model,
             rot: Quat::IDENTITY,
-        })
+        }))
         .add(Projectile {
             target_type,
             damage: 100 + (n64_math::random_f32() * 50.0) as i32,
@@ -159,15 +153,12 @@ pub fn shoot_laser(
 
     entities
         .spawn()
-        .add(Movable {
-            pos: pos + extent,
-            speed,
-        })
+        .add(Movable::new(pos + extent, speed))
         .add(Size { size: LASER.size })
-        .add(MeshDrawable {
-            model: LASER.as_model_data(),
+        .add_optional(LASER.as_model_data().ok().map(|model| MeshDrawable {
+            model,
             rot: Quat::IDENTITY,
-        })
+        }))
         .add(Projectile {
             target_type,
             damage: 2,
@@ -189,10 +180,7 @@ pub fn shoot_flak(
 
         entities
             .spawn()
-            .add(Movable {
-                pos: pos + offset,
-                speed: speed_offset,
-            })
+            .add(Movable::new(pos + offset, speed_offset))
             .add(Size {
                 size: BULLET.size * 0.3,
             })
@@ -200,10 +188,10 @@ pub fn shoot_flak(
                 health: 5,
                 damaged_this_frame: true,
             })
-            .add(MeshDrawable {
-                model: BULLET.as_model_data(),
+            .add_optional(BULLET.as_model_data().ok().map(|model| MeshDrawable {
+                model,
                 rot: Quat::IDENTITY,
-            })
+            }))
             .add(Projectile {
                 target_type,
                 damage: 50 + (n64_math::random_f32() * 20.0) as i32,
@@ -234,7 +222,9 @@ pub fn fire(
             WeaponType::Bullet => {
                 if now - w.last_shoot_time > BULLET_DELAY_MS as i64 * 1000 {
                     if target_type == WeaponTarget::Enemy {
-                        sound_mixer.play_sound(SHOOT_3.as_sound_data());
+                        if let Ok(sound) = SHOOT_3.as_sound_data() {
+                            sound_mixer.play_sound(sound);
+                        }
                     }
                     shoot_bullet(
                         entities,
@@ -250,14 +240,18 @@ pub fn fire(
             }
             WeaponType::Laser => {
                 if target_type == WeaponTarget::Enemy {
-                    sound_mixer.play_sound(LASER_1.as_sound_data());
+                    if let Ok(sound) = LASER_1.as_sound_data() {
+                        sound_mixer.play_sound(sound);
+                    }
                 }
                 shoot_laser(entities, m.pos, m.speed, w.direction, target_type);
             }
             WeaponType::Missile => {
                 if now - w.last_shoot_time > MISSILE_DELAY_MS as i64 * 1000 {
                     if target_type == WeaponTarget::Enemy {
-                        sound_mixer.play_sound(MISSILE_1.as_sound_data());
+                        if let Ok(sound) = MISSILE_1.as_sound_data() {
+                            sound_mixer.play_sound(sound);
+                        }
                     }
 
                     let shooter_pos = m.pos;
@@ -297,7 +291,9 @@ pub fn fire(
             WeaponType::TripleMissile => {
                 if now - w.last_shoot_time > MISSILE_DELAY_MS as i64 * 1000 {
                     if target_type == WeaponTarget::Enemy {
-                        sound_mixer.play_sound(MISSILE_1.as_sound_data());
+                        if let Ok(sound) = MISSILE_1.as_sound_data() {
+                            sound_mixer.play_sound(sound);
+                        }
                     }
 
                     let shooter_pos = m.pos;
@@ -393,7 +389,9 @@ pub fn draw_missile_target(
     let (player, enemy, weapon, movable) =
         world.components.get::<(Player, Enemy, Weapon, Movable)>();
 
-    let target_indicator = TARGET_INDICATOR.as_model_data();
+    let Ok(target_indicator) = TARGET_INDICATOR.as_model_data() else {
+        return;
+    };
 
     for player_entity in player.entities() {
         if let (Some(m), Some(w)) = (
@@ -419,7 +417,11 @@ pub fn draw_missile_target(
                     .filter_map(|e| movable.lookup(*e).map(|m| (m, *e)))
                     .filter_map(|(m, e)| {
                         if e != *player_entity && shooter_pos.y - m.pos.y > 0.0 {
-                            Some(((shooter_pos - m.pos).length(), m.pos, e))
+                            Some((
+                                (shooter_pos - m.pos).length(),
+                                m.interpolated_pos(camera.alpha),
+                                e,
+                            ))
                         } else {
                             None
                         }
@@ -453,6 +455,7 @@ pub fn draw_missile_target(
                         &target_indicator.colors,
                         &target_indicator.indices,
                         &transform.to_cols_array_2d(),
+                        Some(target_indicator.size),
                     );
                 }
             } else if w.weapon_type == WeaponType::TripleMissile {
@@ -465,7 +468,11 @@ pub fn draw_missile_target(
                     .filter_map(|e| movable.lookup(*e).map(|m| (m, *e)))
                     .filter_map(|(m, e)| {
                         if e != *player_entity && shooter_pos.y - m.pos.y > 0.0 {
-                            Some(((shooter_pos - m.pos).length(), m.pos, e))
+                            Some((
+                                (shooter_pos - m.pos).length(),
+                                m.interpolated_pos(camera.alpha),
+                                e,
+                            ))
                         } else {
                             None
                         }
@@ -500,6 +507,7 @@ pub fn draw_missile_target(
                         &target_indicator.colors,
                         &target_indicator.indices,
                         &transform.to_cols_array_2d(),
+                        Some(target_indicator.size),
                     );
                 }
             }
@@ -0,0 +1,65 @@
+use crate::{
+    camera::Camera,
+    components::{missile::Missile, movable::Movable, size::Size, waypoint_ai::WaypointAi},
+    ecs::{query::query, storage::Storage, world::World},
+};
+use n64::{
+    gfx::{debug_draw_enabled, CommandBuffer},
+    VideoMode,
+};
+use n64_math::{vec2, Aabb2, Vec2};
+
+const BOUNDING_BOX_COLOR: u32 = 0x00ff00ff;
+const WAYPOINT_COLOR: u32 = 0xffff00ff;
+const MISSILE_TARGET_COLOR: u32 = 0xff0000ff;
+
+// Seconds of movement shown by the waypoint speed arrows
+const SPEED_ARROW_TIME: f32 = 0.25;
+
+pub fn draw(world: &mut World, cb: &mut CommandBuffer, video_mode: VideoMode, camera: &Camera) {
+    n64::scope!("debug_draw::draw");
+
+    if !debug_draw_enabled() {
+        return;
+    }
+
+    let screen_size = vec2(video_mode.width() as f32, video_mode.height() as f32);
+    let to_screen = |pos: Vec2| (pos - camera.pos) * screen_size;
+
+    for (_e, movable, size) in query::<(Movable, Size)>(&mut world.components) {
+        let bb = Aabb2::from_center_size(movable.interpolated_pos(camera.alpha), size.size);
+
+        cb.add_debug_rect(
+            to_screen(vec2(bb.left(), bb.top())),
+            to_screen(vec2(bb.right(), bb.bottom())),
+            BOUNDING_BOX_COLOR,
+        );
+    }
+
+    for (_e, _ai, movable) in query::<(WaypointAi, Movable)>(&mut world.components) {
+        let pos = movable.interpolated_pos(camera.alpha);
+
+        cb.add_debug_arrow(
+            to_screen(pos),
+            to_screen(pos + SPEED_ARROW_TIME * movable.speed),
+            WAYPOINT_COLOR,
+        );
+    }
+
+    let (missile, movable) = world.components.get::<(Missile, Movable)>();
+
+    for (missile, entity) in missile.components().iter().zip(missile.entities()) {
+        let interpolated_pos = |entity| {
+            movable
+                .lookup(entity)
+                .map(|movable| movable.interpolated_pos(camera.alpha))
+        };
+
+        let target_pos = missile.target.and_then(interpolated_pos);
+
+        if let (Some(pos), Some(target_pos)) = (interpolated_pos(*entity), target_pos) {
+            cb.add_debug_arrow(to_screen(pos), to_screen(target_pos), MISSILE_TARGET_COLOR);
+            cb.add_debug_circle(to_screen(target_pos), 8.0, MISSILE_TARGET_COLOR);
+        }
+    }
+}
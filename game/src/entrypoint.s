@@ -17,6 +17,23 @@
 // Runtime environment pointers
 .set FS_START,              0x8000031C
 
+// Exception vectors, TLB miss, XTLB miss and general
+.set TLB_VECTOR,            0x80000000
+.set XTLB_VECTOR,           0x80000080
+.set GENERAL_VECTOR,        0x80000180
+
+// Layout of n64_sys::mi::ExceptionFrame, after the argument area of the called function
+.set FRAME_GPR,             16
+.set FRAME_HI,              FRAME_GPR + 256
+.set FRAME_LO,              FRAME_HI + 8
+.set FRAME_STATUS,          FRAME_LO + 8
+.set FRAME_CAUSE,           FRAME_STATUS + 4
+.set FRAME_EPC,             FRAME_CAUSE + 4
+.set FRAME_BAD_VADDR,       FRAME_EPC + 4
+.set FRAME_FCSR,            FRAME_BAD_VADDR + 4
+.set FRAME_FPR,             FRAME_FCSR + 8
+.set FRAME_SIZE,            FRAME_FPR + 128 + 8
+
 _start:
     // Initialize stack
     li $t1, OS_MEM_SIZE
@@ -50,6 +67,17 @@ _start:
     li $t1, FS_START
     sw $t0, ($t1)
 
+    // Install the exception vectors
+    li $a0, TLB_VECTOR
+    jal install_vector
+    nop
+    li $a0, XTLB_VECTOR
+    jal install_vector
+    nop
+    li $a0, GENERAL_VECTOR
+    jal install_vector
+    nop
+
     // Jump to Rust
     jal main
     nop
@@ -59,3 +87,162 @@ _start:
 1:
     j 1b
     nop
+
+// Copies the jump to exception_handler to the vector in $a0 and makes it visible to the
+// instruction cache
+install_vector:
+    la $t0, exception_vector
+    li $t1, 4
+1:
+    lw $t2, 0($t0)
+    sw $t2, 0($a0)
+    cache 0x19, 0($a0)
+    cache 0x10, 0($a0)
+    addiu $t0, $t0, 4
+    addiu $a0, $a0, 4
+    addiu $t1, $t1, -1
+    bnez $t1, 1b
+    nop
+    jr $ra
+    nop
+
+exception_vector:
+    la $k0, exception_handler
+    jr $k0
+    nop
+
+// Saves all registers in an ExceptionFrame on the stack, calls n64_sys::mi and then restores them
+exception_handler:
+    .set noat
+    addiu $sp, $sp, -FRAME_SIZE
+
+    sd $at, (FRAME_GPR + 1 * 8)($sp)
+    sd $v0, (FRAME_GPR + 2 * 8)($sp)
+    sd $v1, (FRAME_GPR + 3 * 8)($sp)
+    sd $a0, (FRAME_GPR + 4 * 8)($sp)
+    sd $a1, (FRAME_GPR + 5 * 8)($sp)
+    sd $a2, (FRAME_GPR + 6 * 8)($sp)
+    sd $a3, (FRAME_GPR + 7 * 8)($sp)
+    sd $t0, (FRAME_GPR + 8 * 8)($sp)
+    sd $t1, (FRAME_GPR + 9 * 8)($sp)
+    sd $t2, (FRAME_GPR + 10 * 8)($sp)
+    sd $t3, (FRAME_GPR + 11 * 8)($sp)
+    sd $t4, (FRAME_GPR + 12 * 8)($sp)
+    sd $t5, (FRAME_GPR + 13 * 8)($sp)
+    sd $t6, (FRAME_GPR + 14 * 8)($sp)
+    sd $t7, (FRAME_GPR + 15 * 8)($sp)
+    sd $s0, (FRAME_GPR + 16 * 8)($sp)
+    sd $s1, (FRAME_GPR + 17 * 8)($sp)
+    sd $s2, (FRAME_GPR + 18 * 8)($sp)
+    sd $s3, (FRAME_GPR + 19 * 8)($sp)
+    sd $s4, (FRAME_GPR + 20 * 8)($sp)
+    sd $s5, (FRAME_GPR + 21 * 8)($sp)
+    sd $s6, (FRAME_GPR + 22 * 8)($sp)
+    sd $s7, (FRAME_GPR + 23 * 8)($sp)
+    sd $t8, (FRAME_GPR + 24 * 8)($sp)
+    sd $t9, (FRAME_GPR + 25 * 8)($sp)
+    sd $gp, (FRAME_GPR + 28 * 8)($sp)
+    sd $fp, (FRAME_GPR + 30 * 8)($sp)
+    sd $ra, (FRAME_GPR + 31 * 8)($sp)
+
+    // The stack pointer from before the exception
+    addiu $t0, $sp, FRAME_SIZE
+    sd $t0, (FRAME_GPR + 29 * 8)($sp)
+
+    mfhi $t0
+    sd $t0, FRAME_HI($sp)
+    mflo $t0
+    sd $t0, FRAME_LO($sp)
+
+    mfc0 $t0, $12
+    sw $t0, FRAME_STATUS($sp)
+    mfc0 $t0, $13
+    sw $t0, FRAME_CAUSE($sp)
+    mfc0 $t0, $14
+    sw $t0, FRAME_EPC($sp)
+    mfc0 $t0, $8
+    sw $t0, FRAME_BAD_VADDR($sp)
+
+    // Handlers are Rust code that may use the FPU
+    cfc1 $t0, FPC_CSR
+    sw $t0, FRAME_FCSR($sp)
+    sdc1 $f0, (FRAME_FPR + 0 * 8)($sp)
+    sdc1 $f2, (FRAME_FPR + 1 * 8)($sp)
+    sdc1 $f4, (FRAME_FPR + 2 * 8)($sp)
+    sdc1 $f6, (FRAME_FPR + 3 * 8)($sp)
+    sdc1 $f8, (FRAME_FPR + 4 * 8)($sp)
+    sdc1 $f10, (FRAME_FPR + 5 * 8)($sp)
+    sdc1 $f12, (FRAME_FPR + 6 * 8)($sp)
+    sdc1 $f14, (FRAME_FPR + 7 * 8)($sp)
+    sdc1 $f16, (FRAME_FPR + 8 * 8)($sp)
+    sdc1 $f18, (FRAME_FPR + 9 * 8)($sp)
+    sdc1 $f20, (FRAME_FPR + 10 * 8)($sp)
+    sdc1 $f22, (FRAME_FPR + 11 * 8)($sp)
+    sdc1 $f24, (FRAME_FPR + 12 * 8)($sp)
+    sdc1 $f26, (FRAME_FPR + 13 * 8)($sp)
+    sdc1 $f28, (FRAME_FPR + 14 * 8)($sp)
+    sdc1 $f30, (FRAME_FPR + 15 * 8)($sp)
+
+    jal __n64_exception
+    addiu $a0, $sp, FRAME_GPR
+
+    ldc1 $f0, (FRAME_FPR + 0 * 8)($sp)
+    ldc1 $f2, (FRAME_FPR + 1 * 8)($sp)
+    ldc1 $f4, (FRAME_FPR + 2 * 8)($sp)
+    ldc1 $f6, (FRAME_FPR + 3 * 8)($sp)
+    ldc1 $f8, (FRAME_FPR + 4 * 8)($sp)
+    ldc1 $f10, (FRAME_FPR + 5 * 8)($sp)
+    ldc1 $f12, (FRAME_FPR + 6 * 8)($sp)
+    ldc1 $f14, (FRAME_FPR + 7 * 8)($sp)
+    ldc1 $f16, (FRAME_FPR + 8 * 8)($sp)
+    ldc1 $f18, (FRAME_FPR + 9 * 8)($sp)
+    ldc1 $f20, (FRAME_FPR + 10 * 8)($sp)
+    ldc1 $f22, (FRAME_FPR + 11 * 8)($sp)
+    ldc1 $f24, (FRAME_FPR + 12 * 8)($sp)
+    ldc1 $f26, (FRAME_FPR + 13 * 8)($sp)
+    ldc1 $f28, (FRAME_FPR + 14 * 8)($sp)
+    ldc1 $f30, (FRAME_FPR + 15 * 8)($sp)
+    lw $t0, FRAME_FCSR($sp)
+    ctc1 $t0, FPC_CSR
+
+    // The handler may have moved epc past the faulting instruction
+    lw $t0, FRAME_EPC($sp)
+    mtc0 $t0, $14
+
+    ld $t0, FRAME_HI($sp)
+    mthi $t0
+    ld $t0, FRAME_LO($sp)
+    mtlo $t0
+
+    ld $at, (FRAME_GPR + 1 * 8)($sp)
+    ld $v0, (FRAME_GPR + 2 * 8)($sp)
+    ld $v1, (FRAME_GPR + 3 * 8)($sp)
+    ld $a0, (FRAME_GPR + 4 * 8)($sp)
+    ld $a1, (FRAME_GPR + 5 * 8)($sp)
+    ld $a2, (FRAME_GPR + 6 * 8)($sp)
+    ld $a3, (FRAME_GPR + 7 * 8)($sp)
+    ld $t0, (FRAME_GPR + 8 * 8)($sp)
+    ld $t1, (FRAME_GPR + 9 * 8)($sp)
+    ld $t2, (FRAME_GPR + 10 * 8)($sp)
+    ld $t3, (FRAME_GPR + 11 * 8)($sp)
+    ld $t4, (FRAME_GPR + 12 * 8)($sp)
+    ld $t5, (FRAME_GPR + 13 * 8)($sp)
+    ld $t6, (FRAME_GPR + 14 * 8)($sp)
+    ld $t7, (FRAME_GPR + 15 * 8)($sp)
+    ld $s0, (FRAME_GPR + 16 * 8)($sp)
+    ld $s1, (FRAME_GPR + 17 * 8)($sp)
+    ld $s2, (FRAME_GPR + 18 * 8)($sp)
+    ld $s3, (FRAME_GPR + 19 * 8)($sp)
+    ld $s4, (FRAME_GPR + 20 * 8)($sp)
+    ld $s5, (FRAME_GPR + 21 * 8)($sp)
+    ld $s6, (FRAME_GPR + 22 * 8)($sp)
+    ld $s7, (FRAME_GPR + 23 * 8)($sp)
+    ld $t8, (FRAME_GPR + 24 * 8)($sp)
+    ld $t9, (FRAME_GPR + 25 * 8)($sp)
+    ld $gp, (FRAME_GPR + 28 * 8)($sp)
+    ld $fp, (FRAME_GPR + 30 * 8)($sp)
+    ld $ra, (FRAME_GPR + 31 * 8)($sp)
+
+    addiu $sp, $sp, FRAME_SIZE
+    eret
+    .set at
@@ -0,0 +1,88 @@
+// Runs the simulation with a fixed step no matter the frame rate. Frame time is gathered in an
+// accumulator and simulated in whole steps, the remainder is used to interpolate rendering.
+pub struct FixedTimestep {
+    step: f32,
+    max_steps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub const fn new(step: f32, max_steps: u32) -> Self {
+        Self {
+            step,
+            max_steps,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    // Returns the number of steps to simulate this frame
+    pub fn advance(&mut self, frame_dt: f32) -> u32 {
+        self.accumulator += libm::fmaxf(frame_dt, 0.0);
+
+        let steps = (self.accumulator / self.step) as u32;
+
+        // When simulating is slower than real time the time that does not fit is dropped,
+        // otherwise every frame would need more steps than the last.
+        if steps > self.max_steps {
+            self.accumulator = 0.0;
+            return self.max_steps;
+        }
+
+        self.accumulator -= steps as f32 * self.step;
+
+        steps
+    }
+
+    // How far between the last and the next step the frame is rendered, 0.0 to 1.0
+    pub fn alpha(&self) -> f32 {
+        libm::fminf(self.accumulator / self.step, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A power of two so the arithmetic is exact
+    const STEP: f32 = 0.25;
+
+    #[test]
+    fn remainder_is_kept_for_the_next_frame() {
+        let mut timestep = FixedTimestep::new(STEP, 4);
+
+        assert_eq!(timestep.advance(1.5 * STEP), 1);
+        assert_eq!(timestep.alpha(), 0.5);
+
+        assert_eq!(timestep.advance(0.5 * STEP), 1);
+        assert_eq!(timestep.alpha(), 0.0);
+
+        assert_eq!(timestep.advance(0.25 * STEP), 0);
+        assert_eq!(timestep.alpha(), 0.25);
+    }
+
+    #[test]
+    fn steps_are_capped() {
+        let mut timestep = FixedTimestep::new(STEP, 4);
+
+        assert_eq!(timestep.advance(10.5 * STEP), 4);
+        assert_eq!(timestep.alpha(), 0.0);
+
+        // The dropped time is not made up later
+        assert_eq!(timestep.advance(STEP), 1);
+    }
+
+    #[test]
+    fn negative_dt_is_ignored() {
+        let mut timestep = FixedTimestep::new(STEP, 4);
+
+        assert_eq!(timestep.advance(0.5 * STEP), 0);
+        assert_eq!(timestep.advance(-STEP), 0);
+        assert_eq!(timestep.alpha(), 0.5);
+
+        assert_eq!(timestep.advance(0.5 * STEP), 1);
+    }
+}
@@ -0,0 +1,64 @@
+use n64::{Command, N64};
+
+// Ids for Command::SetVariable
+pub const VARIABLE_TIME_SCALE: u16 = 0;
+
+// Applies commands from the deploy tool, the game can be paused and stepped one simulation step at
+// a time while debugging on hardware
+pub struct HostControl {
+    paused: bool,
+    pending_steps: u32,
+    time_scale: f32,
+}
+
+impl HostControl {
+    pub const fn new() -> Self {
+        Self {
+            paused: false,
+            pending_steps: 0,
+            time_scale: 1.0,
+        }
+    }
+
+    // Returns the frame time to simulate, no time passes while paused except for requested steps
+    pub fn update(&mut self, n64: &mut N64, dt: f32, step: f32) -> f32 {
+        while let Some(command) = n64.host.poll() {
+            match command {
+                Command::Pause => self.paused = true,
+                Command::Resume => self.paused = false,
+                Command::StepFrame => {
+                    self.paused = true;
+                    self.pending_steps += 1;
+                }
+                Command::SetVariable { id, value } => self.set_variable(id, value),
+                Command::RequestScreenshot => n64.host.send_screenshot(&n64.framebuffer),
+            }
+        }
+
+        if !self.paused {
+            return dt * self.time_scale;
+        }
+
+        if self.pending_steps > 0 {
+            self.pending_steps -= 1;
+            return step;
+        }
+
+        0.0
+    }
+
+    fn set_variable(&mut self, id: u16, value: f32) {
+        match id {
+            VARIABLE_TIME_SCALE => self.time_scale = libm::fmaxf(value, 0.0),
+            _ => {
+                n64::debugln!("Unknown variable {}", id);
+            }
+        }
+    }
+}
+
+impl Default for HostControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
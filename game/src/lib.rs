@@ -9,14 +9,23 @@
 
 extern crate alloc;
 
+pub mod assets;
+pub mod atlases;
 pub mod camera;
 pub mod components;
+pub mod debug_draw;
 pub mod ecs;
 pub mod font;
+pub mod game_loop;
+pub mod host_control;
 pub mod map;
 pub mod maps;
 pub mod model;
 pub mod models;
+pub mod replay;
+pub mod replays;
+pub mod save_game;
+pub mod screen_effects;
 pub mod sound;
 pub mod sound_mixer;
 pub mod sounds;
@@ -1,7 +1,6 @@
 #![cfg_attr(target_vendor = "nintendo64", feature(alloc_error_handler))]
 #![cfg_attr(target_vendor = "nintendo64", feature(asm_experimental_arch))]
 #![cfg_attr(target_vendor = "nintendo64", feature(lang_items))]
-#![cfg_attr(target_vendor = "nintendo64", feature(panic_info_message))]
 #![cfg_attr(target_vendor = "nintendo64", feature(start))]
 #![cfg_attr(target_vendor = "nintendo64", no_std)]
 #![allow(clippy::inconsistent_digit_grouping)]
@@ -12,27 +11,37 @@ extern crate alloc;
 use game::{
     camera::Camera,
     components::{
-        box_drawable, diver_ai, enemy,
+        animation_player, box_drawable, diver_ai, enemy,
         health::{self, Health},
-        keep_on_screen, mesh_drawable, missile, movable,
+        keep_on_screen, mesh_drawable, missile, movable, particle_emitter,
         pickup::{self, spawn_pickup},
         player::{self, draw_player_weapon, spawn_player, Player},
         print_position, projectile, remove_when_below, shadow, spawner, sprite_drawable, trap,
         waypoint_ai,
         weapon::draw_missile_target,
     },
+    debug_draw,
     ecs::{storage::Storage, world::World},
     font,
+    game_loop::FixedTimestep,
+    host_control::HostControl,
     map::Map,
     maps::MAP_1,
+    replay::InputSource,
+    replays,
+    save_game::SaveState,
+    screen_effects::ScreenEffects,
     sound_mixer::SoundMixer,
 };
 use n64::{
     self, current_time_us,
-    gfx::{CommandBuffer, CommandBufferCache, FillPipeline, Pipeline},
+    gfx::{
+        debug_draw_enabled, set_debug_draw_enabled, CommandBuffer, CommandBufferCache,
+        FillPipeline, Pipeline,
+    },
     ipl3font, slow_cpu_clear, VideoMode, N64,
 };
-use n64_math::{random_u32, vec2, vec3, Color};
+use n64_math::{random_u32, vec2, vec3, Color, Vec2};
 
 const RED: Color = Color::new(0b10000_00011_00011_1);
 const GREEN: Color = Color::new(0b00011_10000_00011_1);
@@ -48,13 +57,28 @@ const VIDEO_MODE: VideoMode = VideoMode::Pal {
 
 const DEBUG_TRIANGLES: bool = false;
 
+const SIMULATION_DT: f32 = 1.0 / 60.0;
+const MAX_SIMULATION_STEPS: u32 = 4;
+
+// Name of a recording in game/replays to play back, picked when building with LOKA_ROM_REPLAY.
+// LOKA_REPLAY overrides it on the emulator
+const ROM_REPLAY: Option<&str> = option_env!("LOKA_ROM_REPLAY");
+
+const DAMAGE_FLASH_COLOR: u32 = 0xff2000ff;
+const DAMAGE_FLASH_TIME: f32 = 0.15;
+const DAMAGE_SHAKE_STRENGTH: f32 = 4.0;
+const DAMAGE_SHAKE_TIME: f32 = 0.3;
+const DAMAGE_RUMBLE_TIME: f32 = 0.2;
+const EXPLOSION_RUMBLE_TIME: f32 = 0.1;
+const FADE_IN_TIME: f32 = 1.0;
+
 fn main() {
     n64::init_profiler();
 
     let mut n64 = N64::new(VIDEO_MODE);
 
     let mut world = World::new();
-    let map = Map::load(MAP_1);
+    let map = Map::load(MAP_1).expect("Unable to load the map from the asset archive");
 
     let start_pos = vec2(
         map.get_start_pos().x / VIDEO_MODE.width() as f32,
@@ -63,7 +87,12 @@ fn main() {
 
     let mut sound_mixer = SoundMixer::new();
     let mut camera = Camera::new(start_pos);
+    let mut screen_effects = ScreenEffects::new();
     let mut command_buffer_cache = CommandBufferCache::new(VIDEO_MODE);
+    let mut input = InputSource::new(ROM_REPLAY.and_then(replays::find));
+    let mut timestep = FixedTimestep::new(SIMULATION_DT, MAX_SIMULATION_STEPS);
+    let mut host_control = HostControl::new();
+    let mut save_state = SaveState::load();
 
     let _test_pickup = spawn_pickup(&mut world.entities, start_pos + vec2(0.5, 0.2));
 
@@ -71,6 +100,9 @@ fn main() {
 
     map.spawn_enemies(&mut world, &VIDEO_MODE);
 
+    screen_effects.set_fade(0x000000ff, 1.0);
+    screen_effects.fade_to(0x000000ff, 0.0, FADE_IN_TIME);
+
     let mut frame_begin_time;
     let mut last_frame_begin_time = current_time_us();
     let mut swap_time = 0;
@@ -79,9 +111,11 @@ fn main() {
     let mut last_colored_rect_count = 0;
     let mut last_textured_rect_count = 0;
     let mut last_mesh_count = 0;
+    let mut last_culled_triangle_count = 0;
     let mut last_rsp_clock = 0;
 
     let mut last_step = false;
+    let mut last_toggle_debug_draw = false;
 
     loop {
         n64::frame!();
@@ -97,29 +131,81 @@ fn main() {
             n64::scope!("Update");
 
             n64.controllers.update(&n64.graphics);
+            dt = input.update(&mut n64.controllers, dt);
+            dt = host_control.update(&mut n64, dt, SIMULATION_DT);
+
+            let controller = *n64.controllers.port(0);
 
-            camera.update(&n64.controllers, dt, &VIDEO_MODE);
+            if controller.l() && !last_toggle_debug_draw {
+                set_debug_draw_enabled(!debug_draw_enabled());
+            }
+            last_toggle_debug_draw = controller.l();
 
+            // Damage is shown for the whole frame, no matter which step it happened in
             health::clear_was_damaged(&mut world);
 
-            enemy::update(&mut world, &mut sound_mixer);
-            player::update(&mut world, &n64.controllers, &mut sound_mixer, &camera);
+            for _ in 0..timestep.advance(dt) {
+                let dt = timestep.step();
 
-            diver_ai::update(&mut world);
-            waypoint_ai::update(&mut world, dt);
-            missile::update(&mut world, dt);
+                movable::store_prev_pos(&mut world);
+                camera.update(&controller, dt, &VIDEO_MODE);
 
-            movable::simulate(&mut world, dt);
+                if enemy::update(&mut world, &mut sound_mixer) {
+                    n64.controllers.rumble_pulse(0, EXPLOSION_RUMBLE_TIME);
+                }
+                player::update(&mut world, &controller, &mut sound_mixer, &camera);
 
-            projectile::update(&mut world, &mut sound_mixer, &camera, dt);
-            trap::update(&mut world);
-            pickup::update(&mut world, &mut sound_mixer, &camera);
-            spawner::update(&mut world, &camera);
-            keep_on_screen::update(&mut world, &camera);
-            remove_when_below::update(&mut world, &camera);
-            print_position::print(&mut world);
+                diver_ai::update(&mut world);
+                waypoint_ai::update(&mut world, dt);
+                missile::update(&mut world, dt);
+
+                movable::simulate(&mut world, dt);
+                particle_emitter::update(&mut world, dt);
+                animation_player::update(&mut world, dt);
+
+                projectile::update(&mut world, &mut sound_mixer, &camera, dt);
+                trap::update(&mut world);
+                pickup::update(&mut world, &mut sound_mixer, &camera);
+                spawner::update(&mut world, &camera);
+                keep_on_screen::update(&mut world, &camera);
+                remove_when_below::update(&mut world, &camera);
+                print_position::print(&mut world);
+
+                screen_effects.update(dt);
+
+                // Entities spawned or despawned during a step are visible to the next one
+                world.housekeep();
+            }
+
+            // Storing blocks on the EEPROM, so it's done once per frame after all steps. A replay
+            // doesn't get to set the high score
+            if !input.is_replaying() {
+                save_state.update(
+                    world
+                        .components
+                        .get::<(Player,)>()
+                        .lookup(player)
+                        .map(|p| p.score)
+                        .unwrap_or(0),
+                    dt,
+                );
+            }
         }
 
+        if world
+            .components
+            .get::<(Health,)>()
+            .lookup(player)
+            .map(|health| health.damaged_this_frame)
+            .unwrap_or(false)
+        {
+            screen_effects.flash(DAMAGE_FLASH_COLOR, DAMAGE_FLASH_TIME);
+            screen_effects.shake(DAMAGE_SHAKE_STRENGTH, DAMAGE_SHAKE_TIME);
+            n64.controllers.rumble_pulse(0, DAMAGE_RUMBLE_TIME);
+        }
+
+        let render_camera = camera.interpolated(timestep.alpha());
+
         {
             n64::scope!("Audio");
 
@@ -137,15 +223,22 @@ fn main() {
             cb.clear();
 
             if !DEBUG_TRIANGLES {
-                map.render(&mut cb, VIDEO_MODE, &camera);
+                cb.set_screen_offset(screen_effects.screen_offset());
+
+                map.render(&mut cb, VIDEO_MODE, &render_camera);
+
+                shadow::draw(&mut world, &mut cb, VIDEO_MODE, &render_camera);
+
+                box_drawable::draw(&mut world, &mut cb, VIDEO_MODE, &render_camera);
+                sprite_drawable::draw(&mut world, &mut cb, VIDEO_MODE, &render_camera);
+                mesh_drawable::draw(&mut world, &mut cb, VIDEO_MODE, &render_camera);
+                particle_emitter::draw(&mut world, &mut cb, VIDEO_MODE, &render_camera);
 
-                shadow::draw(&mut world, &mut cb, VIDEO_MODE, &camera);
+                draw_missile_target(&mut world, &mut cb, VIDEO_MODE, &render_camera);
 
-                box_drawable::draw(&mut world, &mut cb, VIDEO_MODE, &camera);
-                sprite_drawable::draw(&mut world, &mut cb, VIDEO_MODE, &camera);
-                mesh_drawable::draw(&mut world, &mut cb, VIDEO_MODE, &camera);
+                debug_draw::draw(&mut world, &mut cb, VIDEO_MODE, &render_camera);
 
-                draw_missile_target(&mut world, &mut cb, VIDEO_MODE, &camera);
+                cb.set_screen_offset(Vec2::ZERO);
             }
 
             if DEBUG_TRIANGLES {
@@ -188,6 +281,7 @@ fn main() {
                         [0.0, 0.0, 1.0, 0.0],
                         [0.0, 0.0, 0.0, 1.0],
                     ],
+                    None,
                 );
             }
 
@@ -205,6 +299,12 @@ fn main() {
                     vec2(300.0, 10.0),
                     0x0000efff,
                 );
+                font::draw_number(
+                    &mut cb,
+                    save_state.high_score(),
+                    vec2(300.0, 22.0),
+                    0x7f7fefff,
+                );
                 font::draw_number(
                     &mut cb,
                     world
@@ -260,6 +360,12 @@ fn main() {
                         0x00af00ff,
                     );
                     font::draw_number(&mut cb, last_mesh_count, vec2(300.0, 30.0), 0xaf0000ff);
+                    font::draw_number(
+                        &mut cb,
+                        last_culled_triangle_count,
+                        vec2(200.0, 50.0),
+                        0xaf0000ff,
+                    );
 
                     font::draw_number(
                         &mut cb,
@@ -277,6 +383,8 @@ fn main() {
                 draw_player_weapon(&mut world, &mut cb, &VIDEO_MODE);
             }
 
+            screen_effects.draw(&mut cb);
+
             cb
         };
 
@@ -285,17 +393,24 @@ fn main() {
             n64.graphics.swap_buffers(&mut n64.framebuffer)
         };
 
-        let (colored_rect_count, textured_rect_count, mesh_count, rsp_clock) = {
+        let (
+            colored_rect_count,
+            textured_rect_count,
+            mesh_count,
+            _culled_mesh_count,
+            culled_triangle_count,
+            rsp_clock,
+        ) = {
             n64::scope!("Submit Command Buffer");
             let cb = cb;
 
             let step = if last_step {
                 false
             } else {
-                n64.controllers.start()
+                n64.controllers.port(0).start()
             };
 
-            last_step = n64.controllers.start();
+            last_step = n64.controllers.port(0).start();
 
             cb.submit(&mut n64.graphics, step)
         };
@@ -303,6 +418,7 @@ fn main() {
         last_colored_rect_count = colored_rect_count;
         last_textured_rect_count = textured_rect_count;
         last_mesh_count = mesh_count;
+        last_culled_triangle_count = culled_triangle_count;
         last_rsp_clock = rsp_clock;
 
         {
@@ -313,11 +429,6 @@ fn main() {
             }
         }
 
-        {
-            n64::scope!("Housekeep");
-            world.housekeep();
-        }
-
         if false {
             // !health::is_alive(world.components.get::<(Health,)>(), player) {
             break;
@@ -349,50 +460,7 @@ fn start(_argc: isize, _argv: *const *const u8) -> isize {
 #[cfg(target_vendor = "nintendo64")]
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
-    n64::debugln!("{}", &info);
-
-    const GREEN: Color = Color::new(0b00011_10000_00011_1);
-
-    let mut out_tex = n64::gfx::TextureMut::new(VIDEO_MODE.width(), VIDEO_MODE.height(), unsafe {
-        core::slice::from_raw_parts_mut(n64::vi::get_vi_buffer(), VIDEO_MODE.size() as usize)
-    });
-
-    slow_cpu_clear(out_tex.data);
-
-    ipl3font::draw_str(&mut out_tex, 15, 15, RED, b"PANIC!");
-    if let Some(location) = info.location() {
-        ipl3font::draw_str(
-            &mut out_tex,
-            15,
-            30,
-            RED,
-            alloc::format!(
-                "{}:{}",
-                location.file().rsplit('\\').next().unwrap_or(""),
-                location.line()
-            )
-            .as_bytes(),
-        );
-    }
-    if let Some(args) = info.message() {
-        ipl3font::draw_str(
-            &mut out_tex,
-            15,
-            45,
-            GREEN,
-            alloc::format!("{}", args).as_bytes(),
-        );
-    } else {
-        ipl3font::draw_str(&mut out_tex, 15, 45, GREEN, b"No Message");
-    }
-
-    unsafe {
-        n64::sys::data_cache_hit_writeback(out_tex.data);
-        n64::vi::set_vi_buffer(out_tex.data);
-    }
-
-    #[allow(clippy::empty_loop)]
-    loop {}
+    n64::crash::panic(info)
 }
 
 #[cfg(target_vendor = "nintendo64")]
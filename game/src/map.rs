@@ -8,7 +8,7 @@ use n64::{
         color_combiner_mode::{ColorCombinerMode, DSrc},
         CommandBuffer, Pipeline, StaticTexture,
     },
-    VideoMode,
+    ArchiveError, StaticData, VideoMode,
 };
 use n64_math::Vec2;
 
@@ -25,7 +25,7 @@ pub struct StaticMapData {
     pub tile_width: i32,
     pub tile_height: i32,
     pub tiles: &'static [&'static StaticTexture],
-    pub layers: &'static [u8],
+    pub layers: StaticData,
     pub objects: &'static [StaticObject],
 }
 
@@ -40,8 +40,15 @@ pub struct Map {
 }
 
 impl Map {
-    pub fn load(data: &'static StaticMapData) -> Self {
-        Self { data }
+    // Archived tiles and layers are loaded here so the level does not stall when it scrolls in
+    pub fn load(data: &'static StaticMapData) -> Result<Self, ArchiveError> {
+        data.layers.preload()?;
+
+        for tile in data.tiles {
+            tile.data.preload()?;
+        }
+
+        Ok(Self { data })
     }
 
     pub fn spawn_enemies(&self, world: &mut World, video_mode: &VideoMode) {
@@ -80,7 +87,11 @@ impl Map {
         let first_tile_x = camera_tile.x as i32;
         let first_tile_y = camera_tile.y as i32;
 
-        for layer in self.data.layers.chunks_exact(tiles_in_layer) {
+        let Ok(layers) = self.data.layers.get() else {
+            return;
+        };
+
+        for layer in layers.chunks_exact(tiles_in_layer) {
             for y in first_tile_y..(first_tile_y + tiles_on_screen_y) {
                 if y < 0 || y >= self.data.height_in_tiles {
                     continue;
@@ -108,12 +119,13 @@ impl Map {
 
                     cb.set_pipeline(
                         &MAP_PIPELINE
-                            .with_texture(Some(self.data.tiles[(tile - 1) as usize].as_texture())),
+                            .with_texture(self.data.tiles[(tile - 1) as usize].as_texture().ok()),
                     );
 
                     cb.add_textured_rect(
                         upper_left - camera_pixel_pos,
                         lower_right - camera_pixel_pos,
+                        None,
                     );
                 }
             }
@@ -1,99 +1,220 @@
-use alloc::borrow::Cow;
+use alloc::{borrow::Cow, vec::Vec};
+use n64::{ArchiveError, StaticData};
 use n64_math::Vec2;
 use zerocopy::LayoutVerified;
 
+#[cfg(not(target_vendor = "nintendo64"))]
+fn byteswap_u32_slice(data: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(data.len());
+
+    for part in data.chunks_exact(4) {
+        res.push(part[3]);
+        res.push(part[2]);
+        res.push(part[1]);
+        res.push(part[0]);
+    }
+
+    res
+}
+
 #[derive(Clone)]
 pub struct ModelData<'a> {
     pub verts: Cow<'a, [[f32; 3]]>,
     pub uvs: Cow<'a, [[f32; 2]]>,
     pub colors: Cow<'a, [u32]>,
+    pub normals: Cow<'a, [[f32; 3]]>,
     pub indices: Cow<'a, [[u8; 3]]>,
     pub size: Vec2,
 }
 
 pub struct StaticModelData {
-    pub verts: &'static [u8],
-    pub uvs: &'static [u8],
-    pub colors: &'static [u8],
-    pub indices: &'static [u8],
+    pub verts: StaticData,
+    pub uvs: StaticData,
+    pub colors: StaticData,
+    pub normals: StaticData,
+    pub indices: StaticData,
     pub size: Vec2,
+    // Empty for models without animations
+    pub animations: &'static [StaticAnimationData],
 }
 
 impl StaticModelData {
-    pub fn as_model_data(&self) -> ModelData {
+    pub fn as_model_data(&self) -> Result<ModelData, ArchiveError> {
         #[cfg(target_vendor = "nintendo64")]
         {
-            let verts = LayoutVerified::<_, [[f32; 3]]>::new_slice(self.verts)
-                .unwrap()
+            let verts = LayoutVerified::<_, [[f32; 3]]>::new_slice(self.verts.get()?)
+                .ok_or(ArchiveError::Corrupt)?
+                .into_slice();
+
+            let uvs = LayoutVerified::<_, [[f32; 2]]>::new_slice(self.uvs.get()?)
+                .ok_or(ArchiveError::Corrupt)?
                 .into_slice();
 
-            let uvs = LayoutVerified::<_, [[f32; 2]]>::new_slice(self.uvs)
-                .unwrap()
+            let colors = LayoutVerified::<_, [u32]>::new_slice(self.colors.get()?)
+                .ok_or(ArchiveError::Corrupt)?
                 .into_slice();
 
-            let colors = LayoutVerified::<_, [u32]>::new_slice(self.colors)
-                .unwrap()
+            let normals = LayoutVerified::<_, [[f32; 3]]>::new_slice(self.normals.get()?)
+                .ok_or(ArchiveError::Corrupt)?
                 .into_slice();
 
-            let indices = LayoutVerified::<_, [[u8; 3]]>::new_slice(self.indices)
-                .unwrap()
+            let indices = LayoutVerified::<_, [[u8; 3]]>::new_slice(self.indices.get()?)
+                .ok_or(ArchiveError::Corrupt)?
                 .into_slice();
 
-            ModelData {
+            Ok(ModelData {
                 verts: Cow::Borrowed(verts),
                 uvs: Cow::Borrowed(uvs),
                 colors: Cow::Borrowed(colors),
+                normals: Cow::Borrowed(normals),
                 indices: Cow::Borrowed(indices),
                 size: self.size,
-            }
+            })
         }
 
         #[cfg(not(target_vendor = "nintendo64"))]
         {
-            fn byteswap_u32_slice(data: &[u8]) -> Vec<u8> {
-                let mut res = Vec::with_capacity(data.len());
-
-                for part in data.chunks_exact(4) {
-                    res.push(part[3]);
-                    res.push(part[2]);
-                    res.push(part[1]);
-                    res.push(part[0]);
-                }
-
-                res
-            }
-
-            let verts_in = byteswap_u32_slice(self.verts);
-            let uvs_in = byteswap_u32_slice(self.uvs);
-            let colors_in = byteswap_u32_slice(self.colors);
-            let indices_in = self.indices;
+            let verts_in = byteswap_u32_slice(self.verts.get()?);
+            let uvs_in = byteswap_u32_slice(self.uvs.get()?);
+            let colors_in = byteswap_u32_slice(self.colors.get()?);
+            let normals_in = byteswap_u32_slice(self.normals.get()?);
+            let indices_in = self.indices.get()?;
 
             let verts = LayoutVerified::<_, [[f32; 3]]>::new_slice(verts_in.as_slice())
-                .unwrap()
+                .ok_or(ArchiveError::Corrupt)?
                 .into_slice()
                 .to_owned();
 
             let uvs = LayoutVerified::<_, [[f32; 2]]>::new_slice(uvs_in.as_slice())
-                .unwrap()
+                .ok_or(ArchiveError::Corrupt)?
                 .into_slice()
                 .to_owned();
 
             let colors = LayoutVerified::<_, [u32]>::new_slice(colors_in.as_slice())
-                .unwrap()
+                .ok_or(ArchiveError::Corrupt)?
+                .into_slice()
+                .to_owned();
+
+            let normals = LayoutVerified::<_, [[f32; 3]]>::new_slice(normals_in.as_slice())
+                .ok_or(ArchiveError::Corrupt)?
                 .into_slice()
                 .to_owned();
 
             let indices = LayoutVerified::<_, [[u8; 3]]>::new_slice(indices_in)
-                .unwrap()
+                .ok_or(ArchiveError::Corrupt)?
                 .into_slice();
 
-            ModelData {
+            Ok(ModelData {
                 verts: Cow::Owned(verts),
                 uvs: Cow::Owned(uvs),
                 colors: Cow::Owned(colors),
+                normals: Cow::Owned(normals),
                 indices: Cow::Borrowed(indices),
                 size: self.size,
-            }
+            })
         }
     }
 }
+
+#[derive(Clone)]
+pub struct AnimationData<'a> {
+    pub name: &'static str,
+    pub fps: f32,
+    pub frame_count: usize,
+    pub verts: Cow<'a, [[f32; 3]]>,
+    pub normals: Cow<'a, [[f32; 3]]>,
+}
+
+impl<'a> AnimationData<'a> {
+    pub fn duration(&self) -> f32 {
+        self.frame_count as f32 / self.fps
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.verts.len() / self.frame_count
+    }
+
+    pub fn frame_verts(&self, frame: usize) -> &[[f32; 3]] {
+        let count = self.vertex_count();
+        &self.verts[frame * count..(frame + 1) * count]
+    }
+
+    pub fn frame_normals(&self, frame: usize) -> &[[f32; 3]] {
+        let count = self.vertex_count();
+        &self.normals[frame * count..(frame + 1) * count]
+    }
+}
+
+#[derive(Clone)]
+pub struct AnimatedModelData<'a> {
+    pub model: ModelData<'a>,
+    pub animations: Vec<AnimationData<'a>>,
+}
+
+pub struct StaticAnimationData {
+    pub name: &'static str,
+    pub fps: f32,
+    pub frame_count: usize,
+    pub verts: StaticData,
+    pub normals: StaticData,
+}
+
+impl StaticAnimationData {
+    pub fn as_animation_data(&self) -> Result<AnimationData, ArchiveError> {
+        #[cfg(target_vendor = "nintendo64")]
+        {
+            let verts = LayoutVerified::<_, [[f32; 3]]>::new_slice(self.verts.get()?)
+                .ok_or(ArchiveError::Corrupt)?
+                .into_slice();
+
+            let normals = LayoutVerified::<_, [[f32; 3]]>::new_slice(self.normals.get()?)
+                .ok_or(ArchiveError::Corrupt)?
+                .into_slice();
+
+            Ok(AnimationData {
+                name: self.name,
+                fps: self.fps,
+                frame_count: self.frame_count,
+                verts: Cow::Borrowed(verts),
+                normals: Cow::Borrowed(normals),
+            })
+        }
+
+        #[cfg(not(target_vendor = "nintendo64"))]
+        {
+            let verts_in = byteswap_u32_slice(self.verts.get()?);
+            let normals_in = byteswap_u32_slice(self.normals.get()?);
+
+            let verts = LayoutVerified::<_, [[f32; 3]]>::new_slice(verts_in.as_slice())
+                .ok_or(ArchiveError::Corrupt)?
+                .into_slice()
+                .to_owned();
+
+            let normals = LayoutVerified::<_, [[f32; 3]]>::new_slice(normals_in.as_slice())
+                .ok_or(ArchiveError::Corrupt)?
+                .into_slice()
+                .to_owned();
+
+            Ok(AnimationData {
+                name: self.name,
+                fps: self.fps,
+                frame_count: self.frame_count,
+                verts: Cow::Owned(verts),
+                normals: Cow::Owned(normals),
+            })
+        }
+    }
+}
+
+impl StaticModelData {
+    pub fn as_animated_model_data(&self) -> Result<AnimatedModelData, ArchiveError> {
+        Ok(AnimatedModelData {
+            model: self.as_model_data()?,
+            animations: self
+                .animations
+                .iter()
+                .map(|animation| animation.as_animation_data())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
@@ -0,0 +1,266 @@
+use n64::{Controllers, StaticData};
+use n64_math::set_random_seed;
+
+// File layout: magic, version, then one frame after another until the end. All values are big endian
+const MAGIC: [u8; 4] = *b"LREC";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 8;
+const FRAME_SIZE: usize = 12;
+
+#[cfg(not(target_vendor = "nintendo64"))]
+const RECORD_ENV: &str = "LOKA_RECORD";
+#[cfg(not(target_vendor = "nintendo64"))]
+const REPLAY_ENV: &str = "LOKA_REPLAY";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ReplayFrame {
+    pub controller: u32,
+    pub seed: u32,
+    pub dt: f32,
+}
+
+impl ReplayFrame {
+    #[cfg(not(target_vendor = "nintendo64"))]
+    fn to_bytes(self) -> [u8; FRAME_SIZE] {
+        let mut res = [0; FRAME_SIZE];
+        res[0..4].copy_from_slice(&self.controller.to_be_bytes());
+        res[4..8].copy_from_slice(&self.seed.to_be_bytes());
+        res[8..12].copy_from_slice(&self.dt.to_bits().to_be_bytes());
+        res
+    }
+
+    fn from_bytes(data: &[u8]) -> Self {
+        let word = |offset: usize| {
+            u32::from_be_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ])
+        };
+
+        Self {
+            controller: word(0),
+            seed: word(4),
+            dt: f32::from_bits(word(8)),
+        }
+    }
+}
+
+fn header() -> [u8; HEADER_SIZE] {
+    let mut res = [0; HEADER_SIZE];
+    res[0..4].copy_from_slice(&MAGIC);
+    res[4..8].copy_from_slice(&VERSION.to_be_bytes());
+    res
+}
+
+pub struct Replay<'a> {
+    frames: &'a [u8],
+    frame: usize,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(data: &'a [u8]) -> Option<Self> {
+        if data.len() < HEADER_SIZE || data[..HEADER_SIZE] != header() {
+            return None;
+        }
+
+        Some(Self {
+            frames: &data[HEADER_SIZE..],
+            frame: 0,
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len() / FRAME_SIZE
+    }
+
+    pub fn next_frame(&mut self) -> Option<ReplayFrame> {
+        let start = self.frame * FRAME_SIZE;
+        let frame = self.frames.get(start..start + FRAME_SIZE)?;
+
+        self.frame += 1;
+
+        Some(ReplayFrame::from_bytes(frame))
+    }
+}
+
+#[cfg(not(target_vendor = "nintendo64"))]
+pub struct Recorder {
+    file: std::fs::File,
+}
+
+#[cfg(not(target_vendor = "nintendo64"))]
+impl Recorder {
+    pub fn create(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        use std::io::Write;
+
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(&header())?;
+
+        Ok(Self { file })
+    }
+
+    // Every frame is written right away since the emulator exits without unwinding
+    pub fn record(&mut self, frame: ReplayFrame) -> std::io::Result<()> {
+        use std::io::Write;
+
+        self.file.write_all(&frame.to_bytes())
+    }
+}
+
+pub enum InputSource {
+    Live,
+    #[cfg(not(target_vendor = "nintendo64"))]
+    Record(Recorder),
+    Replay(Replay<'static>),
+}
+
+impl InputSource {
+    // On the emulator LOKA_RECORD or LOKA_REPLAY can point to a file, otherwise the replay from the
+    // asset archive is used
+    pub fn new(rom_replay: Option<StaticData>) -> Self {
+        #[cfg(not(target_vendor = "nintendo64"))]
+        {
+            if let Some(path) = std::env::var_os(REPLAY_ENV) {
+                let data = std::fs::read(&path).expect("Unable to read replay");
+                let replay = Replay::new(Vec::leak(data)).expect("Invalid replay");
+                return InputSource::Replay(replay);
+            }
+
+            if let Some(path) = std::env::var_os(RECORD_ENV) {
+                let recorder = Recorder::create(&path).expect("Unable to create recording");
+                return InputSource::Record(recorder);
+            }
+        }
+
+        let data = match rom_replay.map(StaticData::get) {
+            Some(Ok(data)) => Some(data),
+            Some(Err(err)) => {
+                n64::debugln!("Replay not loaded: {:?}", err);
+                None
+            }
+            None => None,
+        };
+
+        match data.and_then(Replay::new) {
+            Some(replay) => InputSource::Replay(replay),
+            None => InputSource::Live,
+        }
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(self, InputSource::Replay(_))
+    }
+
+    // Call after the controllers have been updated, returns the dt to simulate the frame with
+    pub fn update(&mut self, controllers: &mut Controllers, dt: f32) -> f32 {
+        match self {
+            InputSource::Live => dt,
+            #[cfg(not(target_vendor = "nintendo64"))]
+            InputSource::Record(recorder) => {
+                let frame = ReplayFrame {
+                    controller: controllers.port(0).state(),
+                    seed: n64_math::random_seed(),
+                    dt,
+                };
+
+                if let Err(err) = recorder.record(frame) {
+                    n64::debugln!("Recording stopped: {}", err);
+                    *self = InputSource::Live;
+                }
+
+                dt
+            }
+            InputSource::Replay(replay) => match replay.next_frame() {
+                Some(frame) => {
+                    controllers.port_mut(0).set_state(frame.controller);
+                    set_random_seed(frame.seed);
+                    frame.dt
+                }
+                None => {
+                    n64::debugln!("Replay finished after {} frames", replay.frame_count());
+                    *self = InputSource::Live;
+                    dt
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAMES: [ReplayFrame; 3] = [
+        ReplayFrame {
+            controller: 0x8000_0000,
+            seed: 1,
+            dt: 1.0 / 60.0,
+        },
+        ReplayFrame {
+            controller: 0x0000_5020,
+            seed: 0xdead_beef,
+            dt: 1.0 / 30.0,
+        },
+        ReplayFrame {
+            controller: 0,
+            seed: 42,
+            dt: 0.0,
+        },
+    ];
+
+    fn recording(frames: &[ReplayFrame]) -> Vec<u8> {
+        let mut data = header().to_vec();
+
+        for frame in frames {
+            data.extend_from_slice(&frame.to_bytes());
+        }
+
+        data
+    }
+
+    #[test]
+    fn recorder_replay_roundtrip() {
+        let path = std::env::temp_dir().join(format!("replay_{}.lrec", std::process::id()));
+
+        let mut recorder = Recorder::create(&path).unwrap();
+
+        for frame in FRAMES {
+            recorder.record(frame).unwrap();
+        }
+
+        drop(recorder);
+
+        let data = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut replay = Replay::new(&data).unwrap();
+        assert_eq!(replay.frame_count(), FRAMES.len());
+
+        for frame in FRAMES {
+            assert_eq!(replay.next_frame(), Some(frame));
+        }
+
+        assert_eq!(replay.next_frame(), None);
+    }
+
+    #[test]
+    fn truncated_replay() {
+        let data = recording(&FRAMES);
+
+        assert!(Replay::new(&[]).is_none());
+        assert!(Replay::new(&data[..HEADER_SIZE - 1]).is_none());
+
+        let mut other_version = data.clone();
+        other_version[7] += 1;
+        assert!(Replay::new(&other_version).is_none());
+
+        // A frame that was cut off while it was written is not played back
+        let mut replay = Replay::new(&data[..data.len() - 1]).unwrap();
+        assert_eq!(replay.frame_count(), 2);
+        assert_eq!(replay.next_frame(), Some(FRAMES[0]));
+        assert_eq!(replay.next_frame(), Some(FRAMES[1]));
+        assert_eq!(replay.next_frame(), None);
+    }
+}
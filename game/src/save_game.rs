@@ -0,0 +1,75 @@
+use n64::{
+    save::{self, SaveData},
+    Eeprom,
+};
+use zerocopy::{AsBytes, FromBytes};
+
+// Every write blocks for a while, so new high scores are written at most this often
+const STORE_INTERVAL: f32 = 5.0;
+
+#[repr(C)]
+#[derive(Copy, Clone, Default, AsBytes, FromBytes)]
+pub struct SaveGame {
+    pub high_score: i32,
+}
+
+impl SaveData for SaveGame {
+    const VERSION: u16 = 1;
+}
+
+pub struct SaveState {
+    eeprom: Option<Eeprom>,
+    save_game: SaveGame,
+    dirty: bool,
+    time_since_store: f32,
+}
+
+impl SaveState {
+    pub fn load() -> Self {
+        let mut eeprom = Eeprom::new();
+
+        let save_game = match eeprom.as_mut().map(save::load::<SaveGame>) {
+            Some(Ok(save_game)) => save_game,
+            Some(Err(err)) => {
+                n64::debugln!("Save not loaded: {:?}", err);
+                SaveGame::default()
+            }
+            None => SaveGame::default(),
+        };
+
+        Self {
+            eeprom,
+            save_game,
+            dirty: false,
+            time_since_store: 0.0,
+        }
+    }
+
+    pub fn high_score(&self) -> i32 {
+        self.save_game.high_score
+    }
+
+    pub fn update(&mut self, score: i32, dt: f32) {
+        if score > self.save_game.high_score {
+            self.save_game.high_score = score;
+            self.dirty = true;
+        }
+
+        self.time_since_store += dt;
+
+        if self.dirty && self.time_since_store >= STORE_INTERVAL {
+            self.store();
+        }
+    }
+
+    pub fn store(&mut self) {
+        if let Some(eeprom) = &mut self.eeprom {
+            if let Err(err) = save::store(eeprom, &self.save_game) {
+                n64::debugln!("Save failed: {:?}", err);
+            }
+        }
+
+        self.dirty = false;
+        self.time_since_store = 0.0;
+    }
+}
@@ -0,0 +1,117 @@
+use n64::gfx::{CommandBuffer, PostEffect};
+use n64_math::{random_f32, vec2, Vec2};
+
+pub struct ScreenEffects {
+    fade_color: u32,
+    fade: f32,
+    fade_target: f32,
+    fade_speed: f32,
+    flash_color: u32,
+    flash_time: f32,
+    flash_duration: f32,
+    shake_strength: f32,
+    shake_time: f32,
+    shake_duration: f32,
+    desaturate: f32,
+}
+
+impl ScreenEffects {
+    pub const fn new() -> Self {
+        Self {
+            fade_color: 0x000000ff,
+            fade: 0.0,
+            fade_target: 0.0,
+            fade_speed: 0.0,
+            flash_color: 0xffffffff,
+            flash_time: 0.0,
+            flash_duration: 0.0,
+            shake_strength: 0.0,
+            shake_time: 0.0,
+            shake_duration: 0.0,
+            desaturate: 0.0,
+        }
+    }
+
+    pub fn set_fade(&mut self, color: u32, amount: f32) {
+        self.fade_color = color;
+        self.fade = amount;
+        self.fade_target = amount;
+    }
+
+    // Amount 0.0 is no fade and 1.0 is a solid color
+    pub fn fade_to(&mut self, color: u32, amount: f32, duration: f32) {
+        self.fade_color = color;
+        self.fade_target = amount;
+        self.fade_speed = if duration > 0.0 {
+            libm::fabsf(amount - self.fade) / duration
+        } else {
+            f32::MAX
+        };
+    }
+
+    pub fn flash(&mut self, color: u32, duration: f32) {
+        self.flash_color = color;
+        self.flash_time = duration;
+        self.flash_duration = duration;
+    }
+
+    // Strength is the largest offset in pixels, it decays over the duration
+    pub fn shake(&mut self, strength: f32, duration: f32) {
+        self.shake_strength = strength;
+        self.shake_time = duration;
+        self.shake_duration = duration;
+    }
+
+    pub fn set_desaturate(&mut self, amount: f32) {
+        self.desaturate = amount;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let step = self.fade_speed * dt;
+
+        self.fade = if self.fade < self.fade_target {
+            libm::fminf(self.fade + step, self.fade_target)
+        } else {
+            libm::fmaxf(self.fade - step, self.fade_target)
+        };
+
+        self.flash_time = libm::fmaxf(self.flash_time - dt, 0.0);
+        self.shake_time = libm::fmaxf(self.shake_time - dt, 0.0);
+    }
+
+    pub fn screen_offset(&self) -> Vec2 {
+        if self.shake_time <= 0.0 {
+            return Vec2::ZERO;
+        }
+
+        let strength = self.shake_strength * self.shake_time / self.shake_duration;
+
+        strength * vec2(2.0 * random_f32() - 1.0, 2.0 * random_f32() - 1.0)
+    }
+
+    pub fn draw(&self, cb: &mut CommandBuffer) {
+        n64::scope!("screen_effects::draw");
+
+        cb.add_post_effect(PostEffect::Desaturate {
+            amount: self.desaturate,
+        });
+
+        if self.flash_time > 0.0 {
+            cb.add_post_effect(PostEffect::Fade {
+                color: self.flash_color,
+                amount: self.flash_time / self.flash_duration,
+            });
+        }
+
+        cb.add_post_effect(PostEffect::Fade {
+            color: self.fade_color,
+            amount: self.fade,
+        });
+    }
+}
+
+impl Default for ScreenEffects {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,16 +1,17 @@
+use n64::{ArchiveError, StaticData};
 use zerocopy::LayoutVerified;
 
 pub struct StaticSoundData {
-    pub data: &'static [u8],
+    pub data: StaticData,
 }
 
 impl StaticSoundData {
-    pub fn as_sound_data(&self) -> SoundData {
-        let samples = LayoutVerified::<_, [i16]>::new_slice(self.data)
-            .unwrap()
+    pub fn as_sound_data(&self) -> Result<SoundData, ArchiveError> {
+        let samples = LayoutVerified::<_, [i16]>::new_slice(self.data.get()?)
+            .ok_or(ArchiveError::Corrupt)?
             .into_slice();
 
-        SoundData { samples }
+        Ok(SoundData { samples })
     }
 }
 
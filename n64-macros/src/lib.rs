@@ -3,41 +3,28 @@
 #[cfg(target_vendor = "nintendo64")]
 mod inner {
 
-    use zerocopy::AsBytes;
+    use n64_types::MessageType;
 
-    const MESSAGE_BUFFER_SIZE: usize = 31;
+    const MESSAGE_BUFFER_SIZE: usize = 128;
 
-    #[repr(C, packed)]
-    #[derive(AsBytes)]
-    pub struct DebugWriteMessageBuffer {
-        buffer_message_header: u8,
-        buffer: [u8; MESSAGE_BUFFER_SIZE],
-    }
-
-    n64_types::static_assert!(core::mem::size_of::<DebugWriteMessageBuffer>() == 32);
-
-    #[repr(C, align(16))]
     pub struct DebugWrite {
-        b: DebugWriteMessageBuffer,
+        buffer: [u8; MESSAGE_BUFFER_SIZE],
         cursor: u16,
     }
 
     pub static GLOBAL_DEBUG_PRINT: spin::Mutex<DebugWrite> = spin::Mutex::new(DebugWrite {
-        b: DebugWriteMessageBuffer {
-            buffer_message_header: n64_types::MESSAGE_MAGIC_PRINT,
-            buffer: [0; MESSAGE_BUFFER_SIZE],
-        },
+        buffer: [0; MESSAGE_BUFFER_SIZE],
         cursor: 0,
     });
 
     impl core::fmt::Write for DebugWrite {
         fn write_str(&mut self, s: &str) -> core::fmt::Result {
             for byte in s.as_bytes() {
-                self.b.buffer[self.cursor as usize] = *byte;
+                self.buffer[self.cursor as usize] = *byte;
                 self.cursor += 1;
 
                 if self.cursor == MESSAGE_BUFFER_SIZE as u16 {
-                    core::assert!(n64_sys::ed::usb_write(self.b.as_bytes()));
+                    core::assert!(n64_sys::ed::send(MessageType::Print, &self.buffer));
                     self.cursor = 0;
 
                     let start = n64_sys::sys::current_time_us();
@@ -58,10 +45,12 @@ mod inner {
 
     pub fn debugflush() {
         let mut lock = GLOBAL_DEBUG_PRINT.lock();
-        let cursor = lock.cursor;
+        let cursor = lock.cursor as usize;
         if cursor > 0 {
-            lock.b.buffer[(cursor as usize)..].fill(b'\r');
-            core::assert!(n64_sys::ed::usb_write(lock.b.as_bytes()));
+            core::assert!(n64_sys::ed::send(
+                MessageType::Print,
+                &lock.buffer[..cursor]
+            ));
             lock.cursor = 0;
         }
 
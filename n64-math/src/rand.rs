@@ -15,6 +15,18 @@ impl Rng {
         }
     }
 
+    #[inline]
+    pub const fn new(seed: u32) -> Rng {
+        Rng {
+            seed: Wrapping(seed),
+        }
+    }
+
+    #[inline]
+    pub fn seed(&self) -> u32 {
+        self.seed.0
+    }
+
     #[inline]
     pub fn next_u32(&mut self) -> u32 {
         self.seed = self.seed * Wrapping(214013) + Wrapping(2531011);
@@ -45,6 +57,17 @@ impl Rng {
     }
 }
 
+// The seed is the whole state of the global generator, restoring it repeats the same sequence
+#[inline]
+pub fn random_seed() -> u32 {
+    GLOBAL_RNG.lock().seed()
+}
+
+#[inline]
+pub fn set_random_seed(seed: u32) {
+    *GLOBAL_RNG.lock() = Rng::new(seed);
+}
+
 #[inline]
 pub fn random_u32() -> u32 {
     GLOBAL_RNG.lock().next_u32()
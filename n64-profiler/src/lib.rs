@@ -5,15 +5,12 @@ mod inner {
 
     pub use n64_profiler_macro;
 
-    use core::marker::PhantomData;
+    use core::{marker::PhantomData, mem::size_of};
     use n64_sys::sys::current_time_us;
-    use n64_types::{ProfilerMessageBuffer, ScopeData, MESSAGE_MAGIC_PROFILER};
+    use n64_types::{MessageType, ProfilerMessageBuffer, ScopeData, FRAME_MAX_PAYLOAD};
     use zerocopy::AsBytes;
 
-    #[repr(C, align(16))]
-    pub struct ProfilerMessage {
-        b: ProfilerMessageBuffer,
-    }
+    const SCOPES_PER_MESSAGE: usize = FRAME_MAX_PAYLOAD / size_of::<ProfilerMessageBuffer>();
 
     pub struct Profiler {
         scopes: [ScopeData; 128],
@@ -48,19 +45,27 @@ mod inner {
             self.current_depth -= 1;
         }
 
+        // As many scopes as fit are packed into each message
         #[inline]
         pub fn frame(&mut self) {
+            let mut payload = [0; SCOPES_PER_MESSAGE * size_of::<ProfilerMessageBuffer>()];
+            let mut len = 0;
+
             for i in 0..self.current_index {
-                let msg = ProfilerMessage {
-                    b: ProfilerMessageBuffer {
-                        message_header_buffer: MESSAGE_MAGIC_PROFILER,
-                        scope: self.scopes[i as usize],
-                        index: i,
-                        count: self.current_index,
-                    },
+                let msg = ProfilerMessageBuffer {
+                    scope: self.scopes[i as usize],
+                    index: i,
+                    count: self.current_index,
                 };
 
-                core::assert!(n64_sys::ed::usb_write(msg.b.as_bytes()));
+                payload[len..len + size_of::<ProfilerMessageBuffer>()]
+                    .copy_from_slice(msg.as_bytes());
+                len += size_of::<ProfilerMessageBuffer>();
+
+                if len == payload.len() || i == self.current_index - 1 {
+                    core::assert!(n64_sys::ed::send(MessageType::Profiler, &payload[..len]));
+                    len = 0;
+                }
             }
 
             self.current_index = 0;
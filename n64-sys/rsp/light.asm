@@ -0,0 +1,103 @@
+arch n64.rsp
+endian msb
+output "light.bin", create
+
+include "lib/n64.inc"
+include "lib/n64_rsp.inc"
+
+base $0000
+origin $0000
+
+// Per vertex directional lighting, uploaded to DMEM by the cpu and read back when halted.
+//
+// DMEM layout, all values are s16 and every block is 16 byte aligned
+//   0x00 u32 group count (8 vertices per group)
+//   0x04 u32 light count
+//   0x10 ambient [r, g, b, 0, 0, 0, 0, 0]         (s1.15)
+//   0x20 light   [x, y, z, r, g, b, 0, 0] * 4     (s1.15, x y z points towards the light)
+//   0x80 groups  [nx[8], ny[8], nz[8], r[8], g[8], b[8]]
+//
+// Normals are s1.15 and colors are 0-255, the lit colors are written back over the input colors.
+
+constant group_count = 0x00
+constant light_count = 0x04
+constant ambient_start = 0x10
+constant light_start = 0x20
+constant group_start = 0x80
+constant group_size = 96
+
+align(8)
+
+start:
+    lw t0, group_count(0)  // t0 = groups left
+    lw t1, light_count(0)  // t1 = light count
+    li t2, group_start     // t2 = current group
+
+    vxor v0, v0, v0              // v0 = 0
+    vaddc v0, v0, v0             // Clear the carry flags used by vadd
+    lqv v1[e0], ambient_start(0) // v1 = ambient color
+
+process_group:
+    beq t0, 0, return
+    nop
+
+    lqv v2[e0], 0(t2)  // nx
+    lqv v3[e0], 16(t2) // ny
+    lqv v4[e0], 32(t2) // nz
+
+    // Intensity starts at the ambient color
+    vadd v5, v0, v1[e8]  // red
+    vadd v6, v0, v1[e9]  // green
+    vadd v7, v0, v1[e10] // blue
+
+    li t3, light_start // t3 = current light
+    move t4, t1        // t4 = lights left
+
+process_light:
+    beq t4, 0, apply_intensity
+    nop
+
+    lqv v8[e0], 0(t3) // v8 = light
+
+    // n . l
+    vmulf v9, v2, v8[e8]
+    vmacf v9, v3, v8[e9]
+    vmacf v9, v4, v8[e10]
+
+    // Faces pointing away from the light gets nothing
+    vge v9, v9, v0
+
+    // Saturating add clamps the intensity to 1.0
+    vmulf v10, v9, v8[e11]
+    vadd v5, v5, v10
+    vmulf v10, v9, v8[e12]
+    vadd v6, v6, v10
+    vmulf v10, v9, v8[e13]
+    vadd v7, v7, v10
+
+    addi t3, t3, 16
+    addi t4, t4, -1
+    j process_light
+    nop
+
+apply_intensity:
+    lqv v11[e0], 48(t2) // r
+    lqv v12[e0], 64(t2) // g
+    lqv v13[e0], 80(t2) // b
+
+    vmulf v11, v11, v5
+    vmulf v12, v12, v6
+    vmulf v13, v13, v7
+
+    sqv v11[e0], 48(t2)
+    sqv v12[e0], 64(t2)
+    sqv v13[e0], 80(t2)
+
+    addi t2, t2, group_size
+    addi t0, t0, -1
+    j process_group
+    nop
+
+return:
+    break
+    nop
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
-use crate::{pi, sys::current_time_us};
+use crate::{mi, pi, sys::current_time_us};
+use n64_types::{encode_frame, MessageType, FRAME_MAX_SIZE};
 
 const REG_BASE: usize = 0xBF80_0000;
 
@@ -28,6 +29,13 @@ const USB_CMD_RD: u32 = USB_LE_CFG | USB_LE_CTR | USB_CFG_RD | USB_CFG_ACT;
 const USB_CMD_WR_NOP: u32 = USB_LE_CFG | USB_LE_CTR | USB_CFG_WR;
 const USB_CMD_WR: u32 = USB_LE_CFG | USB_LE_CTR | USB_CFG_WR | USB_CFG_ACT;
 
+pub const USB_BLOCK_SIZE: usize = 512;
+
+#[repr(C, align(16))]
+struct FrameBuffer([u8; FRAME_MAX_SIZE]);
+
+static mut FRAME_BUFFER: FrameBuffer = FrameBuffer([0; FRAME_MAX_SIZE]);
+
 fn register_write(reg: u16, val: u32) {
     unsafe { pi::write(&val as *const u32 as _, 4, REG_BASE + reg as usize) };
 }
@@ -53,7 +61,7 @@ pub fn init() {
     }
 }
 
-fn can_read() -> bool {
+pub fn can_read() -> bool {
     register_read(REG_USB_CFG) & (USB_STA_PWR | USB_STA_RXF) == USB_STA_PWR
 }
 
@@ -123,3 +131,19 @@ pub fn usb_write(src: &[u8]) -> bool {
 
     true
 }
+
+// Transfers get corrupted without a pause between them, like in debugln
+pub fn wait_for_usb() {
+    let start = current_time_us();
+    while current_time_us() - start < 2000 {}
+}
+
+// Sends one framed message to the deploy tool, payloads larger than FRAME_MAX_PAYLOAD are rejected
+pub fn send(message_type: MessageType, payload: &[u8]) -> bool {
+    mi::free(|| unsafe {
+        match encode_frame(message_type, payload, &mut FRAME_BUFFER.0) {
+            Ok(len) => usb_write(&FRAME_BUFFER.0[..len]),
+            Err(_) => false,
+        }
+    })
+}
@@ -0,0 +1,134 @@
+use crate::{
+    pak::{block_to_bytes, bytes_to_block, PIF_NO_RESPONSE},
+    si::Pif,
+    sys::current_time_us,
+};
+
+pub const EEPROM_BLOCK_SIZE: usize = 8;
+
+// The cartridge EEPROM answers on the channel after the four controllers
+pub(crate) const EEPROM_CHANNEL: usize = 4;
+
+pub(crate) const EEPROM_CMD_STATUS: u8 = 0x00;
+pub(crate) const EEPROM_CMD_READ: u8 = 0x04;
+pub(crate) const EEPROM_CMD_WRITE: u8 = 0x05;
+
+pub(crate) const EEPROM_ID_4K: u16 = 0x0080;
+pub(crate) const EEPROM_ID_16K: u16 = 0x00c0;
+
+// Set in the status while a write is in progress, around 15 ms per block
+const EEPROM_STATUS_BUSY: u8 = 0x80;
+const EEPROM_BUSY_TIMEOUT_US: i64 = 100_000;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum EepromType {
+    Eeprom4k,
+    Eeprom16k,
+}
+
+impl EepromType {
+    #[inline]
+    pub const fn blocks(self) -> usize {
+        match self {
+            EepromType::Eeprom4k => 64,
+            EepromType::Eeprom16k => 256,
+        }
+    }
+
+    #[inline]
+    pub const fn size(self) -> usize {
+        self.blocks() * EEPROM_BLOCK_SIZE
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum EepromError {
+    NoEeprom,
+    Timeout,
+}
+
+fn exec_eeprom_command(
+    pif: &mut impl Pif,
+    command: &[u8],
+    response: &mut [u8],
+) -> Result<(), EepromError> {
+    // Zero bytes skip the controller channels
+    let mut block = [0; 64];
+
+    let start = EEPROM_CHANNEL;
+    let command_start = start + 2;
+    let response_start = command_start + command.len();
+    let end = response_start + response.len();
+
+    block[start] = command.len() as u8;
+    block[start + 1] = response.len() as u8;
+    block[command_start..response_start].copy_from_slice(command);
+    block[end] = 0xfe;
+    block[63] = 1;
+
+    let mut out = [0; 8];
+    pif.exec(&bytes_to_block(&block), &mut out);
+    let out = block_to_bytes(&out);
+
+    if out[start + 1] & PIF_NO_RESPONSE != 0 {
+        return Err(EepromError::NoEeprom);
+    }
+
+    response.copy_from_slice(&out[response_start..end]);
+
+    Ok(())
+}
+
+fn status(pif: &mut impl Pif) -> Result<[u8; 3], EepromError> {
+    let mut response = [0; 3];
+    exec_eeprom_command(pif, &[EEPROM_CMD_STATUS], &mut response)?;
+    Ok(response)
+}
+
+fn wait_ready(pif: &mut impl Pif) -> Result<(), EepromError> {
+    let start = current_time_us();
+
+    while status(pif)?[2] & EEPROM_STATUS_BUSY != 0 {
+        if current_time_us() > start + EEPROM_BUSY_TIMEOUT_US {
+            return Err(EepromError::Timeout);
+        }
+    }
+
+    Ok(())
+}
+
+#[inline]
+pub fn detect(pif: &mut impl Pif) -> Option<EepromType> {
+    let response = status(pif).ok()?;
+
+    match u16::from_be_bytes([response[0], response[1]]) {
+        EEPROM_ID_4K => Some(EepromType::Eeprom4k),
+        EEPROM_ID_16K => Some(EepromType::Eeprom16k),
+        _ => None,
+    }
+}
+
+#[inline]
+pub fn read_block(
+    pif: &mut impl Pif,
+    block: u8,
+    data: &mut [u8; EEPROM_BLOCK_SIZE],
+) -> Result<(), EepromError> {
+    wait_ready(pif)?;
+    exec_eeprom_command(pif, &[EEPROM_CMD_READ, block], data)
+}
+
+#[inline]
+pub fn write_block(
+    pif: &mut impl Pif,
+    block: u8,
+    data: &[u8; EEPROM_BLOCK_SIZE],
+) -> Result<(), EepromError> {
+    let mut command = [0; EEPROM_BLOCK_SIZE + 2];
+    command[0] = EEPROM_CMD_WRITE;
+    command[1] = block;
+    command[2..].copy_from_slice(data);
+
+    wait_ready(pif)?;
+    exec_eeprom_command(pif, &command, &mut [0])
+}
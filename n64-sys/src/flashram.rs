@@ -0,0 +1,141 @@
+use crate::{pi, sys::current_time_us};
+
+// 1 Mbit FlashRAM in PI domain 2, programmed a page at a time and erased a sector at a time
+pub const FLASHRAM_ADDRESS: usize = 0x0800_0000;
+pub const FLASHRAM_SIZE: usize = 0x20000;
+pub const FLASHRAM_PAGE_SIZE: usize = 128;
+pub const FLASHRAM_SECTOR_SIZE: usize = 0x4000;
+
+const FLASHRAM_COMMAND: usize = FLASHRAM_ADDRESS + 0x10000;
+
+const CMD_READ_MODE: u32 = 0xf000_0000;
+const CMD_STATUS_MODE: u32 = 0xe100_0000;
+const CMD_SECTOR_ERASE: u32 = 0x4b00_0000;
+const CMD_ERASE_EXECUTE: u32 = 0x7800_0000;
+const CMD_CLEAR_STATUS: u32 = 0xd200_0000;
+const CMD_WRITE_BUFFER: u32 = 0xb400_0000;
+const CMD_PROGRAM: u32 = 0xa500_0000;
+
+const STATUS_WRITE_BUSY: u32 = 0x01;
+const STATUS_ERASE_BUSY: u32 = 0x02;
+const STATUS_WRITE_OK: u32 = 0x04;
+const STATUS_ERASE_OK: u32 = 0x08;
+
+// A sector erase takes a few hundred ms, programming a page only a few
+const FLASHRAM_BUSY_TIMEOUT_US: i64 = 1_000_000;
+
+// First word of the silicon id read in status mode
+const FLASHRAM_ID: u32 = 0x1111_8001;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FlashRamError {
+    EraseFailed,
+    ProgramFailed,
+    Timeout,
+}
+
+// PI DMA needs 8 byte aligned RAM
+#[repr(C, align(8))]
+struct DmaBuffer([u8; FLASHRAM_PAGE_SIZE]);
+
+#[inline]
+fn status() -> u32 {
+    pi::io_write(FLASHRAM_COMMAND, CMD_STATUS_MODE);
+    pi::io_read(FLASHRAM_ADDRESS) & 0xff
+}
+
+#[inline]
+fn clear_status() {
+    pi::io_write(FLASHRAM_COMMAND, CMD_CLEAR_STATUS);
+    pi::io_write(FLASHRAM_ADDRESS, 0);
+}
+
+fn wait_while(busy: u32) -> Result<u32, FlashRamError> {
+    let start = current_time_us();
+
+    loop {
+        let status = status();
+
+        if status & busy == 0 {
+            return Ok(status);
+        }
+
+        if current_time_us() > start + FLASHRAM_BUSY_TIMEOUT_US {
+            return Err(FlashRamError::Timeout);
+        }
+    }
+}
+
+pub fn detect() -> bool {
+    let mut buffer = DmaBuffer([0; FLASHRAM_PAGE_SIZE]);
+
+    pi::io_write(FLASHRAM_COMMAND, CMD_STATUS_MODE);
+    unsafe { pi::read_raw(buffer.0.as_mut_ptr(), 8, FLASHRAM_ADDRESS) };
+
+    u32::from_be_bytes(buffer.0[..4].try_into().unwrap()) == FLASHRAM_ID
+}
+
+// Offset and length have to be even
+pub fn read(offset: usize, data: &mut [u8]) {
+    assert!(offset % 2 == 0 && data.len() % 2 == 0);
+    assert!(offset + data.len() <= FLASHRAM_SIZE);
+
+    let mut buffer = DmaBuffer([0; FLASHRAM_PAGE_SIZE]);
+
+    pi::io_write(FLASHRAM_COMMAND, CMD_READ_MODE);
+
+    for (i, chunk) in data.chunks_mut(FLASHRAM_PAGE_SIZE).enumerate() {
+        // The read mode addresses the array in 16 bit words
+        let address = FLASHRAM_ADDRESS + (offset + i * FLASHRAM_PAGE_SIZE) / 2;
+
+        unsafe { pi::read_raw(buffer.0.as_mut_ptr(), chunk.len() as u32, address) };
+
+        chunk.copy_from_slice(&buffer.0[..chunk.len()]);
+    }
+}
+
+// Sets every byte in the sector to 0xff
+pub fn erase_sector(sector: usize) -> Result<(), FlashRamError> {
+    assert!(sector < FLASHRAM_SIZE / FLASHRAM_SECTOR_SIZE);
+
+    let page = sector * FLASHRAM_SECTOR_SIZE / FLASHRAM_PAGE_SIZE;
+
+    pi::io_write(FLASHRAM_COMMAND, CMD_SECTOR_ERASE | page as u32);
+    pi::io_write(FLASHRAM_COMMAND, CMD_ERASE_EXECUTE);
+
+    let status = wait_while(STATUS_ERASE_BUSY);
+    clear_status();
+
+    if status? & STATUS_ERASE_OK != 0 {
+        Ok(())
+    } else {
+        Err(FlashRamError::EraseFailed)
+    }
+}
+
+// Programming can only clear bits, the page has to be erased first
+pub fn program_page(page: usize, data: &[u8; FLASHRAM_PAGE_SIZE]) -> Result<(), FlashRamError> {
+    assert!(page < FLASHRAM_SIZE / FLASHRAM_PAGE_SIZE);
+
+    let buffer = DmaBuffer(*data);
+
+    pi::io_write(FLASHRAM_COMMAND, CMD_WRITE_BUFFER);
+    unsafe {
+        pi::write(
+            buffer.0.as_ptr(),
+            FLASHRAM_PAGE_SIZE as u32,
+            FLASHRAM_ADDRESS,
+        )
+    };
+
+    pi::io_write(FLASHRAM_COMMAND, CMD_PROGRAM | page as u32);
+
+    let status = wait_while(STATUS_WRITE_BUSY);
+    clear_status();
+
+    if status? & STATUS_WRITE_OK != 0 {
+        Ok(())
+    } else {
+        Err(FlashRamError::ProgramFailed)
+    }
+}
@@ -5,9 +5,18 @@
 
 pub mod ai;
 pub mod ed;
+pub mod eeprom;
+pub mod flashram;
+pub mod mi;
+pub mod pak;
 pub mod pi;
+pub mod pi_queue;
 pub mod rdp;
 pub mod rsp;
 pub mod si;
+pub mod sram;
 pub mod sys;
 pub mod vi;
+
+#[cfg(not(target_vendor = "nintendo64"))]
+pub mod mock_pif;
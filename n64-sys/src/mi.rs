@@ -0,0 +1,286 @@
+#![cfg_attr(not(target_vendor = "nintendo64"), allow(unused))]
+
+use core::{
+    arch::asm,
+    ops::{Deref, DerefMut},
+    ptr::{read_volatile, write_volatile},
+};
+use spin::{Mutex, MutexGuard};
+
+const MI_BASE: usize = 0xA430_0000;
+
+const MI_MODE: *mut usize = (MI_BASE) as _;
+const MI_INTR: *const usize = (MI_BASE + 0x08) as _;
+const MI_INTR_MASK: *mut usize = (MI_BASE + 0x0C) as _;
+
+const MI_MODE_CLEAR_DP_INTR: usize = 0x0800;
+
+// Registers that acknowledge the interrupt of each interface when written
+const SP_STATUS: *mut usize = 0xA404_0010_usize as _;
+const SI_STATUS: *mut usize = 0xA480_0018_usize as _;
+const AI_STATUS: *mut usize = 0xA450_000C_usize as _;
+const VI_CURRENT: *mut usize = 0xA440_0010_usize as _;
+const PI_STATUS: *mut usize = 0xA460_0010_usize as _;
+
+const SP_STATUS_CLEAR_INTR: usize = 0x0008;
+const PI_STATUS_CLEAR_INTR: usize = 0x0002;
+
+// Cop0 status and cause bits
+const STATUS_IE: u32 = 0x0001;
+const STATUS_IM_RCP: u32 = 0x0400;
+const CAUSE_IP_RCP: u32 = 0x0400;
+const CAUSE_EXC_CODE_SHIFT: u32 = 2;
+const CAUSE_EXC_CODE_MASK: u32 = 0x1f;
+const EXC_CODE_INTERRUPT: u32 = 0;
+
+// In the order of the bits in MI_INTR
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Interrupt {
+    Sp = 0,
+    Si = 1,
+    Ai = 2,
+    Vi = 3,
+    Pi = 4,
+    Dp = 5,
+}
+
+const INTERRUPTS: [Interrupt; 6] = [
+    Interrupt::Sp,
+    Interrupt::Si,
+    Interrupt::Ai,
+    Interrupt::Vi,
+    Interrupt::Pi,
+    Interrupt::Dp,
+];
+
+// Saved by the exception vector in entrypoint.s, the layout has to match the offsets there
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct ExceptionFrame {
+    pub gpr: [u64; 32],
+    pub hi: u64,
+    pub lo: u64,
+    pub status: u32,
+    pub cause: u32,
+    pub epc: u32,
+    pub bad_vaddr: u32,
+    pub fcsr: u32,
+    _pad: u32,
+    pub fpr: [u64; 16],
+}
+
+impl ExceptionFrame {
+    #[inline]
+    pub fn exception_code(&self) -> u32 {
+        (self.cause >> CAUSE_EXC_CODE_SHIFT) & CAUSE_EXC_CODE_MASK
+    }
+
+    #[inline]
+    pub fn exception_name(&self) -> &'static str {
+        match self.exception_code() {
+            0 => "Interrupt",
+            1 => "TLB modification",
+            2 => "TLB miss on load",
+            3 => "TLB miss on store",
+            4 => "Address error on load",
+            5 => "Address error on store",
+            6 => "Bus error on fetch",
+            7 => "Bus error on data",
+            8 => "Syscall",
+            9 => "Breakpoint",
+            10 => "Reserved instruction",
+            11 => "Coprocessor unusable",
+            12 => "Arithmetic overflow",
+            13 => "Trap",
+            15 => "Floating point",
+            23 => "Watch",
+            _ => "Unknown",
+        }
+    }
+}
+
+static mut HANDLERS: [Option<fn()>; INTERRUPTS.len()] = [None; INTERRUPTS.len()];
+static mut EXCEPTION_HANDLER: Option<fn(&mut ExceptionFrame)> = None;
+
+#[inline]
+fn read_status() -> u32 {
+    #[cfg(target_vendor = "nintendo64")]
+    unsafe {
+        let status;
+        asm!("mfc0 {}, $12", "nop", out(reg) status);
+        status
+    }
+
+    #[cfg(not(target_vendor = "nintendo64"))]
+    0
+}
+
+#[inline]
+fn write_status(status: u32) {
+    #[cfg(target_vendor = "nintendo64")]
+    unsafe {
+        asm!("mtc0 {}, $12", "nop", in(reg) status);
+    }
+}
+
+// Masks everything in the MI and enables the RCP interrupt line in the cpu. Handlers are enabled
+// one at a time with enable
+pub fn init() {
+    unsafe {
+        write_volatile(MI_INTR_MASK, 0b0101_0101_0101);
+
+        for interrupt in INTERRUPTS {
+            acknowledge(interrupt);
+        }
+    }
+
+    write_status(read_status() | STATUS_IM_RCP | STATUS_IE);
+}
+
+unsafe fn acknowledge(interrupt: Interrupt) {
+    match interrupt {
+        Interrupt::Sp => write_volatile(SP_STATUS, SP_STATUS_CLEAR_INTR),
+        Interrupt::Si => write_volatile(SI_STATUS, 0),
+        Interrupt::Ai => write_volatile(AI_STATUS, 0),
+        Interrupt::Vi => write_volatile(VI_CURRENT, 0),
+        Interrupt::Pi => write_volatile(PI_STATUS, PI_STATUS_CLEAR_INTR),
+        Interrupt::Dp => write_volatile(MI_MODE, MI_MODE_CLEAR_DP_INTR),
+    }
+}
+
+// Handlers run in the exception handler with interrupts disabled, so they should be short
+#[inline]
+pub fn register(interrupt: Interrupt, handler: fn()) {
+    free(|| unsafe { HANDLERS[interrupt as usize] = Some(handler) });
+}
+
+#[inline]
+pub fn unregister(interrupt: Interrupt) {
+    free(|| unsafe { HANDLERS[interrupt as usize] = None });
+}
+
+// Each interrupt has a clear and a set bit in the mask register
+#[inline]
+pub fn enable(interrupt: Interrupt) {
+    unsafe { write_volatile(MI_INTR_MASK, 0b10 << (2 * interrupt as usize)) };
+}
+
+#[inline]
+pub fn disable(interrupt: Interrupt) {
+    unsafe { write_volatile(MI_INTR_MASK, 0b01 << (2 * interrupt as usize)) };
+}
+
+#[inline]
+pub fn is_enabled(interrupt: Interrupt) -> bool {
+    unsafe { read_volatile(MI_INTR_MASK as *const usize) & (1 << interrupt as usize) != 0 }
+}
+
+// Called for every exception that is not an interrupt, like address errors. Without a handler the
+// cpu stops in an endless loop
+#[inline]
+pub fn set_exception_handler(handler: fn(&mut ExceptionFrame)) {
+    free(|| unsafe { EXCEPTION_HANDLER = Some(handler) });
+}
+
+fn dispatch_interrupts() {
+    let pending = unsafe { read_volatile(MI_INTR) & read_volatile(MI_INTR_MASK as *const usize) };
+
+    for interrupt in INTERRUPTS {
+        if pending & (1 << interrupt as usize) == 0 {
+            continue;
+        }
+
+        unsafe {
+            acknowledge(interrupt);
+
+            if let Some(handler) = HANDLERS[interrupt as usize] {
+                handler();
+            }
+        }
+    }
+}
+
+// Entered from the exception vector in entrypoint.s with the saved registers, which are restored
+// from the frame when it returns
+#[no_mangle]
+extern "C" fn __n64_exception(frame: &mut ExceptionFrame) {
+    if frame.exception_code() == EXC_CODE_INTERRUPT {
+        if frame.cause & CAUSE_IP_RCP != 0 {
+            dispatch_interrupts();
+        }
+
+        return;
+    }
+
+    match unsafe { EXCEPTION_HANDLER } {
+        Some(handler) => handler(frame),
+        #[allow(clippy::empty_loop)]
+        None => loop {},
+    }
+}
+
+// Disables interrupts until it is dropped, nested sections keep them disabled until the outermost
+// one ends
+pub struct CriticalSection {
+    enabled: bool,
+}
+
+impl CriticalSection {
+    #[inline]
+    pub fn enter() -> Self {
+        let status = read_status();
+        write_status(status & !STATUS_IE);
+
+        Self {
+            enabled: status & STATUS_IE != 0,
+        }
+    }
+}
+
+impl Drop for CriticalSection {
+    #[inline]
+    fn drop(&mut self) {
+        if self.enabled {
+            write_status(read_status() | STATUS_IE);
+        }
+    }
+}
+
+#[inline]
+pub fn free<R>(f: impl FnOnce() -> R) -> R {
+    let _section = CriticalSection::enter();
+    f()
+}
+
+// A spin::Mutex shared with interrupt handlers has to be locked with interrupts disabled, otherwise
+// a handler that interrupts the owner spins forever
+pub struct InterruptGuard<'a, T> {
+    guard: MutexGuard<'a, T>,
+    _section: CriticalSection,
+}
+
+#[inline]
+pub fn lock<T>(mutex: &Mutex<T>) -> InterruptGuard<'_, T> {
+    let section = CriticalSection::enter();
+
+    InterruptGuard {
+        guard: mutex.lock(),
+        _section: section,
+    }
+}
+
+impl<'a, T> Deref for InterruptGuard<'a, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<'a, T> DerefMut for InterruptGuard<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
@@ -0,0 +1,359 @@
+use crate::{
+    eeprom::{
+        EepromType, EEPROM_BLOCK_SIZE, EEPROM_CHANNEL, EEPROM_CMD_READ, EEPROM_CMD_STATUS,
+        EEPROM_CMD_WRITE, EEPROM_ID_16K, EEPROM_ID_4K,
+    },
+    pak::{
+        address_crc, block_to_bytes, bytes_to_block, data_crc, PAK_BLOCK_SIZE, PAK_CMD_READ,
+        PAK_CMD_WRITE, PAK_SIZE, PIF_NO_RESPONSE, RUMBLE_MOTOR_ADDRESS, RUMBLE_PROBE_ADDRESS,
+    },
+    si::Pif,
+};
+
+const CMD_STATUS: u8 = 0x00;
+const CMD_READ_BUTTONS: u8 = 0x01;
+const CMD_RESET: u8 = 0xff;
+
+const CONTROLLER_TYPE: [u8; 2] = [0x05, 0x00];
+const STATUS_ACCESSORY_PRESENT: u8 = 0x01;
+
+#[derive(Copy, Clone, Default)]
+struct MockRumblePak {
+    probed: bool,
+    on: bool,
+}
+
+// Executes PIF command blocks against four emulated controllers with optional Controller Paks
+// and a cartridge EEPROM
+pub struct MockPif {
+    connected: [bool; 4],
+    buttons: [u32; 4],
+    paks: [Option<[u8; PAK_SIZE]>; 4],
+    rumble_paks: [Option<MockRumblePak>; 4],
+    eeprom_type: Option<EepromType>,
+    eeprom: [u8; EepromType::Eeprom16k.size()],
+}
+
+impl Default for MockPif {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockPif {
+    pub const fn new() -> Self {
+        Self {
+            connected: [true; 4],
+            buttons: [0; 4],
+            paks: [None; 4],
+            rumble_paks: [None; 4],
+            eeprom_type: None,
+            eeprom: [0; EepromType::Eeprom16k.size()],
+        }
+    }
+
+    pub fn insert_eeprom(&mut self, eeprom_type: EepromType) {
+        self.eeprom_type = Some(eeprom_type);
+        self.eeprom = [0; EepromType::Eeprom16k.size()];
+    }
+
+    pub fn eeprom(&self) -> Option<&[u8]> {
+        self.eeprom_type
+            .map(|eeprom_type| &self.eeprom[..eeprom_type.size()])
+    }
+
+    pub fn set_connected(&mut self, port: usize, connected: bool) {
+        self.connected[port] = connected;
+    }
+
+    pub fn set_buttons(&mut self, port: usize, state: u32) {
+        self.buttons[port] = state;
+    }
+
+    pub fn insert_pak(&mut self, port: usize) {
+        self.paks[port] = Some([0; PAK_SIZE]);
+        self.rumble_paks[port] = None;
+    }
+
+    pub fn insert_rumble_pak(&mut self, port: usize) {
+        self.paks[port] = None;
+        self.rumble_paks[port] = Some(MockRumblePak::default());
+    }
+
+    pub fn remove_pak(&mut self, port: usize) {
+        self.paks[port] = None;
+        self.rumble_paks[port] = None;
+    }
+
+    pub fn is_rumbling(&self, port: usize) -> bool {
+        matches!(self.rumble_paks[port], Some(MockRumblePak { on: true, .. }))
+    }
+
+    pub fn pak(&self, port: usize) -> Option<&[u8; PAK_SIZE]> {
+        self.paks[port].as_ref()
+    }
+
+    pub fn pak_mut(&mut self, port: usize) -> Option<&mut [u8; PAK_SIZE]> {
+        self.paks[port].as_mut()
+    }
+
+    fn pak_read(&self, channel: usize, offset: usize, data: &mut [u8; PAK_BLOCK_SIZE]) {
+        if let Some(pak) = &self.paks[channel] {
+            if offset < PAK_SIZE {
+                data.copy_from_slice(&pak[offset..offset + PAK_BLOCK_SIZE]);
+            }
+        }
+
+        if let Some(rumble) = &self.rumble_paks[channel] {
+            if offset == RUMBLE_PROBE_ADDRESS as usize && rumble.probed {
+                data.fill(0x80);
+            }
+        }
+    }
+
+    fn pak_write(&mut self, channel: usize, offset: usize, data: &[u8; PAK_BLOCK_SIZE]) {
+        if let Some(pak) = &mut self.paks[channel] {
+            if offset < PAK_SIZE {
+                pak[offset..offset + PAK_BLOCK_SIZE].copy_from_slice(data);
+            }
+        }
+
+        if let Some(rumble) = &mut self.rumble_paks[channel] {
+            if offset == RUMBLE_PROBE_ADDRESS as usize {
+                rumble.probed = data[0] == 0x80;
+            } else if offset == RUMBLE_MOTOR_ADDRESS as usize {
+                rumble.on = data[0] & 0x01 != 0;
+            }
+        }
+    }
+
+    fn pak_command(&mut self, channel: usize, command: &[u8], response: &mut [u8]) -> bool {
+        let address = u16::from_be_bytes([command[1], command[2]]);
+        let offset = (address & !0x1f) as usize;
+
+        // The pak ignores accesses with a bad address crc, answer with a crc that matches nothing
+        let valid = address_crc(address) == address;
+        let present = self.paks[channel].is_some() || self.rumble_paks[channel].is_some();
+
+        let mut data = [0; PAK_BLOCK_SIZE];
+
+        if command[0] == PAK_CMD_READ {
+            if valid {
+                self.pak_read(channel, offset, &mut data);
+            }
+
+            response[..PAK_BLOCK_SIZE].copy_from_slice(&data);
+        } else {
+            data.copy_from_slice(&command[3..3 + PAK_BLOCK_SIZE]);
+
+            if valid {
+                self.pak_write(channel, offset, &data);
+            }
+        }
+
+        response[response.len() - 1] = match (present, valid) {
+            (true, true) => data_crc(&data),
+            (true, false) => data_crc(&data) ^ 0x55,
+            (false, _) => !data_crc(&data),
+        };
+
+        true
+    }
+
+    fn eeprom_command(&mut self, command: &[u8], response: &mut [u8]) -> bool {
+        let Some(eeprom_type) = self.eeprom_type else {
+            return false;
+        };
+
+        match command[0] {
+            EEPROM_CMD_STATUS if response.len() == 3 => {
+                let id = match eeprom_type {
+                    EepromType::Eeprom4k => EEPROM_ID_4K,
+                    EepromType::Eeprom16k => EEPROM_ID_16K,
+                };
+
+                response[..2].copy_from_slice(&id.to_be_bytes());
+                response[2] = 0;
+                true
+            }
+            EEPROM_CMD_READ if command.len() == 2 && response.len() == EEPROM_BLOCK_SIZE => {
+                let offset = command[1] as usize * EEPROM_BLOCK_SIZE;
+
+                if offset < eeprom_type.size() {
+                    response.copy_from_slice(&self.eeprom[offset..offset + EEPROM_BLOCK_SIZE]);
+                } else {
+                    response.fill(0);
+                }
+                true
+            }
+            EEPROM_CMD_WRITE if command.len() == EEPROM_BLOCK_SIZE + 2 && response.len() == 1 => {
+                let offset = command[1] as usize * EEPROM_BLOCK_SIZE;
+
+                if offset < eeprom_type.size() {
+                    self.eeprom[offset..offset + EEPROM_BLOCK_SIZE].copy_from_slice(&command[2..]);
+                }
+
+                response[0] = 0;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Returns false when nothing answers on the channel
+    fn command(&mut self, channel: usize, command: &[u8], response: &mut [u8]) -> bool {
+        if command.is_empty() {
+            return false;
+        }
+
+        if channel == EEPROM_CHANNEL {
+            return self.eeprom_command(command, response);
+        }
+
+        if channel >= 4 || !self.connected[channel] {
+            return false;
+        }
+
+        match command[0] {
+            CMD_STATUS | CMD_RESET if response.len() == 3 => {
+                response[..2].copy_from_slice(&CONTROLLER_TYPE);
+                response[2] = if self.paks[channel].is_some() || self.rumble_paks[channel].is_some()
+                {
+                    STATUS_ACCESSORY_PRESENT
+                } else {
+                    0
+                };
+                true
+            }
+            CMD_READ_BUTTONS if response.len() == 4 => {
+                response.copy_from_slice(&self.buttons[channel].to_be_bytes());
+                true
+            }
+            PAK_CMD_READ if command.len() == 3 && response.len() == PAK_BLOCK_SIZE + 1 => {
+                self.pak_command(channel, command, response)
+            }
+            PAK_CMD_WRITE if command.len() == PAK_BLOCK_SIZE + 3 && response.len() == 1 => {
+                self.pak_command(channel, command, response)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Pif for MockPif {
+    fn exec(&mut self, inblock: &[u64; 8], outblock: &mut [u64; 8]) {
+        let mut block = block_to_bytes(inblock);
+
+        let mut i = 0;
+        let mut channel = 0;
+
+        while i < 63 {
+            match block[i] {
+                0xfe => break,
+                0xff => {
+                    i += 1;
+                    continue;
+                }
+                0x00 => {
+                    i += 1;
+                    channel += 1;
+                    continue;
+                }
+                _ => (),
+            }
+
+            let tx = (block[i] & 0x3f) as usize;
+            let rx = (block[i + 1] & 0x3f) as usize;
+            let command_start = i + 2;
+            let response_start = command_start + tx;
+
+            if response_start + rx > 63 {
+                break;
+            }
+
+            let (command, response) = block[command_start..response_start + rx].split_at_mut(tx);
+
+            if !self.command(channel, command, response) {
+                block[i + 1] |= PIF_NO_RESPONSE;
+            }
+
+            i = response_start + rx;
+            channel += 1;
+        }
+
+        block[63] = 0;
+
+        *outblock = bytes_to_block(&block);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pak_read_write_roundtrip() {
+        let mut pif = MockPif::new();
+        pif.insert_pak(2);
+
+        let data = core::array::from_fn(|i| i as u8 * 7);
+        crate::pak::write(&mut pif, 2, 0x0140, &data).unwrap();
+
+        let mut read = [0; PAK_BLOCK_SIZE];
+        crate::pak::read(&mut pif, 2, 0x0140, &mut read).unwrap();
+
+        assert_eq!(read, data);
+        assert_eq!(&pif.pak(2).unwrap()[0x140..0x160], &data);
+    }
+
+    #[test]
+    fn pak_errors() {
+        let mut pif = MockPif::new();
+        pif.set_connected(1, false);
+
+        let mut data = [0; PAK_BLOCK_SIZE];
+
+        assert_eq!(
+            crate::pak::read(&mut pif, 0, 0, &mut data),
+            Err(crate::pak::PakError::NoPak)
+        );
+        assert_eq!(
+            crate::pak::write(&mut pif, 1, 0, &data),
+            Err(crate::pak::PakError::NoController)
+        );
+    }
+
+    #[test]
+    fn rumble_pak_probe_and_motor() {
+        let mut pif = MockPif::new();
+        pif.insert_rumble_pak(0);
+        pif.insert_pak(1);
+
+        assert_eq!(crate::pak::is_rumble_pak(&mut pif, 0), Ok(true));
+        assert_eq!(crate::pak::is_rumble_pak(&mut pif, 1), Ok(false));
+
+        crate::pak::set_rumble(&mut pif, 0, true).unwrap();
+        assert!(pif.is_rumbling(0));
+
+        crate::pak::set_rumble(&mut pif, 0, false).unwrap();
+        assert!(!pif.is_rumbling(0));
+    }
+
+    #[test]
+    fn eeprom_detect_read_write() {
+        let mut pif = MockPif::new();
+        assert_eq!(crate::eeprom::detect(&mut pif), None);
+
+        pif.insert_eeprom(EepromType::Eeprom4k);
+        assert_eq!(crate::eeprom::detect(&mut pif), Some(EepromType::Eeprom4k));
+
+        let data = [1, 2, 3, 4, 5, 6, 7, 8];
+        crate::eeprom::write_block(&mut pif, 63, &data).unwrap();
+
+        let mut read = [0; EEPROM_BLOCK_SIZE];
+        crate::eeprom::read_block(&mut pif, 63, &mut read).unwrap();
+
+        assert_eq!(read, data);
+        assert_eq!(&pif.eeprom().unwrap()[504..], &data);
+    }
+}
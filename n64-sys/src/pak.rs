@@ -0,0 +1,187 @@
+use crate::si::Pif;
+
+pub const PAK_BLOCK_SIZE: usize = 32;
+pub const PAK_SIZE: usize = 0x8000;
+
+pub(crate) const RUMBLE_PROBE_ADDRESS: u16 = 0x8000;
+pub(crate) const RUMBLE_MOTOR_ADDRESS: u16 = 0xc000;
+
+pub(crate) const PAK_CMD_READ: u8 = 0x02;
+pub(crate) const PAK_CMD_WRITE: u8 = 0x03;
+
+// Set in the receive length byte when no controller answered
+pub(crate) const PIF_NO_RESPONSE: u8 = 0x80;
+
+// Offsets in the command for one port, after the skipped channels
+const CMD_OFFSET: usize = 2;
+const ADDRESS_OFFSET: usize = 3;
+const DATA_OFFSET: usize = 5;
+const CRC_OFFSET: usize = DATA_OFFSET + PAK_BLOCK_SIZE;
+const END_OFFSET: usize = CRC_OFFSET + 1;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PakError {
+    NoController,
+    NoPak,
+    Crc,
+}
+
+static ADDRESS_CRC_TABLE: [u16; 16] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x15, 0x1f, 0x0b, 0x16, 0x19, 0x07, 0x0e, 0x1c, 0x0d, 0x1a, 0x01,
+];
+
+// The pak only accepts 32 byte aligned addresses with a 5 bit crc in the low bits
+#[inline]
+pub fn address_crc(address: u16) -> u16 {
+    let address = address & !0x1f;
+
+    let crc = (5..16)
+        .filter(|bit| (address >> bit) & 1 != 0)
+        .fold(0, |crc, bit| crc ^ ADDRESS_CRC_TABLE[bit]);
+
+    address | (crc & 0x1f)
+}
+
+// Crc-8 with polynomial 0x85 over the data and 8 zero bits
+#[inline]
+pub fn data_crc(data: &[u8; PAK_BLOCK_SIZE]) -> u8 {
+    let mut crc: u8 = 0;
+
+    for byte in data.iter().copied().chain(Some(0)) {
+        for bit in (0..8).rev() {
+            let xor = crc & 0x80 != 0;
+            crc = (crc << 1) | ((byte >> bit) & 1);
+
+            if xor {
+                crc ^= 0x85;
+            }
+        }
+    }
+
+    crc
+}
+
+pub(crate) fn block_to_bytes(block: &[u64; 8]) -> [u8; 64] {
+    let mut bytes = [0; 64];
+
+    for (chunk, word) in bytes.chunks_exact_mut(8).zip(block) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+
+    bytes
+}
+
+pub(crate) fn bytes_to_block(bytes: &[u8; 64]) -> [u64; 8] {
+    let mut block = [0; 8];
+
+    for (word, chunk) in block.iter_mut().zip(bytes.chunks_exact(8)) {
+        *word = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+
+    block
+}
+
+fn exec_pak_command(
+    pif: &mut impl Pif,
+    port: usize,
+    cmd: u8,
+    address: u16,
+    data: &[u8; PAK_BLOCK_SIZE],
+) -> Result<[u8; 64], PakError> {
+    assert!(port < 4);
+
+    // Channels before the port are skipped with zero bytes
+    let mut command = [0; 64];
+
+    let (tx, rx) = if cmd == PAK_CMD_READ {
+        (3, PAK_BLOCK_SIZE as u8 + 1)
+    } else {
+        (PAK_BLOCK_SIZE as u8 + 3, 1)
+    };
+
+    command[port] = tx;
+    command[port + 1] = rx;
+    command[port + CMD_OFFSET] = cmd;
+    command[port + ADDRESS_OFFSET..port + DATA_OFFSET]
+        .copy_from_slice(&address_crc(address).to_be_bytes());
+    command[port + DATA_OFFSET..port + CRC_OFFSET].copy_from_slice(data);
+    command[port + END_OFFSET] = 0xfe;
+    command[63] = 1;
+
+    let mut response = [0; 8];
+    pif.exec(&bytes_to_block(&command), &mut response);
+    let response = block_to_bytes(&response);
+
+    if response[port + 1] & PIF_NO_RESPONSE != 0 {
+        return Err(PakError::NoController);
+    }
+
+    Ok(response)
+}
+
+// A controller without a pak answers with the inverted crc
+fn check_crc(expected: u8, received: u8) -> Result<(), PakError> {
+    if received == expected {
+        Ok(())
+    } else if received == !expected {
+        Err(PakError::NoPak)
+    } else {
+        Err(PakError::Crc)
+    }
+}
+
+#[inline]
+pub fn read(
+    pif: &mut impl Pif,
+    port: usize,
+    address: u16,
+    data: &mut [u8; PAK_BLOCK_SIZE],
+) -> Result<(), PakError> {
+    let response = exec_pak_command(pif, port, PAK_CMD_READ, address, &[0; PAK_BLOCK_SIZE])?;
+
+    data.copy_from_slice(&response[port + DATA_OFFSET..port + CRC_OFFSET]);
+
+    check_crc(data_crc(data), response[port + CRC_OFFSET])
+}
+
+#[inline]
+pub fn write(
+    pif: &mut impl Pif,
+    port: usize,
+    address: u16,
+    data: &[u8; PAK_BLOCK_SIZE],
+) -> Result<(), PakError> {
+    let response = exec_pak_command(pif, port, PAK_CMD_WRITE, address, data)?;
+
+    check_crc(data_crc(data), response[port + CRC_OFFSET])
+}
+
+// A Rumble Pak reads back 0x80 from the probe address after it has been written, other paks do not
+#[inline]
+pub fn is_rumble_pak(pif: &mut impl Pif, port: usize) -> Result<bool, PakError> {
+    write(pif, port, RUMBLE_PROBE_ADDRESS, &[0xfe; PAK_BLOCK_SIZE])?;
+    write(pif, port, RUMBLE_PROBE_ADDRESS, &[0x80; PAK_BLOCK_SIZE])?;
+
+    let mut data = [0; PAK_BLOCK_SIZE];
+    read(pif, port, RUMBLE_PROBE_ADDRESS, &mut data)?;
+
+    Ok(data[0] == 0x80)
+}
+
+#[inline]
+pub fn set_rumble(pif: &mut impl Pif, port: usize, on: bool) -> Result<(), PakError> {
+    write(pif, port, RUMBLE_MOTOR_ADDRESS, &[on as u8; PAK_BLOCK_SIZE])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_crc_matches_known_addresses() {
+        assert_eq!(address_crc(0x0000), 0x0000);
+        assert_eq!(address_crc(0x8000), 0x8001);
+        assert_eq!(address_crc(0xc000), 0xc01b);
+        assert_eq!(address_crc(0x8013), 0x8001);
+    }
+}
@@ -1,6 +1,9 @@
-use crate::sys::{
-    data_cache_hit_writeback_invalidate, data_cache_hit_writeback_invalidate_single, uncached_addr,
-    uncached_addr_mut, virtual_to_physical, virtual_to_physical_mut,
+use crate::{
+    mi, pi_queue,
+    sys::{
+        data_cache_hit_writeback_invalidate, uncached_addr, uncached_addr_mut, virtual_to_physical,
+        virtual_to_physical_mut,
+    },
 };
 use core::{
     ptr::{read_volatile, write_volatile},
@@ -28,9 +31,14 @@ const PI_BSD_DOM2_RLS_REG: *mut usize = (PI_BASE + 0x30) as _;
 const PI_STATUS_DMA_BUSY: usize = 0x0001;
 const PI_STATUS_IO_BUSY: usize = 0x0002;
 
+#[inline]
+pub(crate) fn is_busy() -> bool {
+    unsafe { read_volatile(PI_STATUS) & (PI_STATUS_DMA_BUSY | PI_STATUS_IO_BUSY) > 0 }
+}
+
 #[inline]
 fn dma_wait() {
-    unsafe { while read_volatile(PI_STATUS) & (PI_STATUS_DMA_BUSY | PI_STATUS_IO_BUSY) > 0 {} }
+    while is_busy() {}
 }
 
 pub fn init() {
@@ -48,27 +56,64 @@ pub fn init() {
     }
 }
 
-pub unsafe fn read(dst: *mut u8, len: u32, pi_address: usize) {
-    data_cache_hit_writeback_invalidate_single(dst as usize);
-
+// Starts a transfer from the bus address into RAM, the PI has to be idle
+pub(crate) unsafe fn start_read(dst: *mut u8, len: u32, pi_address: usize) {
     write_volatile(PI_STATUS, 3);
     write_volatile(PI_RAM_ADDR, uncached_addr_mut(dst as _) as _);
-    write_volatile(
-        PI_CART_ADDR,
-        virtual_to_physical((pi_address | 0x10000000) as *const u8),
-    );
+    write_volatile(PI_CART_ADDR, virtual_to_physical_mut(pi_address as *mut u8));
     write_volatile(PI_WRITE_LENGTH, (len - 1) as _);
+}
 
-    dma_wait();
+// Starts a transfer from RAM to the bus address, the PI has to be idle
+pub(crate) unsafe fn start_write(src: *const u8, len: u32, pi_address: usize) {
+    write_volatile(PI_STATUS, 3);
+    write_volatile(PI_RAM_ADDR, uncached_addr(src as _) as _);
+    write_volatile(PI_CART_ADDR, virtual_to_physical(pi_address as *const u8));
+    write_volatile(PI_READ_LENGTH, (len - 1) as _);
+}
+
+// Transfers queued with pi_queue may still be running, so wait before starting. Interrupts are
+// disabled until the transfer is done, otherwise the PI interrupt could start the next queued
+// transfer in between
+fn transfer(start: impl FnOnce()) {
+    mi::free(|| {
+        dma_wait();
+        start();
+        dma_wait();
+    });
+
+    // Starting the transfer acknowledged the interrupt of a queued transfer that finished meanwhile
+    pi_queue::poll();
+}
+
+pub unsafe fn read(dst: *mut u8, len: u32, pi_address: usize) {
+    data_cache_hit_writeback_invalidate(slice::from_raw_parts(dst, len as _));
+
+    transfer(|| start_read(dst, len, pi_address | 0x10000000));
 }
 
 pub unsafe fn write(src: *const u8, len: u32, pi_address: usize) {
     data_cache_hit_writeback_invalidate(slice::from_raw_parts(src, len as _));
 
-    write_volatile(PI_STATUS, 3);
-    write_volatile(PI_RAM_ADDR, uncached_addr(src as _) as _);
-    write_volatile(PI_CART_ADDR, virtual_to_physical_mut(pi_address as *mut u8));
-    write_volatile(PI_READ_LENGTH, (len - 1) as _);
+    transfer(|| start_write(src, len, pi_address));
+}
+
+// Like read but the address is not moved into the cartridge rom, used for the save memory in domain 2
+pub unsafe fn read_raw(dst: *mut u8, len: u32, pi_address: usize) {
+    data_cache_hit_writeback_invalidate(slice::from_raw_parts(dst, len as _));
 
+    transfer(|| start_read(dst, len, pi_address));
+}
+
+// Single word access to a device on the PI bus
+#[inline]
+pub fn io_read(pi_address: usize) -> u32 {
+    dma_wait();
+    unsafe { read_volatile((pi_address | 0xA000_0000) as *const u32) }
+}
+
+#[inline]
+pub fn io_write(pi_address: usize, value: u32) {
     dma_wait();
+    unsafe { write_volatile((pi_address | 0xA000_0000) as *mut u32, value) }
 }
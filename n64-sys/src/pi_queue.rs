@@ -0,0 +1,325 @@
+use crate::{
+    mi::{self, Interrupt},
+    pi,
+    sys::{
+        data_cache_hit_invalidate, data_cache_hit_writeback, data_cache_hit_writeback_invalidate,
+    },
+};
+use core::{ptr, slice};
+
+const QUEUE_SIZE: usize = 16;
+const LINE_SIZE: usize = 16;
+
+// Requests complete in the order they were queued
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DmaHandle(u32);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DmaError {
+    QueueFull,
+    Unaligned,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Direction {
+    Read,
+    Write,
+}
+
+#[derive(Copy, Clone)]
+struct Request {
+    handle: DmaHandle,
+    direction: Direction,
+    ram: usize,
+    len: u32,
+    pi_address: usize,
+    callback: Option<fn(DmaHandle)>,
+    bounced: bool,
+}
+
+#[repr(C, align(16))]
+#[derive(Copy, Clone)]
+struct BounceLine([u8; LINE_SIZE]);
+
+struct Queue {
+    requests: [Option<Request>; QUEUE_SIZE],
+    head: usize,
+    len: usize,
+    running: bool,
+    next_handle: u32,
+    completed: u32,
+}
+
+impl Queue {
+    const fn new() -> Self {
+        Self {
+            requests: [None; QUEUE_SIZE],
+            head: 0,
+            len: 0,
+            running: false,
+            next_handle: 1,
+            completed: 0,
+        }
+    }
+
+    fn free_slots(&self) -> usize {
+        QUEUE_SIZE - self.len
+    }
+
+    fn push(&mut self, mut request: Request) -> Result<DmaHandle, DmaError> {
+        if self.len == QUEUE_SIZE {
+            return Err(DmaError::QueueFull);
+        }
+
+        request.handle = DmaHandle(self.next_handle);
+        self.next_handle += 1;
+
+        self.requests[(self.head + self.len) % QUEUE_SIZE] = Some(request);
+        self.len += 1;
+
+        Ok(request.handle)
+    }
+
+    fn front(&self) -> Option<Request> {
+        if self.len == 0 {
+            None
+        } else {
+            self.requests[self.head]
+        }
+    }
+
+    fn pop(&mut self) -> Option<Request> {
+        let request = self.requests[self.head].take()?;
+
+        self.head = (self.head + 1) % QUEUE_SIZE;
+        self.len -= 1;
+
+        Some(request)
+    }
+}
+
+static mut QUEUE: Queue = Queue::new();
+
+// Indexed by queue slot, only the DMA writes to them
+static mut BOUNCE_LINES: [BounceLine; QUEUE_SIZE] = [BounceLine([0; LINE_SIZE]); QUEUE_SIZE];
+
+// The PI moves data in RAM at 8 byte alignment and on the bus at 2 byte alignment
+fn check_alignment(ram: usize, pi_address: usize) -> Result<(), DmaError> {
+    if ram % 8 != 0 || pi_address % 2 != 0 {
+        Err(DmaError::Unaligned)
+    } else {
+        Ok(())
+    }
+}
+
+// Cache lines only partly covered by a read are shared with other data that the CPU may write
+// while the transfer runs. Those parts are read into a bounce line and copied when they are done,
+// the rest goes directly to the destination
+fn split_read(request: Request, parts: &mut [Request; 3]) -> usize {
+    let start = request.ram;
+    let end = start + request.len as usize;
+    let first_full = (start + LINE_SIZE - 1) & !(LINE_SIZE - 1);
+    let last_full = end & !(LINE_SIZE - 1);
+
+    let mut count = 0;
+    let mut push = |ram: usize, end: usize, bounced: bool| {
+        if ram < end {
+            parts[count] = Request {
+                ram,
+                len: (end - ram) as u32,
+                pi_address: request.pi_address + (ram - start),
+                bounced,
+                ..request
+            };
+            count += 1;
+        }
+    };
+
+    if first_full > last_full {
+        // Starts and ends inside the same line
+        push(start, end, true);
+    } else {
+        push(start, first_full, true);
+        push(first_full, last_full, false);
+        push(last_full, end, true);
+    }
+
+    count
+}
+
+fn submit(request: Request) -> Result<DmaHandle, DmaError> {
+    check_alignment(request.ram, request.pi_address)?;
+
+    let mut parts = [request; 3];
+    let part_count = match request.direction {
+        Direction::Read => split_read(request, &mut parts),
+        Direction::Write => 1,
+    };
+
+    for part in &parts[..part_count] {
+        let data = unsafe { slice::from_raw_parts(part.ram as *const u8, part.len as usize) };
+
+        // Dirty lines have to be in RAM before the transfer, reads would otherwise be overwritten
+        // when the lines are evicted
+        match part.direction {
+            Direction::Read if part.bounced => (),
+            Direction::Read => unsafe { data_cache_hit_writeback_invalidate(data) },
+            Direction::Write => unsafe { data_cache_hit_writeback(data) },
+        }
+    }
+
+    let handle = mi::free(|| unsafe {
+        if QUEUE.free_slots() < part_count {
+            return Err(DmaError::QueueFull);
+        }
+
+        let mut handle = DmaHandle(QUEUE.completed);
+
+        // Parts complete in order, so only the last one reports back
+        for (i, part) in parts[..part_count].iter().enumerate() {
+            handle = QUEUE.push(Request {
+                callback: if i + 1 == part_count {
+                    part.callback
+                } else {
+                    None
+                },
+                ..*part
+            })?;
+        }
+
+        Ok(handle)
+    })?;
+
+    poll();
+
+    Ok(handle)
+}
+
+// Queues a read from the cartridge rom, like pi::read. The destination must stay untouched until
+// the request is done
+pub unsafe fn read_async(
+    dst: *mut u8,
+    len: u32,
+    pi_address: usize,
+    callback: Option<fn(DmaHandle)>,
+) -> Result<DmaHandle, DmaError> {
+    submit(Request {
+        handle: DmaHandle(0),
+        direction: Direction::Read,
+        ram: dst as usize,
+        len,
+        pi_address: pi_address | 0x10000000,
+        callback,
+        bounced: false,
+    })
+}
+
+// Queues a write to a bus address, like pi::write. The source must stay alive until the request is
+// done
+pub unsafe fn write_async(
+    src: *const u8,
+    len: u32,
+    pi_address: usize,
+    callback: Option<fn(DmaHandle)>,
+) -> Result<DmaHandle, DmaError> {
+    submit(Request {
+        handle: DmaHandle(0),
+        direction: Direction::Write,
+        ram: src as usize,
+        len,
+        pi_address,
+        callback,
+        bounced: false,
+    })
+}
+
+// Lets the PI interrupt drive the queue, callbacks then run in the interrupt handler
+pub fn init() {
+    mi::register(Interrupt::Pi, poll);
+    mi::enable(Interrupt::Pi);
+}
+
+// Completes the running transfer and starts the next one, called from the main loop or when the
+// PI interrupt fires. Callbacks run from here
+pub fn poll() {
+    let finished = mi::free(|| unsafe {
+        if QUEUE.running && pi::is_busy() {
+            return None;
+        }
+
+        let slot = QUEUE.head;
+        let finished = if QUEUE.running { QUEUE.pop() } else { None };
+
+        QUEUE.running = false;
+
+        if let Some(request) = &finished {
+            QUEUE.completed = request.handle.0;
+
+            if request.direction == Direction::Read {
+                if request.bounced {
+                    let line = &BOUNCE_LINES[slot].0;
+
+                    data_cache_hit_invalidate(line);
+                    ptr::copy_nonoverlapping(
+                        line.as_ptr(),
+                        request.ram as *mut u8,
+                        request.len as usize,
+                    );
+                } else {
+                    // Lines speculatively fetched during the transfer are stale
+                    data_cache_hit_invalidate(slice::from_raw_parts(
+                        request.ram as *const u8,
+                        request.len as usize,
+                    ));
+                }
+            }
+        }
+
+        // Synchronous transfers wait for the PI before they start, but one may be running now
+        if let Some(next) = QUEUE.front() {
+            if !pi::is_busy() {
+                match next.direction {
+                    Direction::Read if next.bounced => {
+                        let line = &mut BOUNCE_LINES[QUEUE.head].0;
+
+                        // The line may still be cached from the last copy
+                        data_cache_hit_invalidate(line);
+                        pi::start_read(line.as_mut_ptr(), next.len, next.pi_address)
+                    }
+                    Direction::Read => pi::start_read(next.ram as _, next.len, next.pi_address),
+                    Direction::Write => pi::start_write(next.ram as _, next.len, next.pi_address),
+                }
+
+                QUEUE.running = true;
+            }
+        }
+
+        finished
+    });
+
+    if let Some(Request {
+        handle,
+        callback: Some(callback),
+        ..
+    }) = finished
+    {
+        callback(handle);
+    }
+}
+
+#[inline]
+pub fn is_done(handle: DmaHandle) -> bool {
+    poll();
+    unsafe { handle.0 <= QUEUE.completed }
+}
+
+pub fn wait(handle: DmaHandle) {
+    while !is_done(handle) {}
+}
+
+// Number of transfers that have not completed yet, reads split around partly covered cache lines
+// count once per part
+#[inline]
+pub fn pending() -> usize {
+    unsafe { QUEUE.len }
+}
@@ -214,11 +214,12 @@ pub fn write_dmem(data: &[u8]) {
     }
 }
 
-pub fn read_dmem(data: &mut [u8; 4096]) {
+pub fn read_dmem(data: &mut [u8]) {
     unsafe {
+        assert!(data.len() <= 4096);
         assert!(data.as_ptr() as usize % 8 == 0);
 
-        data_cache_hit_writeback_invalidate(data.as_slice());
+        data_cache_hit_writeback_invalidate(data);
 
         dma_wait();
 
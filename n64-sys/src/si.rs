@@ -64,6 +64,28 @@ fn dma_pif_block(inblock: &[u64; 8], outblock: &mut [u64; 8]) {
     }
 }
 
+// Anything that executes PIF command blocks, the hardware through SI DMA or a mock on the host
+pub trait Pif {
+    fn exec(&mut self, inblock: &[u64; 8], outblock: &mut [u64; 8]);
+}
+
+impl<T: Pif + ?Sized> Pif for &mut T {
+    #[inline]
+    fn exec(&mut self, inblock: &[u64; 8], outblock: &mut [u64; 8]) {
+        (**self).exec(inblock, outblock);
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct SiPif;
+
+impl Pif for SiPif {
+    #[inline]
+    fn exec(&mut self, inblock: &[u64; 8], outblock: &mut [u64; 8]) {
+        dma_pif_block(inblock, outblock);
+    }
+}
+
 #[inline]
 pub fn read_controllers(outblock: &mut [u64; 8]) {
     static READ_CON_BLOCK: [u64; 8] = [
@@ -79,3 +101,19 @@ pub fn read_controllers(outblock: &mut [u64; 8]) {
 
     dma_pif_block(&READ_CON_BLOCK, outblock);
 }
+
+#[inline]
+pub fn read_controller_status(outblock: &mut [u64; 8]) {
+    static STATUS_CON_BLOCK: [u64; 8] = [
+        0xff010300ffffffff,
+        0xff010300ffffffff,
+        0xff010300ffffffff,
+        0xff010300ffffffff,
+        0xfe00000000000000,
+        0,
+        0,
+        1,
+    ];
+
+    dma_pif_block(&STATUS_CON_BLOCK, outblock);
+}
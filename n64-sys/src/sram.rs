@@ -0,0 +1,67 @@
+use crate::pi;
+
+// 256 kbit of battery backed SRAM in PI domain 2
+pub const SRAM_ADDRESS: usize = 0x0800_0000;
+pub const SRAM_SIZE: usize = 0x8000;
+
+const CHUNK_SIZE: usize = 256;
+
+// PI DMA needs 8 byte aligned RAM
+#[repr(C, align(8))]
+struct DmaBuffer([u8; CHUNK_SIZE]);
+
+// Missing SRAM reads back the open bus, so a pattern is written to the end and read back. The old
+// contents are restored afterwards
+pub fn detect() -> bool {
+    const PROBE: [u8; 8] = [0x5a, 0xa5, 0x3c, 0xc3, 0x0f, 0xf0, 0x69, 0x96];
+    let offset = SRAM_SIZE - PROBE.len();
+
+    let mut old = [0; PROBE.len()];
+    read(offset, &mut old);
+    write(offset, &PROBE);
+
+    let mut probe = [0; PROBE.len()];
+    read(offset, &mut probe);
+    write(offset, &old);
+
+    probe == PROBE
+}
+
+// Offset and length have to be even
+pub fn read(offset: usize, data: &mut [u8]) {
+    assert!(offset % 2 == 0 && data.len() % 2 == 0);
+    assert!(offset + data.len() <= SRAM_SIZE);
+
+    let mut buffer = DmaBuffer([0; CHUNK_SIZE]);
+
+    for (i, chunk) in data.chunks_mut(CHUNK_SIZE).enumerate() {
+        unsafe {
+            pi::read_raw(
+                buffer.0.as_mut_ptr(),
+                chunk.len() as u32,
+                SRAM_ADDRESS + offset + i * CHUNK_SIZE,
+            )
+        };
+
+        chunk.copy_from_slice(&buffer.0[..chunk.len()]);
+    }
+}
+
+pub fn write(offset: usize, data: &[u8]) {
+    assert!(offset % 2 == 0 && data.len() % 2 == 0);
+    assert!(offset + data.len() <= SRAM_SIZE);
+
+    let mut buffer = DmaBuffer([0; CHUNK_SIZE]);
+
+    for (i, chunk) in data.chunks(CHUNK_SIZE).enumerate() {
+        buffer.0[..chunk.len()].copy_from_slice(chunk);
+
+        unsafe {
+            pi::write(
+                buffer.0.as_ptr(),
+                chunk.len() as u32,
+                SRAM_ADDRESS + offset + i * CHUNK_SIZE,
+            )
+        };
+    }
+}
@@ -0,0 +1,114 @@
+// Asset archive appended to the rom after the program: a header, a directory table with one
+// entry per asset and then the asset data. All values are big endian
+pub const ARCHIVE_MAGIC: [u8; 4] = *b"LARC";
+pub const ARCHIVE_HEADER_SIZE: usize = 8;
+pub const ARCHIVE_ENTRY_SIZE: usize = 12;
+
+// Every asset starts and ends at this alignment so it can be moved with PI DMA
+pub const ARCHIVE_ALIGN: usize = 8;
+
+// Unused bytes in the rom header where the rom offset of the archive is written
+pub const ARCHIVE_ROM_OFFSET_LOCATION: usize = 0x18;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ArchiveEntry {
+    // From the start of the archive
+    pub offset: u32,
+    pub len: u32,
+    // Smaller than len when the data is lz compressed
+    pub stored_len: u32,
+}
+
+impl ArchiveEntry {
+    #[inline]
+    pub fn to_bytes(self) -> [u8; ARCHIVE_ENTRY_SIZE] {
+        let mut bytes = [0; ARCHIVE_ENTRY_SIZE];
+        bytes[0..4].copy_from_slice(&self.offset.to_be_bytes());
+        bytes[4..8].copy_from_slice(&self.len.to_be_bytes());
+        bytes[8..12].copy_from_slice(&self.stored_len.to_be_bytes());
+        bytes
+    }
+
+    #[inline]
+    pub fn is_compressed(self) -> bool {
+        self.stored_len != self.len
+    }
+
+    #[inline]
+    pub fn from_bytes(bytes: &[u8; ARCHIVE_ENTRY_SIZE]) -> Self {
+        Self {
+            offset: u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            len: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+            stored_len: u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+        }
+    }
+}
+
+#[inline]
+pub fn archive_header(entry_count: u32) -> [u8; ARCHIVE_HEADER_SIZE] {
+    let mut bytes = [0; ARCHIVE_HEADER_SIZE];
+    bytes[0..4].copy_from_slice(&ARCHIVE_MAGIC);
+    bytes[4..8].copy_from_slice(&entry_count.to_be_bytes());
+    bytes
+}
+
+// The directory is padded so the data starts aligned
+#[inline]
+pub fn archive_data_start(entry_count: u32) -> usize {
+    let directory_end = ARCHIVE_HEADER_SIZE + entry_count as usize * ARCHIVE_ENTRY_SIZE;
+    (directory_end + ARCHIVE_ALIGN - 1) / ARCHIVE_ALIGN * ARCHIVE_ALIGN
+}
+
+// Returns the number of entries
+#[inline]
+pub fn parse_archive_header(bytes: &[u8; ARCHIVE_HEADER_SIZE]) -> Option<u32> {
+    if bytes[0..4] != ARCHIVE_MAGIC {
+        return None;
+    }
+
+    Some(u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_roundtrip() {
+        assert_eq!(parse_archive_header(&archive_header(0)), Some(0));
+        assert_eq!(parse_archive_header(&archive_header(1234)), Some(1234));
+
+        let mut header = archive_header(3);
+        header[0] = b'X';
+        assert_eq!(parse_archive_header(&header), None);
+    }
+
+    #[test]
+    fn entry_roundtrip() {
+        let entry = ArchiveEntry {
+            offset: 0x0102_0304,
+            len: 4096,
+            stored_len: 1234,
+        };
+
+        let bytes = entry.to_bytes();
+        assert_eq!(bytes[0..4], [1, 2, 3, 4]);
+        assert_eq!(ArchiveEntry::from_bytes(&bytes), entry);
+        assert!(entry.is_compressed());
+
+        let stored = ArchiveEntry {
+            stored_len: entry.len,
+            ..entry
+        };
+        assert!(!ArchiveEntry::from_bytes(&stored.to_bytes()).is_compressed());
+    }
+
+    #[test]
+    fn data_starts_aligned() {
+        for count in 0..8 {
+            let start = archive_data_start(count);
+            assert_eq!(start % ARCHIVE_ALIGN, 0);
+            assert!(start >= ARCHIVE_HEADER_SIZE + count as usize * ARCHIVE_ENTRY_SIZE);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use core::mem::size_of;
+use zerocopy::{AsBytes, FromBytes, Unaligned};
+
+use crate::static_assert;
+
+// One address of a crash backtrace, sent after the text report so the host can symbolicate it
+#[repr(C, packed)]
+#[derive(AsBytes, FromBytes, Unaligned)]
+pub struct CrashMessageBuffer {
+    pub index: u8,
+    pub count: u8,
+    // The instruction that faulted or a return address, which points two instructions after the call
+    pub is_return_address: u8,
+    pub address: u32,
+}
+
+static_assert!(size_of::<CrashMessageBuffer>() == 7);
+
+impl CrashMessageBuffer {
+    #[inline]
+    pub fn get_address_from_be(&self) -> u32 {
+        u32::from_be(self.address)
+    }
+}
@@ -1,16 +1,31 @@
 #![no_std]
 
+pub use archive::{
+    archive_data_start, archive_header, parse_archive_header, ArchiveEntry, ARCHIVE_ALIGN,
+    ARCHIVE_ENTRY_SIZE, ARCHIVE_HEADER_SIZE, ARCHIVE_MAGIC, ARCHIVE_ROM_OFFSET_LOCATION,
+};
+pub use crash::CrashMessageBuffer;
+pub use lz::{lz_decompress, LzDecoder, LzError, LZ_MAX_OFFSET, LZ_MIN_MATCH};
 pub use profiler::{ProfilerMessageBuffer, ScopeData};
+pub use protocol::{
+    crc16, encode_frame, Command, Frame, FrameDecoder, MessageType, ProtocolError, FRAME_CRC_SIZE,
+    FRAME_HEADER_SIZE, FRAME_MAX_PAYLOAD, FRAME_MAX_SIZE, FRAME_SYNC,
+};
 pub use rdp_command::{RdpBlock, RdpCommand};
+pub use screenshot::{ScreenshotMessageHeader, SCREENSHOT_PIXELS_PER_MESSAGE};
+pub use tmem::{tmem_line_width, tmem_page_rows, TMEM_SIZE};
 pub use video_mode::VideoMode;
 
+mod archive;
+mod crash;
+mod lz;
 mod profiler;
+mod protocol;
 mod rdp_command;
+mod screenshot;
+mod tmem;
 mod video_mode;
 
-pub const MESSAGE_MAGIC_PROFILER: u8 = 0x1c;
-pub const MESSAGE_MAGIC_PRINT: u8 = 0x1d;
-
 #[macro_export]
 macro_rules! static_assert {
     ($cond:expr) => {
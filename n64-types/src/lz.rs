@@ -0,0 +1,179 @@
+// Lz4 style compression that only needs byte operations to decode. A stream is a list of sequences:
+// a token with the literal count in the high nibble and the match length minus LZ_MIN_MATCH in the
+// low nibble, more length bytes when a nibble is 15, the literals, a big endian match offset and
+// more match length bytes. The last sequence only has literals and the stream ends when the output
+// is full
+pub const LZ_MIN_MATCH: usize = 4;
+pub const LZ_MAX_OFFSET: usize = 0xffff;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LzError {
+    Overflow,
+    BadOffset,
+    Truncated,
+}
+
+#[derive(Copy, Clone)]
+enum LzState {
+    Token,
+    LiteralLength,
+    Literals,
+    OffsetHigh,
+    OffsetLow,
+    MatchLength,
+}
+
+// Keeps its state between calls to feed, so the input can be split anywhere. That way it can
+// decompress straight from DMA buffers while the next part is read
+pub struct LzDecoder<'a> {
+    out: &'a mut [u8],
+    pos: usize,
+    state: LzState,
+    token: u8,
+    literals: usize,
+    offset: usize,
+    match_len: usize,
+}
+
+impl<'a> LzDecoder<'a> {
+    #[inline]
+    pub fn new(out: &'a mut [u8]) -> Self {
+        Self {
+            out,
+            pos: 0,
+            state: LzState::Token,
+            token: 0,
+            literals: 0,
+            offset: 0,
+            match_len: 0,
+        }
+    }
+
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.pos == self.out.len()
+    }
+
+    #[inline]
+    fn after_literal_length(&self) -> LzState {
+        if self.literals == 0 {
+            LzState::OffsetHigh
+        } else {
+            LzState::Literals
+        }
+    }
+
+    fn copy_match(&mut self) -> Result<(), LzError> {
+        if self.offset == 0 || self.offset > self.pos {
+            return Err(LzError::BadOffset);
+        }
+
+        if self.pos + self.match_len > self.out.len() {
+            return Err(LzError::Overflow);
+        }
+
+        let start = self.pos - self.offset;
+
+        // Overlapping matches repeat the last offset bytes
+        if self.offset >= self.match_len {
+            self.out
+                .copy_within(start..start + self.match_len, self.pos);
+        } else {
+            for i in 0..self.match_len {
+                self.out[self.pos + i] = self.out[start + i];
+            }
+        }
+
+        self.pos += self.match_len;
+        self.state = LzState::Token;
+
+        Ok(())
+    }
+
+    // Input after the end of the stream is ignored, like the padding after an archive entry
+    pub fn feed(&mut self, mut input: &[u8]) -> Result<(), LzError> {
+        while let Some((&byte, rest)) = input.split_first() {
+            if self.is_done() {
+                return Ok(());
+            }
+
+            match self.state {
+                LzState::Token => {
+                    self.token = byte;
+                    self.literals = (byte >> 4) as usize;
+                    self.state = if self.literals == 15 {
+                        LzState::LiteralLength
+                    } else {
+                        self.after_literal_length()
+                    };
+                    input = rest;
+                }
+                LzState::LiteralLength => {
+                    self.literals += byte as usize;
+
+                    if byte != 255 {
+                        self.state = self.after_literal_length();
+                    }
+                    input = rest;
+                }
+                LzState::Literals => {
+                    let len = self.literals.min(input.len());
+
+                    if self.pos + len > self.out.len() {
+                        return Err(LzError::Overflow);
+                    }
+
+                    self.out[self.pos..self.pos + len].copy_from_slice(&input[..len]);
+                    self.pos += len;
+                    self.literals -= len;
+
+                    if self.literals == 0 {
+                        self.state = LzState::OffsetHigh;
+                    }
+                    input = &input[len..];
+                }
+                LzState::OffsetHigh => {
+                    self.offset = (byte as usize) << 8;
+                    self.state = LzState::OffsetLow;
+                    input = rest;
+                }
+                LzState::OffsetLow => {
+                    self.offset |= byte as usize;
+                    self.match_len = (self.token & 0xf) as usize + LZ_MIN_MATCH;
+
+                    if self.token & 0xf == 15 {
+                        self.state = LzState::MatchLength;
+                    } else {
+                        self.copy_match()?;
+                    }
+                    input = rest;
+                }
+                LzState::MatchLength => {
+                    self.match_len += byte as usize;
+
+                    if byte != 255 {
+                        self.copy_match()?;
+                    }
+                    input = rest;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn finish(self) -> Result<(), LzError> {
+        if self.is_done() {
+            Ok(())
+        } else {
+            Err(LzError::Truncated)
+        }
+    }
+}
+
+pub fn lz_decompress(input: &[u8], out: &mut [u8]) -> Result<(), LzError> {
+    let mut decoder = LzDecoder::new(out);
+    decoder.feed(input)?;
+    decoder.finish()
+}
@@ -27,13 +27,12 @@ impl ScopeData {
 #[repr(C, packed)]
 #[derive(AsBytes, FromBytes, Unaligned)]
 pub struct ProfilerMessageBuffer {
-    pub message_header_buffer: u8,
     pub scope: ScopeData,
     pub index: i16,
     pub count: i16,
 }
 
-static_assert!(size_of::<ProfilerMessageBuffer>() == 16);
+static_assert!(size_of::<ProfilerMessageBuffer>() == 15);
 
 impl ProfilerMessageBuffer {
     pub fn get_scope_from_be(&self) -> ScopeData {
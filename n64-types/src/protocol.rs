@@ -0,0 +1,336 @@
+// Messages between the deploy tool and the N64 are sent as frames: a sync byte, the message type,
+// the big endian payload length, the payload and a big endian crc-16 of everything after the sync
+// byte. Frames are padded to an even length for the PI, the decoder skips padding and garbage while
+// it looks for the next sync byte
+pub const FRAME_SYNC: u8 = 0x4c;
+pub const FRAME_HEADER_SIZE: usize = 4;
+pub const FRAME_CRC_SIZE: usize = 2;
+pub const FRAME_MAX_PAYLOAD: usize = 1024;
+pub const FRAME_MAX_SIZE: usize = FRAME_HEADER_SIZE + FRAME_MAX_PAYLOAD + FRAME_CRC_SIZE;
+
+#[repr(u8)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MessageType {
+    // N64 to host
+    Print = 0x01,
+    Profiler = 0x02,
+    Crash = 0x03,
+    Screenshot = 0x04,
+
+    // Host to N64
+    Pause = 0x40,
+    Resume = 0x41,
+    StepFrame = 0x42,
+    SetVariable = 0x43,
+    RequestScreenshot = 0x44,
+}
+
+impl MessageType {
+    #[inline]
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0x01 => MessageType::Print,
+            0x02 => MessageType::Profiler,
+            0x03 => MessageType::Crash,
+            0x04 => MessageType::Screenshot,
+            0x40 => MessageType::Pause,
+            0x41 => MessageType::Resume,
+            0x42 => MessageType::StepFrame,
+            0x43 => MessageType::SetVariable,
+            0x44 => MessageType::RequestScreenshot,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ProtocolError {
+    PayloadTooLarge,
+    BufferTooSmall,
+    Crc,
+}
+
+// Crc-16/CCITT-FALSE
+#[inline]
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xffff;
+
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
+}
+
+// Returns the length of the padded frame
+pub fn encode_frame(
+    message_type: MessageType,
+    payload: &[u8],
+    out: &mut [u8],
+) -> Result<usize, ProtocolError> {
+    if payload.len() > FRAME_MAX_PAYLOAD {
+        return Err(ProtocolError::PayloadTooLarge);
+    }
+
+    let crc_start = FRAME_HEADER_SIZE + payload.len();
+    let len = crc_start + FRAME_CRC_SIZE;
+    let padded_len = (len + 1) & !1;
+
+    if out.len() < padded_len {
+        return Err(ProtocolError::BufferTooSmall);
+    }
+
+    out[0] = FRAME_SYNC;
+    out[1] = message_type as u8;
+    out[2..4].copy_from_slice(&(payload.len() as u16).to_be_bytes());
+    out[FRAME_HEADER_SIZE..crc_start].copy_from_slice(payload);
+
+    let crc = crc16(&out[1..crc_start]);
+    out[crc_start..len].copy_from_slice(&crc.to_be_bytes());
+    out[len..padded_len].fill(0);
+
+    Ok(padded_len)
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Frame<'a> {
+    pub message_type: MessageType,
+    pub payload: &'a [u8],
+}
+
+// Collects bytes until a whole frame has arrived, so it works with any transfer size. After a crc
+// error the bytes already received are scanned again, frames found in them are returned by the
+// following pushes
+pub struct FrameDecoder {
+    buffer: [u8; FRAME_MAX_SIZE],
+    len: usize,
+    // Length of the frame returned by the last push, it is dropped on the next one
+    consumed: usize,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameDecoder {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; FRAME_MAX_SIZE],
+            len: 0,
+            consumed: 0,
+        }
+    }
+
+    #[inline]
+    fn payload_len(&self) -> usize {
+        u16::from_be_bytes([self.buffer[2], self.buffer[3]]) as usize
+    }
+
+    // Drops the sync byte at the start of the buffer and moves on to the next one
+    fn resync(&mut self) {
+        let next = self.buffer[1..self.len]
+            .iter()
+            .position(|byte| *byte == FRAME_SYNC)
+            .map_or(self.len, |position| position + 1);
+
+        self.buffer.copy_within(next..self.len, 0);
+        self.len -= next;
+    }
+
+    pub fn push(&mut self, byte: u8) -> Option<Result<Frame<'_>, ProtocolError>> {
+        if self.consumed > 0 {
+            self.buffer.copy_within(self.consumed..self.len, 0);
+            self.len -= self.consumed;
+            self.consumed = 0;
+        }
+
+        if self.len == 0 && byte != FRAME_SYNC {
+            return None;
+        }
+
+        self.buffer[self.len] = byte;
+        self.len += 1;
+
+        loop {
+            if self.len == 0 {
+                return None;
+            }
+
+            // A type or length that can not be right means the sync byte was part of something else
+            let bad_type = self.len >= 2 && MessageType::from_u8(self.buffer[1]).is_none();
+            let bad_len = self.len >= FRAME_HEADER_SIZE && self.payload_len() > FRAME_MAX_PAYLOAD;
+
+            if bad_type || bad_len {
+                self.resync();
+                continue;
+            }
+
+            if self.len < FRAME_HEADER_SIZE {
+                return None;
+            }
+
+            let crc_start = FRAME_HEADER_SIZE + self.payload_len();
+            let frame_len = crc_start + FRAME_CRC_SIZE;
+
+            if self.len < frame_len {
+                return None;
+            }
+
+            let crc = u16::from_be_bytes([self.buffer[crc_start], self.buffer[crc_start + 1]]);
+
+            // The sync byte might not have started a frame, a real one can begin inside it
+            if crc != crc16(&self.buffer[1..crc_start]) {
+                self.resync();
+                return Some(Err(ProtocolError::Crc));
+            }
+
+            self.consumed = frame_len;
+
+            return MessageType::from_u8(self.buffer[1]).map(|message_type| {
+                Ok(Frame {
+                    message_type,
+                    payload: &self.buffer[FRAME_HEADER_SIZE..crc_start],
+                })
+            });
+        }
+    }
+}
+
+// Sent from the deploy tool to control the game while it runs
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Command {
+    Pause,
+    Resume,
+    StepFrame,
+    SetVariable { id: u16, value: f32 },
+    RequestScreenshot,
+}
+
+impl Command {
+    pub fn encode(self, out: &mut [u8]) -> Result<usize, ProtocolError> {
+        match self {
+            Command::Pause => encode_frame(MessageType::Pause, &[], out),
+            Command::Resume => encode_frame(MessageType::Resume, &[], out),
+            Command::StepFrame => encode_frame(MessageType::StepFrame, &[], out),
+            Command::SetVariable { id, value } => {
+                let mut payload = [0; 6];
+                payload[0..2].copy_from_slice(&id.to_be_bytes());
+                payload[2..6].copy_from_slice(&value.to_bits().to_be_bytes());
+
+                encode_frame(MessageType::SetVariable, &payload, out)
+            }
+            Command::RequestScreenshot => encode_frame(MessageType::RequestScreenshot, &[], out),
+        }
+    }
+
+    // Returns None for frames that are not commands
+    pub fn decode(frame: &Frame) -> Option<Self> {
+        Some(match frame.message_type {
+            MessageType::Pause => Command::Pause,
+            MessageType::Resume => Command::Resume,
+            MessageType::StepFrame => Command::StepFrame,
+            MessageType::SetVariable => {
+                let payload: &[u8; 6] = frame.payload.try_into().ok()?;
+
+                Command::SetVariable {
+                    id: u16::from_be_bytes([payload[0], payload[1]]),
+                    value: f32::from_bits(u32::from_be_bytes([
+                        payload[2], payload[3], payload[4], payload[5],
+                    ])),
+                }
+            }
+            MessageType::RequestScreenshot => Command::RequestScreenshot,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoder_skips_garbage_and_bad_frames() {
+        let mut stream = [0; 64];
+        let mut len = 0;
+
+        stream[0..3].copy_from_slice(&[0x00, 0x12, FRAME_SYNC]);
+        len += 3;
+
+        // The last byte of the payload is corrupted
+        let bad_len = encode_frame(MessageType::Print, b"bad", &mut stream[len..]).unwrap();
+        stream[len + FRAME_HEADER_SIZE + 2] ^= 0xff;
+        len += bad_len;
+
+        len += Command::SetVariable { id: 3, value: 1.5 }
+            .encode(&mut stream[len..])
+            .unwrap();
+
+        let mut decoder = FrameDecoder::new();
+        let mut errors = 0;
+        let mut commands = 0;
+
+        for byte in &stream[..len] {
+            match decoder.push(*byte) {
+                Some(Ok(frame)) => {
+                    assert_eq!(
+                        Command::decode(&frame),
+                        Some(Command::SetVariable { id: 3, value: 1.5 })
+                    );
+                    commands += 1;
+                }
+                Some(Err(err)) => {
+                    assert_eq!(err, ProtocolError::Crc);
+                    errors += 1;
+                }
+                None => (),
+            }
+        }
+
+        assert_eq!((errors, commands), (1, 1));
+    }
+
+    #[test]
+    fn decoder_finds_frame_inside_false_sync() {
+        let mut stream = [0; 64];
+
+        // Looks like the header of a 20 byte print, the real frame starts inside its payload
+        stream[0..4].copy_from_slice(&[FRAME_SYNC, MessageType::Print as u8, 0, 20]);
+        let len = 4 + Command::SetVariable { id: 7, value: -2.0 }
+            .encode(&mut stream[4..])
+            .unwrap();
+
+        // Enough bytes for the false frame to end and one more to return the real one
+        let len = len.max(FRAME_HEADER_SIZE + 20 + FRAME_CRC_SIZE) + 1;
+
+        let mut decoder = FrameDecoder::new();
+        let mut results = [None; 2];
+        let mut count = 0;
+
+        for byte in &stream[..len] {
+            if let Some(result) = decoder.push(*byte) {
+                results[count] = Some(result.map(|frame| Command::decode(&frame)));
+                count += 1;
+            }
+        }
+
+        assert_eq!(
+            results,
+            [
+                Some(Err(ProtocolError::Crc)),
+                Some(Ok(Some(Command::SetVariable { id: 7, value: -2.0 })))
+            ]
+        );
+    }
+}
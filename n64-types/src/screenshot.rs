@@ -0,0 +1,36 @@
+use core::mem::size_of;
+use zerocopy::{AsBytes, FromBytes, Unaligned};
+
+use crate::{static_assert, FRAME_MAX_PAYLOAD};
+
+// Starts every screenshot message and is followed by big endian 5551 pixels in row order, offset
+// is the index of the first of them
+#[repr(C, packed)]
+#[derive(AsBytes, FromBytes, Unaligned)]
+pub struct ScreenshotMessageHeader {
+    pub width: u16,
+    pub height: u16,
+    pub offset: u32,
+}
+
+static_assert!(size_of::<ScreenshotMessageHeader>() == 8);
+
+pub const SCREENSHOT_PIXELS_PER_MESSAGE: usize =
+    (FRAME_MAX_PAYLOAD - size_of::<ScreenshotMessageHeader>()) / 2;
+
+impl ScreenshotMessageHeader {
+    #[inline]
+    pub fn get_width_from_be(&self) -> u16 {
+        u16::from_be(self.width)
+    }
+
+    #[inline]
+    pub fn get_height_from_be(&self) -> u16 {
+        u16::from_be(self.height)
+    }
+
+    #[inline]
+    pub fn get_offset_from_be(&self) -> u32 {
+        u32::from_be(self.offset)
+    }
+}
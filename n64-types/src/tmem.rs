@@ -0,0 +1,31 @@
+// Texture memory of the RDP, a texture or the part of it that is drawn has to fit
+pub const TMEM_SIZE: i32 = 4096;
+
+// Tmem lines are 64 bits, four 16 bit texels
+#[inline]
+pub const fn tmem_line_width(width: i32) -> i32 {
+    (width + 3) & !3
+}
+
+// Atlases larger than tmem are split into pages of whole rows and no image crosses a page, so a
+// page can be loaded at once and every image on it drawn without another load
+#[inline]
+pub const fn tmem_page_rows(width: i32) -> i32 {
+    TMEM_SIZE / (2 * tmem_line_width(width))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_fit_in_tmem() {
+        for width in 1..=512 {
+            let rows = tmem_page_rows(width);
+            assert!(2 * tmem_line_width(width) * rows <= TMEM_SIZE);
+            assert!(2 * tmem_line_width(width) * (rows + 1) > TMEM_SIZE);
+        }
+
+        assert_eq!(tmem_page_rows(56), 36);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::archive_io;
+use alloc::{vec, vec::Vec};
+use n64_sys::pi_queue::DmaError;
+use n64_types::{
+    parse_archive_header, ArchiveEntry, LzDecoder, ARCHIVE_ENTRY_SIZE, ARCHIVE_HEADER_SIZE,
+};
+use spin::Once;
+use zerocopy::AsBytes;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ArchiveError {
+    NotFound,
+    Corrupt,
+    InvalidIndex(u32),
+    Dma(DmaError),
+}
+
+impl From<DmaError> for ArchiveError {
+    fn from(err: DmaError) -> Self {
+        ArchiveError::Dma(err)
+    }
+}
+
+// The asset archive built by the pipeline. On N64 it is read from the rom and the path is only
+// used on PC, where it can be overridden with N64_ASSETS_PATH
+pub struct Archive {
+    path: &'static str,
+    entries: Once<Result<Vec<ArchiveEntry>, ArchiveError>>,
+}
+
+impl Archive {
+    #[inline]
+    pub const fn new(path: &'static str) -> Self {
+        Self {
+            path,
+            entries: Once::new(),
+        }
+    }
+
+    fn read_directory(&self) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+        let mut header = [0u64; ARCHIVE_HEADER_SIZE / 8];
+        archive_io::read(self.path, 0, &mut header)?;
+
+        let count = parse_archive_header(header.as_bytes().try_into().unwrap())
+            .ok_or(ArchiveError::Corrupt)?;
+
+        // The directory is padded to whole words
+        let table_len = count as usize * ARCHIVE_ENTRY_SIZE;
+        let mut table = vec![0u64; (table_len + 7) / 8];
+        archive_io::read(self.path, ARCHIVE_HEADER_SIZE, &mut table)?;
+
+        Ok(table.as_bytes()[..table_len]
+            .chunks_exact(ARCHIVE_ENTRY_SIZE)
+            .map(|entry| ArchiveEntry::from_bytes(entry.try_into().unwrap()))
+            .collect())
+    }
+
+    fn entries(&self) -> Result<&[ArchiveEntry], ArchiveError> {
+        match self.entries.call_once(|| self.read_directory()) {
+            Ok(entries) => Ok(entries),
+            Err(err) => Err(*err),
+        }
+    }
+
+    #[inline]
+    pub fn entry_count(&self) -> Result<usize, ArchiveError> {
+        Ok(self.entries()?.len())
+    }
+
+    // Reads the asset into a new heap buffer, compressed assets are decompressed while they stream in
+    pub fn load(&self, index: u32) -> Result<AssetBuffer, ArchiveError> {
+        let entry = *self
+            .entries()?
+            .get(index as usize)
+            .ok_or(ArchiveError::InvalidIndex(index))?;
+
+        let len = entry.len as usize;
+        let mut words = vec![0u64; (len + 7) / 8];
+
+        if entry.is_compressed() {
+            let mut decoder = LzDecoder::new(&mut words.as_bytes_mut()[..len]);
+
+            archive_io::stream(
+                self.path,
+                entry.offset as usize,
+                entry.stored_len as usize,
+                |chunk| decoder.feed(chunk).map_err(|_| ArchiveError::Corrupt),
+            )?;
+
+            decoder.finish().map_err(|_| ArchiveError::Corrupt)?;
+        } else {
+            archive_io::read(self.path, entry.offset as usize, &mut words)?;
+        }
+
+        Ok(AssetBuffer { words, len })
+    }
+}
+
+// 8 byte aligned so it can be used directly as texture, sound or vertex data
+pub struct AssetBuffer {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl AssetBuffer {
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.words.as_bytes()[..self.len]
+    }
+}
+
+// An asset that is loaded the first time it is used and then kept for the rest of the run. Data
+// that is only needed for a while can be loaded into a buffer of its own instead
+pub struct Asset {
+    archive: &'static Archive,
+    index: u32,
+    data: Once<Result<AssetBuffer, ArchiveError>>,
+}
+
+impl Asset {
+    #[inline]
+    pub const fn new(archive: &'static Archive, index: u32) -> Self {
+        Self {
+            archive,
+            index,
+            data: Once::new(),
+        }
+    }
+
+    #[inline]
+    pub fn is_loaded(&self) -> bool {
+        self.data.is_completed()
+    }
+
+    // Reads the asset into a buffer that is freed when it is dropped, the cached copy is not used
+    #[inline]
+    pub fn load(&self) -> Result<AssetBuffer, ArchiveError> {
+        self.archive.load(self.index)
+    }
+
+    pub fn get(&'static self) -> Result<&'static [u8], ArchiveError> {
+        match self.data.call_once(|| self.load()) {
+            Ok(buffer) => Ok(buffer.as_bytes()),
+            Err(err) => Err(*err),
+        }
+    }
+}
+
+// Data compiled into the binary or kept in the asset archive
+#[derive(Copy, Clone)]
+pub enum StaticData {
+    Static(&'static [u8]),
+    Asset(&'static Asset),
+}
+
+impl StaticData {
+    #[inline]
+    pub fn get(self) -> Result<&'static [u8], ArchiveError> {
+        match self {
+            StaticData::Static(data) => Ok(data),
+            StaticData::Asset(asset) => asset.get(),
+        }
+    }
+
+    // Loads archived data now instead of on first use, for example when a level starts
+    #[inline]
+    pub fn preload(self) -> Result<(), ArchiveError> {
+        self.get().map(|_| ())
+    }
+}
@@ -0,0 +1,46 @@
+use crate::archive::ArchiveError;
+use std::{
+    env,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+};
+use zerocopy::AsBytes;
+
+const ASSETS_PATH_ENV: &str = "N64_ASSETS_PATH";
+const STREAM_CHUNK_SIZE: usize = 4096;
+
+fn open(path: &str, offset: usize) -> Result<File, ArchiveError> {
+    let path = env::var(ASSETS_PATH_ENV).unwrap_or_else(|_| path.to_string());
+
+    let mut file = File::open(path).map_err(|_| ArchiveError::NotFound)?;
+
+    file.seek(SeekFrom::Start(offset as u64))
+        .map_err(|_| ArchiveError::Corrupt)?;
+
+    Ok(file)
+}
+
+pub(crate) fn read(path: &str, offset: usize, dst: &mut [u64]) -> Result<(), ArchiveError> {
+    open(path, offset)?
+        .read_exact(dst.as_bytes_mut())
+        .map_err(|_| ArchiveError::Corrupt)
+}
+
+pub(crate) fn stream(
+    path: &str,
+    offset: usize,
+    len: usize,
+    mut f: impl FnMut(&[u8]) -> Result<(), ArchiveError>,
+) -> Result<(), ArchiveError> {
+    let mut file = open(path, offset)?;
+    let mut buffer = vec![0; STREAM_CHUNK_SIZE];
+
+    for chunk_start in (0..len).step_by(STREAM_CHUNK_SIZE) {
+        let chunk = &mut buffer[..STREAM_CHUNK_SIZE.min(len - chunk_start)];
+
+        file.read_exact(chunk).map_err(|_| ArchiveError::Corrupt)?;
+        f(chunk)?;
+    }
+
+    Ok(())
+}
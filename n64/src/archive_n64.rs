@@ -0,0 +1,113 @@
+#![cfg_attr(not(target_vendor = "nintendo64"), allow(unused))]
+
+use crate::archive::ArchiveError;
+use alloc::vec;
+use core::slice;
+use n64_sys::{pi, pi_queue};
+use n64_types::{ARCHIVE_MAGIC, ARCHIVE_ROM_OFFSET_LOCATION};
+use spin::Once;
+
+const STREAM_CHUNK_SIZE: usize = 4096;
+
+// Each chunk covers whole cache lines, so the transfer into one never shares a line with the other
+#[repr(C, align(16))]
+#[derive(Clone)]
+struct StreamChunk([u8; STREAM_CHUNK_SIZE]);
+
+// Written into the rom header when the archive is appended to the rom
+static ROM_OFFSET: Once<Option<usize>> = Once::new();
+
+fn rom_offset() -> Option<usize> {
+    *ROM_OFFSET.call_once(|| {
+        // PI DMA needs 8 byte aligned RAM
+        let mut word = 0u64;
+        unsafe { pi::read(&mut word as *mut u64 as _, 8, ARCHIVE_ROM_OFFSET_LOCATION) };
+
+        let offset = (word >> 32) as usize;
+
+        if offset == 0 {
+            return None;
+        }
+
+        unsafe { pi::read(&mut word as *mut u64 as _, 8, offset) };
+
+        if word.to_be_bytes()[..4] == ARCHIVE_MAGIC {
+            Some(offset)
+        } else {
+            None
+        }
+    })
+}
+
+pub(crate) fn read(_path: &str, offset: usize, dst: &mut [u64]) -> Result<(), ArchiveError> {
+    let rom_offset = rom_offset().ok_or(ArchiveError::NotFound)?;
+
+    if dst.is_empty() {
+        return Ok(());
+    }
+
+    let handle = unsafe {
+        pi_queue::read_async(
+            dst.as_mut_ptr() as _,
+            (dst.len() * 8) as u32,
+            rom_offset + offset,
+            None,
+        )?
+    };
+
+    pi_queue::wait(handle);
+
+    Ok(())
+}
+
+// Reads len bytes in chunks and passes them to f, the next chunk is transferred while f works on the
+// current one
+pub(crate) fn stream(
+    _path: &str,
+    offset: usize,
+    len: usize,
+    mut f: impl FnMut(&[u8]) -> Result<(), ArchiveError>,
+) -> Result<(), ArchiveError> {
+    let rom_offset = rom_offset().ok_or(ArchiveError::NotFound)?;
+
+    let mut buffers = vec![StreamChunk([0; STREAM_CHUNK_SIZE]); 2];
+    let buffers = buffers.as_mut_ptr() as *mut u8;
+
+    let chunk_count = (len + STREAM_CHUNK_SIZE - 1) / STREAM_CHUNK_SIZE;
+    let chunk_len = |chunk: usize| STREAM_CHUNK_SIZE.min(len - chunk * STREAM_CHUNK_SIZE);
+    let buffer = |chunk: usize| unsafe { buffers.add((chunk % 2) * STREAM_CHUNK_SIZE) };
+
+    // Assets are padded in the archive so transfers can be rounded up to whole words
+    let start = |chunk: usize| unsafe {
+        pi_queue::read_async(
+            buffer(chunk),
+            ((chunk_len(chunk) + 7) / 8 * 8) as u32,
+            rom_offset + offset + chunk * STREAM_CHUNK_SIZE,
+            None,
+        )
+    };
+
+    if chunk_count == 0 {
+        return Ok(());
+    }
+
+    let mut pending = start(0)?;
+
+    for chunk in 0..chunk_count {
+        pi_queue::wait(pending);
+
+        if chunk + 1 < chunk_count {
+            pending = start(chunk + 1)?;
+        }
+
+        let data = unsafe { slice::from_raw_parts(buffer(chunk), chunk_len(chunk)) };
+
+        // The buffers are freed on return, so the transfer into the other one has to finish first
+        if let Err(err) = f(data) {
+            pi_queue::wait(pending);
+            return Err(err);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use crate::current_time_us;
+
+pub const MAX_PORTS: usize = 4;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Accessory {
+    ControllerPak,
+    RumblePak,
+}
+
+// Buttons and stick in the format read from the PIF, plus the port status
+#[derive(Copy, Clone, Default)]
+pub struct Controller {
+    pub(crate) state: u32,
+    pub(crate) connected: bool,
+    pub(crate) accessory: Option<Accessory>,
+}
+
+impl Controller {
+    #[inline]
+    pub const fn new() -> Controller {
+        Controller {
+            state: 0,
+            connected: false,
+            accessory: None,
+        }
+    }
+
+    #[inline]
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    // A Controller Pak, Rumble Pak or other accessory is plugged into the controller
+    #[inline]
+    pub fn has_accessory(&self) -> bool {
+        self.accessory.is_some()
+    }
+
+    #[inline]
+    pub fn accessory(&self) -> Option<Accessory> {
+        self.accessory
+    }
+
+    #[inline]
+    pub fn state(&self) -> u32 {
+        self.state
+    }
+
+    #[inline]
+    pub fn set_state(&mut self, state: u32) {
+        self.state = state;
+    }
+
+    #[inline]
+    pub fn x(&self) -> i8 {
+        ((self.state >> 8) & 0xff) as i8
+    }
+
+    #[inline]
+    pub fn y(&self) -> i8 {
+        (self.state & 0xff) as i8
+    }
+
+    #[inline]
+    pub fn a(&self) -> bool {
+        self.state & 0x8000_0000 > 0
+    }
+
+    #[inline]
+    pub fn b(&self) -> bool {
+        self.state & 0x4000_0000 > 0
+    }
+
+    #[inline]
+    pub fn z(&self) -> bool {
+        self.state & 0x2000_0000 > 0
+    }
+
+    #[inline]
+    pub fn start(&self) -> bool {
+        self.state & 0x1000_0000 > 0
+    }
+
+    #[inline]
+    pub fn up(&self) -> bool {
+        self.state & 0x0800_0000 > 0
+    }
+
+    #[inline]
+    pub fn down(&self) -> bool {
+        self.state & 0x0400_0000 > 0
+    }
+
+    #[inline]
+    pub fn left(&self) -> bool {
+        self.state & 0x0200_0000 > 0
+    }
+
+    #[inline]
+    pub fn right(&self) -> bool {
+        self.state & 0x0100_0000 > 0
+    }
+
+    #[inline]
+    pub fn l(&self) -> bool {
+        self.state & 0x0020_0000 > 0
+    }
+
+    #[inline]
+    pub fn r(&self) -> bool {
+        self.state & 0x0010_0000 > 0
+    }
+
+    #[inline]
+    pub fn c_up(&self) -> bool {
+        self.state & 0x0008_0000 > 0
+    }
+
+    #[inline]
+    pub fn c_down(&self) -> bool {
+        self.state & 0x0004_0000 > 0
+    }
+
+    #[inline]
+    pub fn c_left(&self) -> bool {
+        self.state & 0x0002_0000 > 0
+    }
+
+    #[inline]
+    pub fn c_right(&self) -> bool {
+        self.state & 0x0001_0000 > 0
+    }
+}
+
+// Requested rumble per port. Pulses turn themselves off and the backends only switch the motor
+// when the state changes.
+#[derive(Default)]
+pub(crate) struct Rumble {
+    on: [bool; MAX_PORTS],
+    until: [Option<i64>; MAX_PORTS],
+    applied: [bool; MAX_PORTS],
+}
+
+impl Rumble {
+    pub(crate) fn set(&mut self, port: usize, on: bool) {
+        self.on[port] = on;
+        self.until[port] = None;
+    }
+
+    pub(crate) fn pulse(&mut self, port: usize, seconds: f32) {
+        let until = current_time_us() + (seconds * 1_000_000.0) as i64;
+
+        if !self.on[port] || matches!(self.until[port], Some(current) if current < until) {
+            self.until[port] = Some(until);
+        }
+
+        self.on[port] = true;
+    }
+
+    // Ports where the motor has to be switched, and the state to switch to
+    pub(crate) fn changes(&mut self) -> [Option<bool>; MAX_PORTS] {
+        let now = current_time_us();
+        let mut changes = [None; MAX_PORTS];
+
+        for (port, change) in changes.iter_mut().enumerate() {
+            if matches!(self.until[port], Some(until) if until <= now) {
+                self.on[port] = false;
+                self.until[port] = None;
+            }
+
+            if self.on[port] != self.applied[port] {
+                self.applied[port] = self.on[port];
+                *change = Some(self.on[port]);
+            }
+        }
+
+        changes
+    }
+
+    // The motor is off after a pak has been inserted again
+    pub(crate) fn reset(&mut self, port: usize) {
+        self.applied[port] = false;
+    }
+}
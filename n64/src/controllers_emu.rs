@@ -1,122 +1,251 @@
-use crate::graphics::Graphics;
+use crate::{
+    controller::{Accessory, Controller, Rumble, MAX_PORTS},
+    graphics::Graphics,
+};
+use config::{ControllerConfig, PadInput, Target, AXIS_BUTTON_THRESHOLD};
+use gilrs::{
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder},
+    GamepadId, Gilrs,
+};
 use std::collections::HashSet;
 use winit::event::VirtualKeyCode;
 
-#[derive(Default)]
-pub struct Controllers {
-    data: HashSet<VirtualKeyCode>,
-}
+mod config;
 
-impl Controllers {
-    #[inline]
-    pub fn new() -> Controllers {
-        Controllers {
-            data: HashSet::new(),
+// Full deflection of a real N64 stick is around 80, digital inputs use the whole range
+const STICK_RANGE: f32 = 80.0;
+const STICK_DIGITAL: i32 = 127;
+
+const RUMBLE_MAGNITUDE: u16 = 0xc000;
+
+fn stick_from_targets(targets: &[Target]) -> (i32, i32) {
+    let mut x = 0;
+    let mut y = 0;
+
+    for target in targets {
+        match target {
+            Target::StickUp => y += STICK_DIGITAL,
+            Target::StickDown => y -= STICK_DIGITAL,
+            Target::StickLeft => x -= STICK_DIGITAL,
+            Target::StickRight => x += STICK_DIGITAL,
+            Target::Button(_) => (),
         }
     }
 
-    #[inline]
-    pub fn update(&mut self, graphics: &Graphics) {
-        self.data = graphics.keys_down.clone();
+    (x, y)
+}
+
+fn buttons_from_targets(targets: &[Target]) -> u32 {
+    targets.iter().fold(0, |data, target| match target {
+        Target::Button(mask) => data | mask,
+        _ => data,
+    })
+}
+
+// Radial deadzone, the remaining range is rescaled so the stick starts moving from zero
+fn stick_from_axes(x: f32, y: f32, deadzone: f32) -> (i32, i32) {
+    let magnitude = (x * x + y * y).sqrt();
+
+    if magnitude <= deadzone {
+        return (0, 0);
     }
 
-    #[inline]
-    pub fn x(&self) -> i8 {
-        let mut res = 0;
+    let scale = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0) * STICK_RANGE / magnitude;
 
-        if self.data.contains(&VirtualKeyCode::Right) {
-            res += 127;
-        }
+    ((x * scale).round() as i32, (y * scale).round() as i32)
+}
 
-        if self.data.contains(&VirtualKeyCode::Left) {
-            res -= 127;
-        }
+fn start_rumble(gilrs: &mut Gilrs, id: GamepadId) -> Result<Effect, gilrs::ff::Error> {
+    let effect = EffectBuilder::new()
+        .add_effect(BaseEffect {
+            kind: BaseEffectType::Strong {
+                magnitude: RUMBLE_MAGNITUDE,
+            },
+            ..Default::default()
+        })
+        .gamepads(&[id])
+        .finish(gilrs)?;
 
-        res
-    }
+    effect.play()?;
 
-    #[inline]
-    pub fn y(&self) -> i8 {
-        let mut res = 0;
+    Ok(effect)
+}
 
-        if self.data.contains(&VirtualKeyCode::Up) {
-            res += 127;
-        }
+fn pack_state(buttons: u32, x: i32, y: i32) -> u32 {
+    let x = x.clamp(-127, 127) as i8;
+    let y = y.clamp(-127, 127) as i8;
 
-        if self.data.contains(&VirtualKeyCode::Down) {
-            res -= 127;
-        }
+    buttons | ((x as u8 as u32) << 8) | (y as u8 as u32)
+}
 
-        res
-    }
+pub struct Controllers {
+    ports: [Controller; MAX_PORTS],
+    config: ControllerConfig,
+    gilrs: Option<Gilrs>,
+    pads: [Option<GamepadId>; MAX_PORTS],
+    rumble: Rumble,
+    rumble_effects: [Option<Effect>; MAX_PORTS],
+}
 
-    #[inline]
-    pub fn a(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::X)
+impl Default for Controllers {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
+impl Controllers {
     #[inline]
-    pub fn b(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::C)
-    }
+    pub fn new() -> Controllers {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                println!("Controllers: Gamepads unavailable: {e}");
+                None
+            }
+        };
 
-    #[inline]
-    pub fn z(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::Space)
+        Controllers {
+            ports: [Controller::new(); MAX_PORTS],
+            config: ControllerConfig::load(),
+            gilrs,
+            pads: [None; MAX_PORTS],
+            rumble: Rumble::default(),
+            rumble_effects: Default::default(),
+        }
     }
 
-    #[inline]
-    pub fn start(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::Return)
-    }
+    fn update_keyboard(&self, keys: &HashSet<VirtualKeyCode>) -> (u32, i32, i32) {
+        let targets = self
+            .config
+            .keys
+            .iter()
+            .filter(|(key, _)| keys.contains(key))
+            .map(|(_, target)| *target)
+            .collect::<Vec<_>>();
 
-    #[inline]
-    pub fn up(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::W)
-    }
+        let (x, y) = stick_from_targets(&targets);
 
-    #[inline]
-    pub fn down(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::S)
+        (buttons_from_targets(&targets), x, y)
     }
 
     #[inline]
-    pub fn left(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::A)
-    }
+    pub fn update(&mut self, graphics: &Graphics) {
+        let mut buttons = [0; MAX_PORTS];
+        let mut sticks = [(0, 0); MAX_PORTS];
+        let mut connected = [false; MAX_PORTS];
+        let mut accessories = [None; MAX_PORTS];
+
+        self.pads = [None; MAX_PORTS];
+
+        if self.config.keyboard_port < MAX_PORTS {
+            let (data, x, y) = self.update_keyboard(&graphics.keys_down);
+            buttons[self.config.keyboard_port] = data;
+            sticks[self.config.keyboard_port] = (x, y);
+            connected[self.config.keyboard_port] = true;
+        }
 
-    #[inline]
-    pub fn right(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::D)
+        if let Some(gilrs) = &mut self.gilrs {
+            while gilrs.next_event().is_some() {}
+
+            // Gamepads take the ports in the order they were connected
+            for (port, (id, gamepad)) in gilrs
+                .gamepads()
+                .filter(|(_, gamepad)| gamepad.is_connected())
+                .take(MAX_PORTS)
+                .enumerate()
+            {
+                let targets = self
+                    .config
+                    .pad
+                    .iter()
+                    .filter(|(input, _)| match *input {
+                        PadInput::Button(button) => gamepad.is_pressed(button),
+                        PadInput::Axis(axis, sign) => {
+                            gamepad.value(axis) * sign > AXIS_BUTTON_THRESHOLD
+                        }
+                    })
+                    .map(|(_, target)| *target)
+                    .collect::<Vec<_>>();
+
+                let (digital_x, digital_y) = stick_from_targets(&targets);
+                let (x, y) = stick_from_axes(
+                    gamepad.value(gilrs::Axis::LeftStickX),
+                    gamepad.value(gilrs::Axis::LeftStickY),
+                    self.config.deadzone,
+                );
+
+                buttons[port] |= buttons_from_targets(&targets);
+                sticks[port].0 += x + digital_x;
+                sticks[port].1 += y + digital_y;
+                connected[port] = true;
+
+                self.pads[port] = Some(id);
+
+                // Gamepads with force feedback act like they have a Rumble Pak inserted
+                if gamepad.is_ff_supported() {
+                    accessories[port] = Some(Accessory::RumblePak);
+                }
+            }
+        }
+
+        for (port, controller) in self.ports.iter_mut().enumerate() {
+            controller.state = pack_state(buttons[port], sticks[port].0, sticks[port].1);
+            controller.connected = connected[port];
+            controller.accessory = accessories[port];
+        }
+
+        self.update_rumble();
     }
 
-    #[inline]
-    pub fn l(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::Q)
+    fn update_rumble(&mut self) {
+        for (port, on) in self.rumble.changes().into_iter().enumerate() {
+            let Some(on) = on else {
+                continue;
+            };
+
+            if let Some(effect) = self.rumble_effects[port].take() {
+                effect.stop().ok();
+            }
+
+            let (Some(gilrs), Some(id), Some(Accessory::RumblePak)) =
+                (&mut self.gilrs, self.pads[port], self.ports[port].accessory)
+            else {
+                println!("Controllers: Rumble {} on port {port}", if on { "on" } else { "off" });
+                continue;
+            };
+
+            if on {
+                match start_rumble(gilrs, id) {
+                    Ok(effect) => self.rumble_effects[port] = Some(effect),
+                    Err(e) => println!("Controllers: Rumble failed on port {port}: {e}"),
+                }
+            }
+        }
     }
 
     #[inline]
-    pub fn r(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::E)
+    pub fn port(&self, port: usize) -> &Controller {
+        &self.ports[port]
     }
 
     #[inline]
-    pub fn c_up(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::I)
+    pub fn port_mut(&mut self, port: usize) -> &mut Controller {
+        &mut self.ports[port]
     }
 
     #[inline]
-    pub fn c_down(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::K)
+    pub fn set_rumble(&mut self, port: usize, on: bool) {
+        self.rumble.set(port, on);
     }
 
+    // Rumbles for the given time, applied from the next update
     #[inline]
-    pub fn c_left(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::J)
+    pub fn rumble_pulse(&mut self, port: usize, seconds: f32) {
+        self.rumble.pulse(port, seconds);
     }
 
     #[inline]
-    pub fn c_right(&self) -> bool {
-        self.data.contains(&VirtualKeyCode::L)
+    pub fn ports(&self) -> &[Controller; MAX_PORTS] {
+        &self.ports
     }
 }
@@ -0,0 +1,345 @@
+use gilrs::{Axis, Button};
+use std::{env, fs, path::PathBuf};
+use winit::event::VirtualKeyCode;
+
+const CONFIG_PATH_ENV: &str = "N64_CONTROLLER_CONFIG";
+const DEFAULT_CONFIG_PATH: &str = "controllers.cfg";
+
+const DEFAULT_DEADZONE: f32 = 0.15;
+
+// Axes mapped to buttons count as pressed past this value
+pub(crate) const AXIS_BUTTON_THRESHOLD: f32 = 0.5;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Target {
+    Button(u32),
+    StickUp,
+    StickDown,
+    StickLeft,
+    StickRight,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) enum PadInput {
+    Button(Button),
+    Axis(Axis, f32),
+}
+
+pub(crate) struct ControllerConfig {
+    pub(crate) deadzone: f32,
+    pub(crate) keyboard_port: usize,
+    pub(crate) keys: Vec<(VirtualKeyCode, Target)>,
+    pub(crate) pad: Vec<(PadInput, Target)>,
+}
+
+macro_rules! name_table {
+    ($fn_name:ident, $ty:ty, [$($name:ident),* $(,)?]) => {
+        fn $fn_name(name: &str) -> Option<$ty> {
+            match name {
+                $(stringify!($name) => Some(<$ty>::$name),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+name_table!(
+    key_from_name,
+    VirtualKeyCode,
+    [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Key0, Key1,
+        Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Numpad0, Numpad1, Numpad2, Numpad3,
+        Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9, Up, Down, Left, Right, Space, Return,
+        Tab, Back, Comma, Period, Semicolon, Slash, LShift, RShift, LControl, RControl, LAlt, RAlt,
+    ]
+);
+
+name_table!(
+    button_from_name,
+    Button,
+    [
+        South,
+        East,
+        North,
+        West,
+        C,
+        Z,
+        LeftTrigger,
+        LeftTrigger2,
+        RightTrigger,
+        RightTrigger2,
+        Select,
+        Start,
+        Mode,
+        LeftThumb,
+        RightThumb,
+        DPadUp,
+        DPadDown,
+        DPadLeft,
+        DPadRight,
+    ]
+);
+
+name_table!(
+    axis_from_name,
+    Axis,
+    [
+        LeftStickX,
+        LeftStickY,
+        LeftZ,
+        RightStickX,
+        RightStickY,
+        RightZ,
+        DPadX,
+        DPadY,
+    ]
+);
+
+fn target_from_name(name: &str) -> Option<Target> {
+    Some(match name {
+        "a" => Target::Button(0x8000_0000),
+        "b" => Target::Button(0x4000_0000),
+        "z" => Target::Button(0x2000_0000),
+        "start" => Target::Button(0x1000_0000),
+        "up" => Target::Button(0x0800_0000),
+        "down" => Target::Button(0x0400_0000),
+        "left" => Target::Button(0x0200_0000),
+        "right" => Target::Button(0x0100_0000),
+        "l" => Target::Button(0x0020_0000),
+        "r" => Target::Button(0x0010_0000),
+        "c_up" => Target::Button(0x0008_0000),
+        "c_down" => Target::Button(0x0004_0000),
+        "c_left" => Target::Button(0x0002_0000),
+        "c_right" => Target::Button(0x0001_0000),
+        "stick_up" => Target::StickUp,
+        "stick_down" => Target::StickDown,
+        "stick_left" => Target::StickLeft,
+        "stick_right" => Target::StickRight,
+        _ => return None,
+    })
+}
+
+// Pad inputs are a button name or an axis name followed by the direction, like RightStickY+
+fn pad_input_from_name(name: &str) -> Option<PadInput> {
+    if let Some(button) = button_from_name(name) {
+        return Some(PadInput::Button(button));
+    }
+
+    let (axis, sign) = match name.split_at(name.len().checked_sub(1)?) {
+        (axis, "+") => (axis, 1.0),
+        (axis, "-") => (axis, -1.0),
+        _ => return None,
+    };
+
+    axis_from_name(axis).map(|axis| PadInput::Axis(axis, sign))
+}
+
+impl ControllerConfig {
+    fn default_keys() -> Vec<(VirtualKeyCode, Target)> {
+        [
+            ("X", "a"),
+            ("C", "b"),
+            ("Space", "z"),
+            ("Return", "start"),
+            ("W", "up"),
+            ("S", "down"),
+            ("A", "left"),
+            ("D", "right"),
+            ("Q", "l"),
+            ("E", "r"),
+            ("I", "c_up"),
+            ("K", "c_down"),
+            ("J", "c_left"),
+            ("L", "c_right"),
+            ("Up", "stick_up"),
+            ("Down", "stick_down"),
+            ("Left", "stick_left"),
+            ("Right", "stick_right"),
+        ]
+        .iter()
+        .map(|(key, target)| {
+            (
+                key_from_name(key).unwrap(),
+                target_from_name(target).unwrap(),
+            )
+        })
+        .collect()
+    }
+
+    fn default_pad() -> Vec<(PadInput, Target)> {
+        [
+            ("South", "a"),
+            ("West", "b"),
+            ("LeftTrigger2", "z"),
+            ("Start", "start"),
+            ("DPadUp", "up"),
+            ("DPadDown", "down"),
+            ("DPadLeft", "left"),
+            ("DPadRight", "right"),
+            ("LeftTrigger", "l"),
+            ("RightTrigger", "r"),
+            ("RightStickY+", "c_up"),
+            ("RightStickY-", "c_down"),
+            ("RightStickX-", "c_left"),
+            ("RightStickX+", "c_right"),
+        ]
+        .iter()
+        .map(|(input, target)| {
+            (
+                pad_input_from_name(input).unwrap(),
+                target_from_name(target).unwrap(),
+            )
+        })
+        .collect()
+    }
+
+    // Lines look like `key.a = X`, `pad.c_up = RightStickY+`, `deadzone = 0.2` or `keyboard_port = 1`.
+    // Any key or pad line replaces all defaults for that device.
+    pub(crate) fn parse(text: &str) -> Self {
+        let mut deadzone = DEFAULT_DEADZONE;
+        let mut keyboard_port = 0;
+        let mut keys = Vec::new();
+        let mut pad = Vec::new();
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let parsed = line.split_once('=').and_then(|(name, value)| {
+                let (name, value) = (name.trim(), value.trim());
+
+                match name.split_once('.') {
+                    Some(("key", target)) => {
+                        keys.push((key_from_name(value)?, target_from_name(target)?));
+                    }
+                    Some(("pad", target)) => {
+                        pad.push((pad_input_from_name(value)?, target_from_name(target)?));
+                    }
+                    None if name == "deadzone" => deadzone = value.parse().ok()?,
+                    None if name == "keyboard_port" => keyboard_port = value.parse().ok()?,
+                    _ => return None,
+                }
+
+                Some(())
+            });
+
+            if parsed.is_none() {
+                println!(
+                    "Controller config: Invalid line {}: {}",
+                    line_number + 1,
+                    line
+                );
+            }
+        }
+
+        Self {
+            deadzone: deadzone.clamp(0.0, 0.99),
+            keyboard_port,
+            keys: if keys.is_empty() {
+                Self::default_keys()
+            } else {
+                keys
+            },
+            pad: if pad.is_empty() {
+                Self::default_pad()
+            } else {
+                pad
+            },
+        }
+    }
+
+    pub(crate) fn load() -> Self {
+        let path = env::var_os(CONFIG_PATH_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+
+        Self::parse(&fs::read_to_string(path).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_valid_bindings() {
+        let config = ControllerConfig::parse(
+            "deadzone = 0.25
+            keyboard_port = 2
+
+            # Comments and blank lines are skipped
+            key.a = Z # Trailing comments too
+            key.stick_left = Left
+            pad.b = East
+            pad.c_down = RightStickY-",
+        );
+
+        assert_eq!(config.deadzone, 0.25);
+        assert_eq!(config.keyboard_port, 2);
+        assert_eq!(
+            config.keys,
+            [
+                (VirtualKeyCode::Z, Target::Button(0x8000_0000)),
+                (VirtualKeyCode::Left, Target::StickLeft),
+            ]
+        );
+        assert_eq!(
+            config.pad,
+            [
+                (PadInput::Button(Button::East), Target::Button(0x4000_0000)),
+                (
+                    PadInput::Axis(Axis::RightStickY, -1.0),
+                    Target::Button(0x0004_0000)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_names_are_skipped() {
+        let config = ControllerConfig::parse(
+            "key.a = Banana
+            key.jump = X
+            pad.a = Paddle1
+            pad.c_up = TouchpadX+
+            mouse.a = Left
+            sensitivity = 2",
+        );
+
+        assert_eq!(config.deadzone, DEFAULT_DEADZONE);
+        assert_eq!(config.keyboard_port, 0);
+        assert_eq!(config.keys, ControllerConfig::default_keys());
+        assert_eq!(config.pad, ControllerConfig::default_pad());
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let config = ControllerConfig::parse(
+            "key.a X
+            = X
+            key. = X
+            pad.a = RightStickY
+            pad.b = +
+            deadzone = lots
+            keyboard_port = -1
+            key.b = C",
+        );
+
+        assert_eq!(config.deadzone, DEFAULT_DEADZONE);
+        assert_eq!(config.keyboard_port, 0);
+        assert_eq!(
+            config.keys,
+            [(VirtualKeyCode::C, Target::Button(0x4000_0000))]
+        );
+        assert_eq!(config.pad, ControllerConfig::default_pad());
+    }
+
+    #[test]
+    fn deadzone_is_clamped() {
+        assert_eq!(ControllerConfig::parse("deadzone = 4").deadzone, 0.99);
+        assert_eq!(ControllerConfig::parse("deadzone = -1").deadzone, 0.0);
+    }
+}
@@ -1,101 +1,110 @@
 #![cfg_attr(not(target_vendor = "nintendo64"), allow(unused))]
 
-use crate::graphics_n64::Graphics;
-use n64_sys::si;
+use crate::{
+    controller::{Accessory, Controller, Rumble, MAX_PORTS},
+    graphics_n64::Graphics,
+};
+use n64_sys::{
+    pak,
+    si::{self, SiPif},
+};
+
+// The PIF sets the top bit of the receive length when nothing answered on the port
+const PIF_NO_RESPONSE: u64 = 0x80 << 40;
+
+const STATUS_ACCESSORY_PRESENT: u64 = 0x01;
+
+// Frames between status queries, accessories are only detected this often
+const STATUS_INTERVAL: u32 = 30;
 
 #[derive(Default)]
 pub struct Controllers {
-    data: [u64; 8],
+    ports: [Controller; MAX_PORTS],
+    rumble: Rumble,
+    frame: u32,
 }
 
 impl Controllers {
     #[inline]
     pub fn new() -> Controllers {
-        Controllers { data: [0; 8] }
+        Controllers {
+            ports: [Controller::new(); MAX_PORTS],
+            rumble: Rumble::default(),
+            frame: 0,
+        }
     }
 
     #[inline]
     pub fn update(&mut self, _graphics: &Graphics) {
-        si::read_controllers(&mut self.data);
-    }
-
-    #[inline]
-    pub fn x(&self) -> i8 {
-        ((self.data[0] >> 8) & 0xff) as i8
-    }
+        let mut data = [0; 8];
 
-    #[inline]
-    pub fn y(&self) -> i8 {
-        (self.data[0] & 0xff) as i8
-    }
-
-    #[inline]
-    pub fn a(&self) -> bool {
-        self.data[0] & 0x8000_0000 > 0
-    }
-
-    #[inline]
-    pub fn b(&self) -> bool {
-        self.data[0] & 0x4000_0000 > 0
-    }
+        if self.frame % STATUS_INTERVAL == 0 {
+            si::read_controller_status(&mut data);
 
-    #[inline]
-    pub fn z(&self) -> bool {
-        self.data[0] & 0x2000_0000 > 0
-    }
+            for (port, (controller, response)) in self.ports.iter_mut().zip(data).enumerate() {
+                let present = response & PIF_NO_RESPONSE == 0
+                    && (response >> 8) & STATUS_ACCESSORY_PRESENT != 0;
 
-    #[inline]
-    pub fn start(&self) -> bool {
-        self.data[0] & 0x1000_0000 > 0
-    }
+                // Newly inserted paks are probed once to tell rumble paks apart
+                controller.accessory = match (present, controller.accessory) {
+                    (false, _) => None,
+                    (true, Some(accessory)) => Some(accessory),
+                    (true, None) => {
+                        self.rumble.reset(port);
 
-    #[inline]
-    pub fn up(&self) -> bool {
-        self.data[0] & 0x0800_0000 > 0
-    }
+                        match pak::is_rumble_pak(&mut SiPif, port) {
+                            Ok(true) => Some(Accessory::RumblePak),
+                            Ok(false) => Some(Accessory::ControllerPak),
+                            Err(_) => None,
+                        }
+                    }
+                };
+            }
+        }
 
-    #[inline]
-    pub fn down(&self) -> bool {
-        self.data[0] & 0x0400_0000 > 0
-    }
+        for (port, on) in self.rumble.changes().into_iter().enumerate() {
+            if let (Some(on), Some(Accessory::RumblePak)) = (on, self.ports[port].accessory) {
+                pak::set_rumble(&mut SiPif, port, on).ok();
+            }
+        }
 
-    #[inline]
-    pub fn left(&self) -> bool {
-        self.data[0] & 0x0200_0000 > 0
-    }
+        self.frame = self.frame.wrapping_add(1);
 
-    #[inline]
-    pub fn right(&self) -> bool {
-        self.data[0] & 0x0100_0000 > 0
-    }
+        si::read_controllers(&mut data);
 
-    #[inline]
-    pub fn l(&self) -> bool {
-        self.data[0] & 0x0020_0000 > 0
+        for (controller, response) in self.ports.iter_mut().zip(data) {
+            controller.connected = response & PIF_NO_RESPONSE == 0;
+            controller.state = if controller.connected {
+                response as u32
+            } else {
+                0
+            };
+        }
     }
 
     #[inline]
-    pub fn r(&self) -> bool {
-        self.data[0] & 0x0010_0000 > 0
+    pub fn port(&self, port: usize) -> &Controller {
+        &self.ports[port]
     }
 
     #[inline]
-    pub fn c_up(&self) -> bool {
-        self.data[0] & 0x0008_0000 > 0
+    pub fn port_mut(&mut self, port: usize) -> &mut Controller {
+        &mut self.ports[port]
     }
 
     #[inline]
-    pub fn c_down(&self) -> bool {
-        self.data[0] & 0x0004_0000 > 0
+    pub fn set_rumble(&mut self, port: usize, on: bool) {
+        self.rumble.set(port, on);
     }
 
+    // Rumbles for the given time, applied from the next update
     #[inline]
-    pub fn c_left(&self) -> bool {
-        self.data[0] & 0x0002_0000 > 0
+    pub fn rumble_pulse(&mut self, port: usize, seconds: f32) {
+        self.rumble.pulse(port, seconds);
     }
 
     #[inline]
-    pub fn c_right(&self) -> bool {
-        self.data[0] & 0x0001_0000 > 0
+    pub fn ports(&self) -> &[Controller; MAX_PORTS] {
+        &self.ports
     }
 }
@@ -0,0 +1,366 @@
+use crate::{gfx::TextureMut, ipl3font, slow_cpu_clear, VideoMode};
+use core::{
+    arch::asm,
+    fmt::{self, Write},
+    mem,
+    panic::PanicInfo,
+    ptr::read_volatile,
+    slice,
+};
+use n64_math::Color;
+use n64_sys::{
+    ed,
+    mi::{self, CriticalSection, ExceptionFrame},
+    sys::{current_time_us, data_cache_hit_writeback},
+    vi,
+};
+use n64_types::{CrashMessageBuffer, MessageType, FRAME_MAX_PAYLOAD};
+use spin::Once;
+use zerocopy::AsBytes;
+
+#[allow(clippy::unusual_byte_groupings)]
+const RED: Color = Color::new(0b10000_00011_00011_1);
+#[allow(clippy::unusual_byte_groupings)]
+const WHITE: Color = Color::new(0b11111_11111_11111_1);
+
+const MARGIN: i32 = 12;
+const LINE_HEIGHT: i32 = ipl3font::GLYPH_HEIGHT + 3;
+const PAGE_TIME_US: i64 = 4_000_000;
+
+const REPORT_SIZE: usize = 2048;
+const MAX_BACKTRACE: usize = 16;
+const MAX_STACK_SCAN: usize = 4096;
+
+const OS_MEM_SIZE: usize = 0x8000_0318;
+const RAM_START: u32 = 0x8000_0000;
+
+const GPR_NAMES: [&str; 32] = [
+    "zr", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4", "t5", "t6", "t7",
+    "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1", "gp", "sp", "fp", "ra",
+];
+
+extern "C" {
+    static __text_start: u8;
+    static __text_end: u8;
+}
+
+struct Text<const N: usize> {
+    data: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Text<N> {
+    const fn new() -> Self {
+        Self {
+            data: [0; N],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.data[..self.len]).unwrap_or("")
+    }
+}
+
+// Text that does not fit is dropped, there is no allocator to rely on after a crash
+impl<const N: usize> Write for Text<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let len = s.len().min(N - self.len);
+        self.data[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+        Ok(())
+    }
+}
+
+type Report = Text<REPORT_SIZE>;
+
+static mut REPORT: Report = Report::new();
+static mut CRASHED: bool = false;
+
+static VIDEO_MODE: Once<VideoMode> = Once::new();
+
+// Shows a crash screen for panics and cpu exceptions, the vi buffer has to be set up first
+pub fn init(video_mode: VideoMode) {
+    VIDEO_MODE.call_once(|| video_mode);
+    mi::set_exception_handler(exception);
+}
+
+// Interrupts stay disabled from here on, a crash while reporting a crash just stops
+fn enter() -> &'static mut Report {
+    mem::forget(CriticalSection::enter());
+
+    unsafe {
+        if CRASHED {
+            #[allow(clippy::empty_loop)]
+            loop {}
+        }
+
+        CRASHED = true;
+
+        &mut REPORT
+    }
+}
+
+// Registers of the panicking function that are still meaningful when the panic handler runs
+#[inline(always)]
+fn capture_frame() -> ExceptionFrame {
+    let mut frame = ExceptionFrame::default();
+    let regs: [u32; 12];
+
+    unsafe {
+        let (s0, s1, s2, s3, s4, s5, s6, s7): (u32, u32, u32, u32, u32, u32, u32, u32);
+        let (gp, sp, fp, ra): (u32, u32, u32, u32);
+
+        asm!(
+            "move {0}, $16",
+            "move {1}, $17",
+            "move {2}, $18",
+            "move {3}, $19",
+            "move {4}, $20",
+            "move {5}, $21",
+            "move {6}, $22",
+            "move {7}, $23",
+            out(reg) s0, out(reg) s1, out(reg) s2, out(reg) s3,
+            out(reg) s4, out(reg) s5, out(reg) s6, out(reg) s7,
+        );
+        asm!(
+            "move {0}, $28",
+            "move {1}, $29",
+            "move {2}, $30",
+            "move {3}, $31",
+            out(reg) gp, out(reg) sp, out(reg) fp, out(reg) ra,
+        );
+
+        regs = [s0, s1, s2, s3, s4, s5, s6, s7, gp, sp, fp, ra];
+    }
+
+    for (i, value) in (16..24).chain(28..32).zip(regs) {
+        frame.gpr[i] = value as u64;
+    }
+
+    frame
+}
+
+fn is_code(address: u32) -> bool {
+    let (start, end) = unsafe {
+        (
+            &__text_start as *const u8 as u32,
+            &__text_end as *const u8 as u32,
+        )
+    };
+
+    address % 4 == 0 && address >= start + 8 && address < end
+}
+
+// A return address points two instructions after a jal or jalr
+fn is_return_address(address: u32) -> bool {
+    if !is_code(address) {
+        return false;
+    }
+
+    let call = unsafe { read_volatile((address - 8) as *const u32) };
+
+    call >> 26 == 0b000011 || (call >> 26 == 0 && call & 0x3f == 0b001001)
+}
+
+#[derive(Copy, Clone, Default)]
+struct TraceEntry {
+    address: u32,
+    is_return_address: bool,
+}
+
+struct Backtrace {
+    entries: [TraceEntry; MAX_BACKTRACE],
+    len: usize,
+}
+
+impl Backtrace {
+    // Without frame information every word on the stack that looks like a return address is
+    // reported, so there can be stale entries from earlier calls
+    fn capture(frame: &ExceptionFrame) -> Self {
+        let mut backtrace = Backtrace {
+            entries: [TraceEntry::default(); MAX_BACKTRACE],
+            len: 0,
+        };
+
+        if is_code(frame.epc) {
+            backtrace.push(frame.epc, false);
+        }
+
+        let ra = frame.gpr[31] as u32;
+        if is_return_address(ra) {
+            backtrace.push(ra, true);
+        }
+
+        let stack_end = RAM_START + unsafe { read_volatile(OS_MEM_SIZE as *const u32) };
+        let sp = frame.gpr[29] as u32 & !3;
+
+        if sp >= RAM_START && sp < stack_end {
+            for address in (sp..stack_end).step_by(4).take(MAX_STACK_SCAN) {
+                let value = unsafe { read_volatile(address as *const u32) };
+
+                if is_return_address(value) {
+                    backtrace.push(value, true);
+                }
+            }
+        }
+
+        backtrace
+    }
+
+    fn push(&mut self, address: u32, is_return_address: bool) {
+        if self.len < MAX_BACKTRACE && self.entries().last().map(|e| e.address) != Some(address) {
+            self.entries[self.len] = TraceEntry {
+                address,
+                is_return_address,
+            };
+            self.len += 1;
+        }
+    }
+
+    fn entries(&self) -> &[TraceEntry] {
+        &self.entries[..self.len]
+    }
+}
+
+fn write_registers(report: &mut Report, frame: &ExceptionFrame) {
+    for (name, value) in GPR_NAMES.iter().zip(frame.gpr).skip(1) {
+        writeln!(report, "{} {:08x}", name, value as u32).ok();
+    }
+
+    writeln!(report, "hi {:08x}", frame.hi as u32).ok();
+    writeln!(report, "lo {:08x}", frame.lo as u32).ok();
+}
+
+fn write_backtrace(report: &mut Report, backtrace: &Backtrace) {
+    writeln!(report, "backtrace").ok();
+
+    for entry in backtrace.entries() {
+        writeln!(report, "  {:08x}", entry.address).ok();
+    }
+}
+
+pub fn panic(info: &PanicInfo) -> ! {
+    let report = enter();
+    let frame = capture_frame();
+
+    writeln!(report, "PANIC!").ok();
+
+    match info.message() {
+        Some(message) => writeln!(report, "{}", message).ok(),
+        None => writeln!(report, "No Message").ok(),
+    };
+
+    if let Some(location) = info.location() {
+        let file = location.file().rsplit(['/', '\\']).next().unwrap_or("");
+        writeln!(report, "{}:{}", file, location.line()).ok();
+    }
+
+    let backtrace = Backtrace::capture(&frame);
+
+    write_registers(report, &frame);
+    write_backtrace(report, &backtrace);
+
+    show(report.as_str(), &backtrace)
+}
+
+fn exception(frame: &mut ExceptionFrame) {
+    let report = enter();
+
+    writeln!(report, "{} exception", frame.exception_name()).ok();
+    writeln!(report, "cause {:08x}", frame.cause).ok();
+    writeln!(report, "epc {:08x}", frame.epc).ok();
+    writeln!(report, "badvaddr {:08x}", frame.bad_vaddr).ok();
+    writeln!(report, "status {:08x}", frame.status).ok();
+
+    let backtrace = Backtrace::capture(frame);
+
+    write_registers(report, frame);
+    write_backtrace(report, &backtrace);
+
+    show(report.as_str(), &backtrace)
+}
+
+fn send(report: &str) {
+    for chunk in report.as_bytes().chunks(FRAME_MAX_PAYLOAD) {
+        ed::send(MessageType::Print, chunk);
+        ed::wait_for_usb();
+    }
+}
+
+// The deploy tool symbolicates the addresses, an empty backtrace is sent as one message with a
+// count of zero
+fn send_backtrace(backtrace: &Backtrace) {
+    for index in 0..backtrace.len.max(1) {
+        let entry = backtrace.entries[index];
+
+        let message = CrashMessageBuffer {
+            index: index as u8,
+            count: backtrace.len as u8,
+            is_return_address: entry.is_return_address as u8,
+            address: entry.address.to_be(),
+        };
+
+        ed::send(MessageType::Crash, message.as_bytes());
+        ed::wait_for_usb();
+    }
+}
+
+// Long lines are wrapped and the report is split into pages that are shown one at a time
+fn show(report: &str, backtrace: &Backtrace) -> ! {
+    send(report);
+    send_backtrace(backtrace);
+
+    let Some(&video_mode) = VIDEO_MODE.get() else {
+        #[allow(clippy::empty_loop)]
+        loop {}
+    };
+
+    let mut out_tex = TextureMut::new(video_mode.width(), video_mode.height(), unsafe {
+        slice::from_raw_parts_mut(
+            vi::get_vi_buffer(),
+            (video_mode.width() * video_mode.height()) as usize,
+        )
+    });
+
+    let line_chars = ((out_tex.width - 2 * MARGIN) / (ipl3font::GLYPH_WIDTH + 1)).max(1) as usize;
+    let page_lines = ((out_tex.height - 2 * MARGIN) / LINE_HEIGHT - 1).max(1) as usize;
+
+    let lines = || {
+        report
+            .lines()
+            .flat_map(move |line| line.as_bytes().chunks(line_chars))
+    };
+
+    let pages = (lines().count() + page_lines - 1) / page_lines;
+    let mut page = 0;
+
+    loop {
+        slow_cpu_clear(out_tex.data);
+
+        let mut title = Text::<32>::new();
+        write!(title, "CRASH {}/{}", page + 1, pages).ok();
+
+        ipl3font::draw_str(&mut out_tex, MARGIN, MARGIN, RED, title.as_str().as_bytes());
+
+        for (i, line) in lines().skip(page * page_lines).take(page_lines).enumerate() {
+            ipl3font::draw_str(
+                &mut out_tex,
+                MARGIN,
+                MARGIN + (i as i32 + 1) * LINE_HEIGHT,
+                WHITE,
+                line,
+            );
+        }
+
+        unsafe {
+            data_cache_hit_writeback(out_tex.data);
+            vi::set_vi_buffer(out_tex.data);
+        }
+
+        let start = current_time_us();
+        while current_time_us() - start < PAGE_TIME_US {}
+
+        page = (page + 1) % pages.max(1);
+    }
+}
@@ -0,0 +1,50 @@
+use crate::save_file_emu::SaveFile;
+use n64_sys::eeprom::{EepromError, EepromType, EEPROM_BLOCK_SIZE};
+
+const EEPROM_PATH_ENV: &str = "N64_EEPROM_PATH";
+const DEFAULT_EEPROM_PATH: &str = "save.eeprom";
+
+// A 16K EEPROM backed by a file
+pub struct Eeprom {
+    eeprom_type: EepromType,
+    file: SaveFile,
+}
+
+impl Eeprom {
+    #[inline]
+    pub fn new() -> Option<Eeprom> {
+        let eeprom_type = EepromType::Eeprom16k;
+
+        Some(Eeprom {
+            eeprom_type,
+            file: SaveFile::open(EEPROM_PATH_ENV, DEFAULT_EEPROM_PATH, eeprom_type.size(), 0),
+        })
+    }
+
+    #[inline]
+    pub fn eeprom_type(&self) -> EepromType {
+        self.eeprom_type
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.eeprom_type.size()
+    }
+
+    // Offset and length have to be multiples of the block size
+    pub fn read(&mut self, offset: usize, data: &mut [u8]) -> Result<(), EepromError> {
+        assert!(offset % EEPROM_BLOCK_SIZE == 0 && data.len() % EEPROM_BLOCK_SIZE == 0);
+
+        self.file.read(offset, data);
+
+        Ok(())
+    }
+
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), EepromError> {
+        assert!(offset % EEPROM_BLOCK_SIZE == 0 && data.len() % EEPROM_BLOCK_SIZE == 0);
+
+        self.file.write(offset, data);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+#![cfg_attr(not(target_vendor = "nintendo64"), allow(unused))]
+
+use n64_sys::{
+    eeprom::{self, EepromError, EepromType, EEPROM_BLOCK_SIZE},
+    si::SiPif,
+};
+
+pub struct Eeprom {
+    eeprom_type: EepromType,
+}
+
+impl Eeprom {
+    // None when the cartridge has no EEPROM
+    #[inline]
+    pub fn new() -> Option<Eeprom> {
+        eeprom::detect(&mut SiPif).map(|eeprom_type| Eeprom { eeprom_type })
+    }
+
+    #[inline]
+    pub fn eeprom_type(&self) -> EepromType {
+        self.eeprom_type
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.eeprom_type.size()
+    }
+
+    // Offset and length have to be multiples of the block size
+    pub fn read(&mut self, offset: usize, data: &mut [u8]) -> Result<(), EepromError> {
+        assert!(offset % EEPROM_BLOCK_SIZE == 0 && data.len() % EEPROM_BLOCK_SIZE == 0);
+        assert!(offset + data.len() <= self.size());
+
+        for (i, chunk) in data.chunks_exact_mut(EEPROM_BLOCK_SIZE).enumerate() {
+            let block = (offset / EEPROM_BLOCK_SIZE + i) as u8;
+            eeprom::read_block(&mut SiPif, block, chunk.try_into().unwrap())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), EepromError> {
+        assert!(offset % EEPROM_BLOCK_SIZE == 0 && data.len() % EEPROM_BLOCK_SIZE == 0);
+        assert!(offset + data.len() <= self.size());
+
+        for (i, chunk) in data.chunks_exact(EEPROM_BLOCK_SIZE).enumerate() {
+            let block = (offset / EEPROM_BLOCK_SIZE + i) as u8;
+            eeprom::write_block(&mut SiPif, block, chunk.try_into().unwrap())?;
+        }
+
+        Ok(())
+    }
+}
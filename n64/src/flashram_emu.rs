@@ -0,0 +1,43 @@
+use crate::save_file_emu::SaveFile;
+use n64_sys::flashram::{FlashRamError, FLASHRAM_SIZE};
+
+const FLASHRAM_PATH_ENV: &str = "N64_FLASHRAM_PATH";
+const DEFAULT_FLASHRAM_PATH: &str = "save.flashram";
+
+pub struct FlashRam {
+    file: SaveFile,
+}
+
+impl FlashRam {
+    #[inline]
+    pub fn new() -> Option<FlashRam> {
+        Some(FlashRam {
+            file: SaveFile::open(
+                FLASHRAM_PATH_ENV,
+                DEFAULT_FLASHRAM_PATH,
+                FLASHRAM_SIZE,
+                0xff,
+            ),
+        })
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        FLASHRAM_SIZE
+    }
+
+    // Offset and length have to be even
+    pub fn read(&mut self, offset: usize, data: &mut [u8]) {
+        assert!(offset % 2 == 0 && data.len() % 2 == 0);
+
+        self.file.read(offset, data);
+    }
+
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), FlashRamError> {
+        assert!(offset % 2 == 0 && data.len() % 2 == 0);
+
+        self.file.write(offset, data);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+#![cfg_attr(not(target_vendor = "nintendo64"), allow(unused))]
+
+use alloc::vec;
+use n64_sys::flashram::{
+    self, FlashRamError, FLASHRAM_PAGE_SIZE, FLASHRAM_SECTOR_SIZE, FLASHRAM_SIZE,
+};
+
+pub struct FlashRam {}
+
+impl FlashRam {
+    // None when the cartridge has no FlashRAM
+    #[inline]
+    pub fn new() -> Option<FlashRam> {
+        if flashram::detect() {
+            Some(FlashRam {})
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        FLASHRAM_SIZE
+    }
+
+    // Offset and length have to be even
+    #[inline]
+    pub fn read(&mut self, offset: usize, data: &mut [u8]) {
+        flashram::read(offset, data);
+    }
+
+    // Every sector touched by the write is read back, erased and programmed again
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), FlashRamError> {
+        assert!(offset % 2 == 0 && data.len() % 2 == 0);
+        assert!(offset + data.len() <= FLASHRAM_SIZE);
+
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let end = offset + data.len();
+        let mut sector_data = vec![0; FLASHRAM_SECTOR_SIZE];
+
+        for sector in offset / FLASHRAM_SECTOR_SIZE..=(end - 1) / FLASHRAM_SECTOR_SIZE {
+            let sector_start = sector * FLASHRAM_SECTOR_SIZE;
+            let copy_start = offset.max(sector_start);
+            let copy_end = end.min(sector_start + FLASHRAM_SECTOR_SIZE);
+
+            flashram::read(sector_start, &mut sector_data);
+
+            sector_data[copy_start - sector_start..copy_end - sector_start]
+                .copy_from_slice(&data[copy_start - offset..copy_end - offset]);
+
+            flashram::erase_sector(sector)?;
+
+            for (i, page) in sector_data.chunks_exact(FLASHRAM_PAGE_SIZE).enumerate() {
+                // Erased pages are already all 0xff
+                if page.iter().any(|byte| *byte != 0xff) {
+                    let page_index = sector_start / FLASHRAM_PAGE_SIZE + i;
+                    flashram::program_page(page_index, page.try_into().unwrap())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
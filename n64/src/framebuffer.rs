@@ -8,7 +8,7 @@ pub struct ViBufferToken(pub(crate) *mut Color);
 pub struct GpuFramebuffer(pub(crate) Box<[Color]>);
 
 pub struct Framebuffer {
-    video_mode: VideoMode,
+    pub(crate) video_mode: VideoMode,
     pub(crate) vi_buffer: ViFramebuffer,
     pub(crate) gpu_buffer: GpuFramebuffer,
 }
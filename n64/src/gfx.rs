@@ -1,6 +1,10 @@
 pub use command_buffer::{CommandBuffer, CommandBufferCache};
-pub use pipeline::{CycleType, FillPipeline, Pipeline, ZMode, ZSrc};
-pub use texture::{StaticTexture, Texture, TextureAlignment, TextureMut};
+pub use debug_draw::{debug_draw_enabled, set_debug_draw_enabled};
+pub use lights::{DirectionalLight, Lights, MAX_DIRECTIONAL_LIGHTS};
+pub use pipeline::{CullMode, CycleType, FillPipeline, Pipeline, ZMode, ZSrc};
+pub use post_effect::PostEffect;
+pub use sprite::Sprite;
+pub use texture::{StaticTexture, Texture, TextureAlignment, TextureMut, TextureRect};
 
 mod command_buffer_n64;
 
@@ -15,5 +19,10 @@ use command_buffer_emu as command_buffer;
 
 pub mod blend_mode;
 pub mod color_combiner_mode;
+mod culling;
+mod debug_draw;
+mod lights;
 mod pipeline;
+mod post_effect;
+mod sprite;
 mod texture;
@@ -109,6 +109,33 @@ impl BlendMode {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub const fn two(
+        p_0: PMCycleOne,
+        a_0: ASrc,
+        m_0: PMCycleOne,
+        b_0: BSrc,
+        p_1: PMCycleTwo,
+        a_1: ASrc,
+        m_1: PMCycleTwo,
+        b_1: BSrc,
+    ) -> Self {
+        Self {
+            p_0,
+            a_0,
+            m_0,
+            b_0,
+
+            p_1,
+            a_1,
+            m_1,
+            b_1,
+
+            rgb_dither: RgbDither::NoDither,
+            alpha_dither: AlphaDither::NoDither,
+        }
+    }
+
     pub const fn simple(p: PMCycleOne, m: PMCycleOne) -> Self {
         Self {
             p_0: p,
@@ -53,6 +53,7 @@ pub enum CSrc {
     Environment = 5,
     CombinedAlpha = 7,
     TexelAlpha = 8,
+    Texel1Alpha = 9,
     PrimitiveAlpha = 10,
     ShadeAlpha = 11,
     EnvironmentAlpha = 12,
@@ -103,6 +104,7 @@ pub enum BAlphaSrc {
 pub enum CAlphaSrc {
     CombinedAlphaInvalid = 0,
     TexelAlpha = 1,
+    Texel1Alpha = 2,
     PrimitiveAlpha = 3,
     ShadeAlpha = 4,
     EnvironmentAlpha = 5,
@@ -394,6 +396,7 @@ impl CSrc {
             CSrc::Environment => CAlphaSrc::EnvironmentAlpha,
             CSrc::CombinedAlpha => CAlphaSrc::CombinedAlphaInvalid,
             CSrc::TexelAlpha => CAlphaSrc::TexelAlpha,
+            CSrc::Texel1Alpha => CAlphaSrc::Texel1Alpha,
             CSrc::PrimitiveAlpha => CAlphaSrc::PrimitiveAlpha,
             CSrc::ShadeAlpha => CAlphaSrc::ShadeAlpha,
             CSrc::EnvironmentAlpha => CAlphaSrc::EnvironmentAlpha,
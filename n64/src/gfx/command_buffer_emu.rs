@@ -1,23 +1,31 @@
-use super::{FillPipeline, Pipeline};
+use super::{
+    culling::{mesh_is_outside_screen, triangle_is_culled},
+    debug_draw::{self, DebugLine, DEBUG_DRAW_PIPELINE},
+    lights::color_to_vec3,
+    post_effect::{fade_overlay_color, screen_corners, POST_EFFECT_PIPELINE},
+    CullMode, FillPipeline, Lights, Pipeline, PostEffect, Sprite, TextureRect,
+    MAX_DIRECTIONAL_LIGHTS,
+};
 use crate::{
     framebuffer::ViBufferToken,
     graphics::QUAD_INDEX_DATA,
     graphics_emu::{
         colored_rect::{ColoredRectUniforms, MAX_COLORED_RECTS},
+        desaturate::{DesaturateUniforms, MAX_DESATURATES},
         dst_texture::DstTexture,
         textured_rect::{TexturedRectUniforms, MAX_TEXTURED_RECTS},
         Graphics,
     },
 };
 use crate::{graphics_emu::mesh::MeshUniforms, graphics_emu::mesh::MAX_MESHES};
-use alloc::sync::Arc;
+use alloc::{borrow::Cow, sync::Arc};
 use assert_into::AssertInto;
 use core::{
     slice,
     sync::atomic::{AtomicBool, Ordering},
     time::Duration,
 };
-use n64_math::{Color, Vec2};
+use n64_math::{Color, Mat4, Vec2, Vec3};
 use n64_profiler::scope;
 use n64_types::VideoMode;
 use std::mem;
@@ -34,22 +42,31 @@ enum Command {
     TexturedRect {
         upper_left: Vec2,
         lower_right: Vec2,
+        uv_upper_left: Vec2,
+        uv_lower_right: Vec2,
         pipeline: Pipeline,
     },
     Mesh {
         verts: Vec<[f32; 3]>,
         uvs: Vec<[f32; 2]>,
         colors: Vec<u32>,
+        normals: Cow<'static, [[f32; 3]]>,
         indices: Vec<u8>,
         transform: [[f32; 4]; 4],
+        lighting: Option<(Lights, [[f32; 4]; 4])>,
         pipeline: Pipeline,
         buffer_index: usize,
     },
+    Desaturate {
+        amount: f32,
+    },
 }
 
 pub struct CommandBufferCache {
     video_mode: VideoMode,
     commands: Vec<Command>,
+    debug_lines: Vec<DebugLine>,
+    post_effects: Vec<PostEffect>,
 }
 
 impl CommandBufferCache {
@@ -57,10 +74,25 @@ impl CommandBufferCache {
         Self {
             video_mode,
             commands: Vec::new(),
+            debug_lines: Vec::new(),
+            post_effects: Vec::new(),
         }
     }
 }
 
+// Mesh indices are u8 and every line is a quad
+const DEBUG_LINES_PER_MESH: usize = 64;
+
+// Meshes indexed with u8 share this buffer, anything with more verts gets its own
+static ZERO_NORMALS: [[f32; 3]; 256] = [[0.0; 3]; 256];
+
+fn zero_normals(len: usize) -> Cow<'static, [[f32; 3]]> {
+    match ZERO_NORMALS.get(..len) {
+        Some(normals) => Cow::Borrowed(normals),
+        None => Cow::Owned(vec![[0.0; 3]; len]),
+    }
+}
+
 #[derive(Copy, Clone)]
 enum EmuPipeline {
     Pipeline(Pipeline),
@@ -89,20 +121,36 @@ pub struct CommandBuffer<'a> {
     colored_rect_count: u32,
     textured_rect_count: u32,
     mesh_count: u32,
+    culled_mesh_count: u32,
+    culled_triangle_count: u32,
+    sprite_mesh_count: u32,
+    debug_mesh_count: u32,
+    post_effect_count: u32,
     current_pipeline: Option<EmuPipeline>,
+    lights: Lights,
+    screen_offset: Vec2,
     cache: &'a mut CommandBufferCache,
 }
 
 impl<'a> CommandBuffer<'a> {
     pub fn new(out_tex: ViBufferToken, cache: &'a mut CommandBufferCache) -> Self {
         cache.commands.clear();
+        cache.debug_lines.clear();
+        cache.post_effects.clear();
         Self {
             out_tex,
             clear: false,
             colored_rect_count: 0,
             textured_rect_count: 0,
             mesh_count: 0,
+            culled_mesh_count: 0,
+            culled_triangle_count: 0,
+            sprite_mesh_count: 0,
+            debug_mesh_count: 0,
+            post_effect_count: 0,
             current_pipeline: None,
+            lights: Lights::default(),
+            screen_offset: Vec2::ZERO,
             cache,
         }
     }
@@ -122,31 +170,176 @@ impl<'a> CommandBuffer<'a> {
         self
     }
 
+    pub fn set_lights(&mut self, lights: &Lights) -> &mut Self {
+        self.lights = *lights;
+        self
+    }
+
+    // Moves everything drawn after this call, used for screen shake
+    pub fn set_screen_offset(&mut self, offset: Vec2) -> &mut Self {
+        self.screen_offset = offset;
+        self
+    }
+
+    pub fn add_post_effect(&mut self, effect: PostEffect) -> &mut Self {
+        if effect.is_visible() {
+            self.cache.post_effects.push(effect);
+        }
+        self
+    }
+
     pub fn add_colored_rect(&mut self, upper_left: Vec2, lower_right: Vec2) -> &mut Self {
         self.colored_rect_count += 1;
         self.cache.commands.push(Command::ColoredRect {
-            upper_left,
-            lower_right,
+            upper_left: upper_left + self.screen_offset,
+            lower_right: lower_right + self.screen_offset,
             pipeline: self.current_pipeline.expect("No pipelien set"),
         });
 
         self
     }
 
-    pub fn add_textured_rect(&mut self, upper_left: Vec2, lower_right: Vec2) -> &mut Self {
+    pub fn add_textured_rect(
+        &mut self,
+        upper_left: Vec2,
+        lower_right: Vec2,
+        sub_rect: Option<TextureRect>,
+    ) -> &mut Self {
+        let pipeline = *self
+            .current_pipeline
+            .expect("No pipeline has been set on the command buffer")
+            .as_pipeline();
+
+        let Some(texture) = pipeline.texture else {
+            return self;
+        };
+
+        let (uv_upper_left, uv_lower_right) = match sub_rect {
+            Some(rect) => {
+                let texture_size = Vec2::new(texture.width as f32, texture.height as f32);
+                let start = Vec2::new(rect.x as f32, rect.y as f32);
+                let size = Vec2::new(rect.width as f32, rect.height as f32);
+                (start / texture_size, (start + size) / texture_size)
+            }
+            None => (Vec2::ZERO, Vec2::ONE),
+        };
+
         self.textured_rect_count += 1;
         self.cache.commands.push(Command::TexturedRect {
-            upper_left,
-            lower_right,
-            pipeline: *self
-                .current_pipeline
-                .expect("No pipeline has been set on the command buffer")
-                .as_pipeline(),
+            upper_left: upper_left + self.screen_offset,
+            lower_right: lower_right + self.screen_offset,
+            uv_upper_left,
+            uv_lower_right,
+            pipeline,
         });
 
         self
     }
 
+    pub fn add_sprite(&mut self, sprite: &Sprite) -> &mut Self {
+        let mut pipeline = *self
+            .current_pipeline
+            .expect("No pipeline has been set on the command buffer")
+            .as_pipeline();
+
+        if pipeline.texture.is_none() {
+            return self;
+        }
+
+        let (upper_left, lower_right) = sprite.screen_rect();
+
+        if lower_right.x <= upper_left.x || lower_right.y <= upper_left.y {
+            return self;
+        }
+
+        self.textured_rect_count += 1;
+
+        if let Some(tint) = sprite.tint {
+            pipeline.prim_color = Some(tint);
+        }
+
+        if sprite.is_axis_aligned() {
+            let (uv_upper_left, uv_lower_right) = sprite.uv_rect();
+
+            self.cache.commands.push(Command::TexturedRect {
+                upper_left: upper_left + self.screen_offset,
+                lower_right: lower_right + self.screen_offset,
+                uv_upper_left,
+                uv_lower_right,
+                pipeline,
+            });
+        } else {
+            self.sprite_mesh_count += 1;
+
+            let corners = sprite.corners();
+            let color = sprite.tint.unwrap_or(0xffffffff);
+
+            self.cache.commands.push(Command::Mesh {
+                verts: corners
+                    .iter()
+                    .map(|(pos, _)| (*pos + self.screen_offset).extend(0.0).to_array())
+                    .collect(),
+                uvs: corners.iter().map(|(_, uv)| uv.to_array()).collect(),
+                colors: vec![color; 4],
+                normals: zero_normals(4),
+                indices: vec![0, 1, 2, 0, 2, 3],
+                transform: Mat4::IDENTITY.to_cols_array_2d(),
+                lighting: None,
+                pipeline,
+                buffer_index: 0,
+            });
+        }
+
+        self
+    }
+
+    pub fn add_sprite_batch(&mut self, sprites: &[Sprite]) -> &mut Self {
+        for sprite in sprites {
+            self.add_sprite(sprite);
+        }
+        self
+    }
+
+    // Debug primitives are moved by the screen offset like everything else
+    pub fn add_debug_line(&mut self, start: Vec2, end: Vec2, color: u32) -> &mut Self {
+        let offset = self.screen_offset;
+        debug_draw::push_line(
+            &mut self.cache.debug_lines,
+            start + offset,
+            end + offset,
+            color,
+        );
+        self
+    }
+
+    pub fn add_debug_rect(&mut self, upper_left: Vec2, lower_right: Vec2, color: u32) -> &mut Self {
+        let offset = self.screen_offset;
+        debug_draw::push_rect(
+            &mut self.cache.debug_lines,
+            upper_left + offset,
+            lower_right + offset,
+            color,
+        );
+        self
+    }
+
+    pub fn add_debug_circle(&mut self, center: Vec2, radius: f32, color: u32) -> &mut Self {
+        let offset = self.screen_offset;
+        debug_draw::push_circle(&mut self.cache.debug_lines, center + offset, radius, color);
+        self
+    }
+
+    pub fn add_debug_arrow(&mut self, start: Vec2, end: Vec2, color: u32) -> &mut Self {
+        let offset = self.screen_offset;
+        debug_draw::push_arrow(
+            &mut self.cache.debug_lines,
+            start + offset,
+            end + offset,
+            color,
+        );
+        self
+    }
+
     pub fn add_mesh_indexed(
         &mut self,
         verts: &[[f32; 3]],
@@ -154,26 +347,209 @@ impl<'a> CommandBuffer<'a> {
         colors: &[u32],
         indices: &[[u8; 3]],
         transform: &[[f32; 4]; 4],
+        size: Option<Vec2>,
     ) -> &mut Self {
+        if self.mesh_is_culled(transform, size) {
+            return self;
+        }
+
+        self.mesh_count += 1;
+
+        let pipeline = *self
+            .current_pipeline
+            .expect("No pipeline has been set on the command buffer")
+            .as_pipeline();
+
+        let indices = self.visible_indices(verts, indices, transform, pipeline.cull_mode);
+
+        if indices.is_empty() {
+            return self;
+        }
+
+        self.cache.commands.push(Command::Mesh {
+            verts: verts.to_owned(),
+            uvs: uvs.to_owned(),
+            colors: colors.to_owned(),
+            normals: zero_normals(verts.len()),
+            indices,
+            transform: self.offset_transform(transform),
+            lighting: None,
+            pipeline,
+            buffer_index: 0,
+        });
+
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_lit_mesh_indexed(
+        &mut self,
+        verts: &[[f32; 3]],
+        uvs: &[[f32; 2]],
+        colors: &[u32],
+        normals: &[[f32; 3]],
+        indices: &[[u8; 3]],
+        transform: &[[f32; 4]; 4],
+        normal_transform: &[[f32; 4]; 4],
+        size: Option<Vec2>,
+    ) -> &mut Self {
+        if self.mesh_is_culled(transform, size) {
+            return self;
+        }
+
         self.mesh_count += 1;
 
+        let pipeline = *self
+            .current_pipeline
+            .expect("No pipeline has been set on the command buffer")
+            .as_pipeline();
+
+        let indices = self.visible_indices(verts, indices, transform, pipeline.cull_mode);
+
+        if indices.is_empty() {
+            return self;
+        }
+
         self.cache.commands.push(Command::Mesh {
             verts: verts.to_owned(),
             uvs: uvs.to_owned(),
             colors: colors.to_owned(),
-            indices: indices.iter().flatten().copied().collect(),
-            transform: *transform,
-            pipeline: *self
-                .current_pipeline
-                .expect("No pipeline has been set on the command buffer")
-                .as_pipeline(),
+            normals: Cow::Owned(normals.to_owned()),
+            indices,
+            transform: self.offset_transform(transform),
+            lighting: Some((self.lights, *normal_transform)),
+            pipeline,
             buffer_index: 0,
         });
 
         self
     }
 
-    pub fn submit(self, graphics: &mut Graphics, _step: bool) -> (i32, i32, i32, i32) {
+    fn mesh_is_culled(&mut self, transform: &[[f32; 4]; 4], size: Option<Vec2>) -> bool {
+        if let Some(size) = size {
+            if mesh_is_outside_screen(
+                &Mat4::from_cols_array_2d(transform),
+                size,
+                self.cache.video_mode,
+            ) {
+                self.culled_mesh_count += 1;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // Translating after the projection moves the mesh in screen space independent of depth
+    fn offset_transform(&self, transform: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+        (Mat4::from_translation(self.screen_offset.extend(0.0))
+            * Mat4::from_cols_array_2d(transform))
+        .to_cols_array_2d()
+    }
+
+    fn visible_indices(
+        &mut self,
+        verts: &[[f32; 3]],
+        indices: &[[u8; 3]],
+        transform: &[[f32; 4]; 4],
+        cull_mode: CullMode,
+    ) -> Vec<u8> {
+        if cull_mode == CullMode::None {
+            return indices.iter().flatten().copied().collect();
+        }
+
+        let transform = Mat4::from_cols_array_2d(transform);
+        let project = |index: u8| transform.project_point3(Vec3::from(verts[index as usize]));
+
+        let mut res = Vec::with_capacity(3 * indices.len());
+
+        for triangle in indices {
+            if triangle_is_culled(
+                cull_mode,
+                project(triangle[0]),
+                project(triangle[1]),
+                project(triangle[2]),
+            ) {
+                self.culled_triangle_count += 1;
+                continue;
+            }
+
+            res.extend_from_slice(triangle);
+        }
+
+        res
+    }
+
+    fn draw_post_effects(&mut self) {
+        let screen_size = Vec2::new(
+            self.cache.video_mode.width() as f32,
+            self.cache.video_mode.height() as f32,
+        );
+        let corners = screen_corners(screen_size);
+
+        for effect in self.cache.post_effects.iter() {
+            match *effect {
+                PostEffect::Fade { color, amount } => {
+                    self.post_effect_count += 1;
+
+                    self.cache.commands.push(Command::Mesh {
+                        verts: corners.iter().map(|c| [c.x, c.y, 0.0]).collect(),
+                        uvs: vec![[0.0; 2]; 4],
+                        colors: vec![0; 4],
+                        normals: zero_normals(4),
+                        indices: vec![0, 1, 2, 0, 2, 3],
+                        transform: Mat4::IDENTITY.to_cols_array_2d(),
+                        lighting: None,
+                        pipeline: POST_EFFECT_PIPELINE
+                            .with_prim_color(Some(fade_overlay_color(color, amount))),
+                        buffer_index: 0,
+                    });
+                }
+                PostEffect::Desaturate { amount } => {
+                    self.cache.commands.push(Command::Desaturate { amount });
+                }
+            }
+        }
+    }
+
+    fn draw_debug_lines(&mut self) {
+        for lines in self.cache.debug_lines.chunks(DEBUG_LINES_PER_MESH) {
+            self.debug_mesh_count += 1;
+
+            let mut verts = Vec::with_capacity(4 * lines.len());
+            let mut colors = Vec::with_capacity(4 * lines.len());
+            let mut indices = Vec::with_capacity(6 * lines.len());
+
+            for line in lines {
+                let base = verts.len() as u8;
+
+                verts.extend(line.corners().iter().map(|c| [c.x, c.y, 0.0]));
+                colors.extend([line.color; 4]);
+                indices.extend([0, 1, 2, 0, 2, 3].map(|i| base + i));
+            }
+
+            self.cache.commands.push(Command::Mesh {
+                uvs: vec![[0.0; 2]; verts.len()],
+                normals: zero_normals(verts.len()),
+                verts,
+                colors,
+                indices,
+                transform: Mat4::IDENTITY.to_cols_array_2d(),
+                lighting: None,
+                pipeline: DEBUG_DRAW_PIPELINE,
+                buffer_index: 0,
+            });
+        }
+    }
+
+    pub fn submit(
+        mut self,
+        graphics: &mut Graphics,
+        _step: bool,
+    ) -> (i32, i32, i32, i32, i32, i32) {
+        self.draw_post_effects();
+        self.draw_debug_lines();
+
         let dst = DstTexture::new(
             &graphics.device,
             self.cache.video_mode.width(),
@@ -186,7 +562,13 @@ impl<'a> CommandBuffer<'a> {
 
         assert!(self.colored_rect_count <= MAX_COLORED_RECTS as u32);
         assert!(self.textured_rect_count <= MAX_TEXTURED_RECTS as u32);
-        assert!(self.mesh_count <= MAX_MESHES as u32);
+        assert!(
+            self.mesh_count
+                + self.sprite_mesh_count
+                + self.post_effect_count
+                + self.debug_mesh_count
+                <= MAX_MESHES as u32
+        );
 
         let command_buf = {
             let mut encoder = graphics
@@ -202,6 +584,7 @@ impl<'a> CommandBuffer<'a> {
                 let mut textured_rect_uniforms =
                     Vec::with_capacity(self.textured_rect_count as usize);
                 let mut mesh_uniforms = Vec::with_capacity(self.mesh_count as usize);
+                let mut desaturate_uniforms = Vec::new();
 
                 for command in &mut self.cache.commands {
                     match command {
@@ -226,6 +609,8 @@ impl<'a> CommandBuffer<'a> {
                         Command::TexturedRect {
                             upper_left,
                             lower_right,
+                            uv_upper_left,
+                            uv_lower_right,
                             pipeline,
                         } => {
                             let texture = pipeline.texture.expect("Invalid pipeline");
@@ -248,9 +633,13 @@ impl<'a> CommandBuffer<'a> {
                             let blend_color = pipeline.blend_color.unwrap_or(0);
                             let fog_color = pipeline.fog_color.unwrap_or(0);
 
+                            let uv_scale = *uv_lower_right - *uv_upper_left;
+
                             textured_rect_uniforms.push(TexturedRectUniforms {
                                 offset: [offset_x, offset_y],
                                 scale: [scale.x, scale.y],
+                                uv_offset: [uv_upper_left.x, uv_upper_left.y],
+                                uv_scale: [uv_scale.x, uv_scale.y],
                                 combine_mode: [
                                     ((color_combiner_mode >> 32) & u32::MAX as u64) as u32,
                                     (color_combiner_mode & u32::MAX as u64) as u32,
@@ -289,8 +678,10 @@ impl<'a> CommandBuffer<'a> {
                             verts,
                             uvs,
                             colors,
+                            normals,
                             indices,
                             transform,
+                            lighting,
                             pipeline,
                             buffer_index,
                             ..
@@ -301,14 +692,19 @@ impl<'a> CommandBuffer<'a> {
                                 pos: [f32; 3],
                                 tex_coord: [f32; 2],
                                 color: [f32; 4],
+                                normal: [f32; 3],
                             }
 
                             assert!(verts.len() == uvs.len());
                             assert!(verts.len() == colors.len());
+                            assert!(verts.len() == normals.len());
 
                             let mut vertices = Vec::with_capacity(verts.len());
 
-                            for (v, (t, c)) in verts.iter().zip(uvs.iter().zip(colors.iter())) {
+                            for (v, (t, (c, n))) in verts
+                                .iter()
+                                .zip(uvs.iter().zip(colors.iter().zip(normals.iter())))
+                            {
                                 vertices.push(MeshVertex {
                                     pos: *v,
                                     tex_coord: *t,
@@ -318,6 +714,7 @@ impl<'a> CommandBuffer<'a> {
                                         ((*c >> 8) & 0xff) as f32 / 255.0,
                                         (*c & 0xff) as f32 / 255.0,
                                     ],
+                                    normal: *n,
                                 });
                             }
 
@@ -365,8 +762,29 @@ impl<'a> CommandBuffer<'a> {
                             let blend_color = pipeline.blend_color.unwrap_or(0);
                             let fog_color = pipeline.fog_color.unwrap_or(0);
 
+                            let mut normal_transform = [[0.0; 4]; 4];
+                            let mut ambient_color = [1.0; 4];
+                            let mut light_directions = [[0.0; 4]; MAX_DIRECTIONAL_LIGHTS];
+                            let mut light_colors = [[0.0; 4]; MAX_DIRECTIONAL_LIGHTS];
+                            let mut light_count = 0;
+
+                            if let Some((lights, lights_normal_transform)) = lighting {
+                                normal_transform = *lights_normal_transform;
+                                ambient_color =
+                                    color_to_vec3(lights.ambient_color).extend(1.0).to_array();
+
+                                for light in lights.directional.iter().flatten() {
+                                    light_directions[light_count] =
+                                        light.direction.normalize_or_zero().extend(0.0).to_array();
+                                    light_colors[light_count] =
+                                        color_to_vec3(light.color).extend(1.0).to_array();
+                                    light_count += 1;
+                                }
+                            }
+
                             mesh_uniforms.push(MeshUniforms {
                                 transform: *transform,
+                                normal_transform,
                                 screen_size_and_pad: [
                                     graphics.video_mode.width() as f32,
                                     graphics.video_mode.height() as f32,
@@ -405,11 +823,22 @@ impl<'a> CommandBuffer<'a> {
                                     ((fog_color >> 8) & 0xff) as f32 / 255.0,
                                     (fog_color & 0xff) as f32 / 255.0,
                                 ],
+                                ambient_color,
+                                light_directions,
+                                light_colors,
+                                lighting: [lighting.is_some() as u32, light_count as u32, 0, 0],
+                            });
+                        }
+                        Command::Desaturate { amount } => {
+                            desaturate_uniforms.push(DesaturateUniforms {
+                                amount: [*amount, 0.0, 0.0, 0.0],
                             });
                         }
                     }
                 }
 
+                assert!(desaturate_uniforms.len() <= MAX_DESATURATES as usize);
+
                 if !colored_rect_uniforms.is_empty() {
                     let temp_buffer =
                         graphics
@@ -468,48 +897,43 @@ impl<'a> CommandBuffer<'a> {
                         (mesh_uniforms.len() * mem::size_of::<MeshUniforms>()) as u64,
                     );
                 }
+
+                if !desaturate_uniforms.is_empty() {
+                    let temp_buffer =
+                        graphics
+                            .device
+                            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                                label: None,
+                                contents: desaturate_uniforms.as_bytes(),
+                                usage: wgpu::BufferUsages::COPY_SRC,
+                            });
+
+                    encoder.copy_buffer_to_buffer(
+                        &temp_buffer,
+                        0,
+                        &graphics.desaturate.shader_storage_buffer,
+                        0,
+                        (desaturate_uniforms.len() * mem::size_of::<DesaturateUniforms>()) as u64,
+                    );
+                }
             }
 
-            {
-                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: None,
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &dst.tex_view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: if self.clear {
-                                wgpu::LoadOp::Clear(wgpu::Color {
-                                    r: 0.0,
-                                    g: 0.0,
-                                    b: 0.0,
-                                    a: 1.0,
-                                })
-                            } else {
-                                wgpu::LoadOp::Load
-                            },
-                            store: true,
-                        },
-                    })],
-                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                        view: &dst.depth_view,
-                        depth_ops: Some(wgpu::Operations {
-                            load: if self.clear {
-                                wgpu::LoadOp::Clear(1.0)
-                            } else {
-                                wgpu::LoadOp::Load
-                            },
-                            store: true,
-                        }),
-                        stencil_ops: None,
-                    }),
-                });
+            let mut colored_rect_index = 0;
+            let mut textured_rect_index = 0;
+            let mut mesh_index = 0;
+            let mut desaturate_index = 0;
+
+            let mut commands = self.cache.commands.iter();
+            let mut clear = self.clear;
+
+            // Desaturating samples everything drawn before it, so it ends the current pass
+            loop {
+                let mut desaturate = false;
 
                 {
-                    let mut colored_rect_index = 0;
-                    let mut textured_rect_index = 0;
-                    let mut mesh_index = 0;
+                    let mut render_pass = begin_render_pass(&mut encoder, &dst, clear);
 
-                    for command in &self.cache.commands {
+                    for command in commands.by_ref() {
                         match command {
                             Command::ColoredRect { .. } => {
                                 render_pass.set_index_buffer(
@@ -615,9 +1039,43 @@ impl<'a> CommandBuffer<'a> {
                                 );
                                 mesh_index += 1;
                             }
+                            Command::Desaturate { .. } => {
+                                desaturate = true;
+                                break;
+                            }
                         }
                     }
                 }
+
+                if !desaturate {
+                    break;
+                }
+
+                encoder.copy_texture_to_texture(
+                    dst.tex.as_image_copy(),
+                    graphics.desaturate.src_tex.as_image_copy(),
+                    dst.tex_extent,
+                );
+
+                {
+                    let mut render_pass = begin_render_pass(&mut encoder, &dst, false);
+
+                    render_pass.set_index_buffer(
+                        graphics.quad_index_buf.slice(..),
+                        wgpu::IndexFormat::Uint16,
+                    );
+                    render_pass.set_vertex_buffer(0, graphics.quad_vertex_buf.slice(..));
+                    render_pass.set_pipeline(&graphics.desaturate.pipeline);
+                    render_pass.set_bind_group(0, &graphics.desaturate.bind_group, &[]);
+                    render_pass.draw_indexed(
+                        0..(QUAD_INDEX_DATA.len() as u32),
+                        0,
+                        desaturate_index..(desaturate_index + 1),
+                    );
+                    desaturate_index += 1;
+                }
+
+                clear = false;
             }
 
             encoder.copy_texture_to_buffer(
@@ -682,13 +1140,56 @@ impl<'a> CommandBuffer<'a> {
             {
                 *fb_color = Color::from_bytes(mapped_color.assert_into());
             }
+
+            graphics.frame_rendered(&mapped_colored_rect_dst_buffer);
         }
 
         (
             self.colored_rect_count as i32,
             self.textured_rect_count as i32,
             self.mesh_count as i32,
+            self.culled_mesh_count as i32,
+            self.culled_triangle_count as i32,
             0,
         )
     }
 }
+
+fn begin_render_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    dst: &'a DstTexture,
+    clear: bool,
+) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &dst.tex_view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: if clear {
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.0,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 1.0,
+                    })
+                } else {
+                    wgpu::LoadOp::Load
+                },
+                store: true,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: &dst.depth_view,
+            depth_ops: Some(wgpu::Operations {
+                load: if clear {
+                    wgpu::LoadOp::Clear(1.0)
+                } else {
+                    wgpu::LoadOp::Load
+                },
+                store: true,
+            }),
+            stencil_ops: None,
+        }),
+    })
+}
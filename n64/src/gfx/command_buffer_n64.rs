@@ -1,6 +1,11 @@
 #![cfg_attr(not(target_vendor = "nintendo64"), allow(unused))]
 
-use super::{FillPipeline, Pipeline};
+use super::{
+    culling::{mesh_is_outside_screen, triangle_is_culled},
+    debug_draw::{self, DebugLine, DEBUG_DRAW_PIPELINE},
+    post_effect::{amount_to_alpha, fade_overlay_color, screen_corners, POST_EFFECT_PIPELINE},
+    CullMode, FillPipeline, Lights, Pipeline, PostEffect, Sprite, Texture, TextureRect,
+};
 use crate::{
     framebuffer::ViBufferToken, graphics_n64::Graphics, ipl3font, slow_cpu_clear, VideoMode,
 };
@@ -9,15 +14,18 @@ use n64_math::{vec2, Color, Mat4, Vec2, Vec3};
 use n64_sys::rsp;
 use rdp_command_builder::*;
 use rdp_math::{
-    color_to_i32, edge_slope, is_triangle_right_major, shaded_triangle_coeff,
-    slope_y_next_subpixel_intersection, slope_y_prev_scanline_intersection, sorted_triangle,
-    sorted_triangle_indices, triangle_is_too_small, truncate_to_pixel, z_triangle_coeff,
+    clip_polygon, color_to_i32, edge_slope, is_triangle_right_major, safe_cast_i32,
+    shaded_triangle_coeff, slope_y_next_subpixel_intersection, slope_y_prev_scanline_intersection,
+    sorted_triangle, sorted_triangle_indices, triangle_is_too_small, truncate_to_pixel,
+    z_triangle_coeff,
 };
 use rdp_state::RdpState;
+use rsp_lighting::RspLighting;
 
 mod rdp_command_builder;
 mod rdp_math;
 mod rdp_state;
+mod rsp_lighting;
 
 // Note: Primitive color, g*DPSetPrimColor( ), primitive depth, g*DPSetPrimDepth( ), and scissor, g*DPSetScissor( ), are attributes that do not require any syncs.
 
@@ -27,6 +35,10 @@ pub struct CommandBufferCache {
     depth_buffer: Box<[u16]>,
     vertex_cache: Box<[(Vec3, i32); 256]>,
     vertex_cache_generation: i32,
+    lighting: RspLighting,
+    lit_colors: Vec<u32>,
+    debug_lines: Vec<DebugLine>,
+    post_effects: Vec<PostEffect>,
 }
 
 impl CommandBufferCache {
@@ -41,6 +53,10 @@ impl CommandBufferCache {
             },
             vertex_cache: Box::new([(Vec3::ZERO, 0); 256]),
             vertex_cache_generation: 0,
+            lighting: RspLighting::new(),
+            lit_colors: Vec::new(),
+            debug_lines: Vec::new(),
+            post_effects: Vec::new(),
         }
     }
 
@@ -66,13 +82,22 @@ pub struct CommandBuffer<'a> {
     colored_rect_count: u32,
     textured_rect_count: u32,
     mesh_count: u32,
+    culled_mesh_count: u32,
+    culled_triangle_count: u32,
     current_state: RdpState,
+    cull_mode: CullMode,
+    texture: Option<Texture<'static>>,
+    pipeline_prim_color: u32,
+    lights: Lights,
+    screen_offset: Vec2,
     cache: &'a mut CommandBufferCache,
 }
 
 impl<'a> CommandBuffer<'a> {
     pub fn new(out_tex: ViBufferToken, cache: &'a mut CommandBufferCache) -> Self {
         cache.rdp.clear();
+        cache.debug_lines.clear();
+        cache.post_effects.clear();
 
         cache
             .rdp
@@ -97,7 +122,14 @@ impl<'a> CommandBuffer<'a> {
             colored_rect_count: 0,
             textured_rect_count: 0,
             mesh_count: 0,
+            culled_mesh_count: 0,
+            culled_triangle_count: 0,
             current_state: RdpState::default(),
+            cull_mode: CullMode::None,
+            texture: None,
+            pipeline_prim_color: 0,
+            lights: Lights::default(),
+            screen_offset: Vec2::ZERO,
             cache,
         }
     }
@@ -161,27 +193,225 @@ impl<'a> CommandBuffer<'a> {
 
     pub fn set_pipeline(&mut self, pipeline: &Pipeline) -> &mut Self {
         rdp_state::apply_pipeline(&mut self.cache.rdp, &mut self.current_state, pipeline);
+        self.cull_mode = pipeline.cull_mode;
+        self.texture = pipeline.texture;
+        self.pipeline_prim_color = self.current_state.prim_color;
+        self
+    }
+
+    pub fn set_lights(&mut self, lights: &Lights) -> &mut Self {
+        self.lights = *lights;
+        self
+    }
+
+    // Moves everything drawn after this call, used for screen shake
+    pub fn set_screen_offset(&mut self, offset: Vec2) -> &mut Self {
+        self.screen_offset = offset;
+        self
+    }
+
+    pub fn add_post_effect(&mut self, effect: PostEffect) -> &mut Self {
+        if effect.is_visible() {
+            self.cache.post_effects.push(effect);
+        }
         self
     }
 
     pub fn add_colored_rect(&mut self, upper_left: Vec2, lower_right: Vec2) -> &mut Self {
         self.colored_rect_count += 1;
-        self.cache
-            .rdp
-            .fill_rectangle(upper_left, lower_right - vec2(1.0, 1.0));
+        self.cache.rdp.fill_rectangle(
+            upper_left + self.screen_offset,
+            lower_right + self.screen_offset - vec2(1.0, 1.0),
+        );
 
         self
     }
 
-    pub fn add_textured_rect(&mut self, upper_left: Vec2, lower_right: Vec2) -> &mut Self {
+    pub fn add_textured_rect(
+        &mut self,
+        upper_left: Vec2,
+        lower_right: Vec2,
+        sub_rect: Option<TextureRect>,
+    ) -> &mut Self {
+        let Some(texture) = self.texture else {
+            return self;
+        };
+        let rect = sub_rect.unwrap_or_else(|| TextureRect::full(&texture));
+
+        if lower_right.x <= upper_left.x || lower_right.y <= upper_left.y {
+            return self;
+        }
+
+        let page = rdp_state::tmem_page(&texture, rect);
+
+        if !rdp_state::apply_texture(&mut self.cache.rdp, &mut self.current_state, &texture, page) {
+            return self;
+        }
+
         self.textured_rect_count += 1;
 
+        let st_start = vec2(rect.x as f32, rect.y as f32);
+        let d_st = vec2(rect.width as f32, rect.height as f32) / (lower_right - upper_left);
+
         self.cache.rdp.texture_rectangle(
-            upper_left,
-            lower_right,
+            upper_left + self.screen_offset,
+            lower_right + self.screen_offset,
             0,
-            vec2(0.0, 0.0),
-            vec2(32.0, 32.0),
+            st_start,
+            32.0 * d_st,
+        );
+        self
+    }
+
+    pub fn add_sprite(&mut self, sprite: &Sprite) -> &mut Self {
+        self.push_sprite(sprite);
+        self.restore_pipeline_prim_color();
+        self
+    }
+
+    pub fn add_sprite_batch(&mut self, sprites: &[Sprite]) -> &mut Self {
+        for sprite in sprites {
+            self.push_sprite(sprite);
+        }
+        self.restore_pipeline_prim_color();
+        self
+    }
+
+    // Tints leave the primitive color changed, later draws expect the one from the pipeline
+    fn restore_pipeline_prim_color(&mut self) {
+        rdp_state::apply_prim_color(
+            &mut self.cache.rdp,
+            &mut self.current_state,
+            self.pipeline_prim_color,
+        );
+    }
+
+    fn push_sprite(&mut self, sprite: &Sprite) {
+        let Some(texture) = self.texture else {
+            return;
+        };
+        let texture_size = vec2(texture.width as f32, texture.height as f32);
+
+        let (upper_left, lower_right) = sprite.screen_rect();
+
+        if lower_right.x <= upper_left.x || lower_right.y <= upper_left.y {
+            return;
+        }
+
+        {
+            let uv_min = sprite.uv_upper_left.min(sprite.uv_lower_right) * texture_size;
+            let uv_max = sprite.uv_upper_left.max(sprite.uv_lower_right) * texture_size;
+
+            let x = libm::floorf(uv_min.x) as i32;
+            let y = libm::floorf(uv_min.y) as i32;
+
+            let rect = TextureRect::new(
+                x,
+                y,
+                libm::ceilf(uv_max.x) as i32 - x,
+                libm::ceilf(uv_max.y) as i32 - y,
+            );
+
+            let page = rdp_state::tmem_page(&texture, rect);
+
+            if !rdp_state::apply_texture(
+                &mut self.cache.rdp,
+                &mut self.current_state,
+                &texture,
+                page,
+            ) {
+                return;
+            }
+        }
+
+        self.textured_rect_count += 1;
+
+        rdp_state::apply_prim_color(
+            &mut self.cache.rdp,
+            &mut self.current_state,
+            sprite.tint.unwrap_or(self.pipeline_prim_color),
+        );
+
+        if sprite.is_axis_aligned() {
+            let (uv_start, uv_end) = sprite.uv_rect();
+
+            let st_start = uv_start * texture_size;
+            let d_st = (uv_end * texture_size - st_start) / (lower_right - upper_left);
+
+            // Flipped sprites start on the last texel and step backwards
+            let st_start = st_start + d_st.min(Vec2::ZERO);
+
+            self.cache.rdp.texture_rectangle(
+                upper_left + self.screen_offset,
+                lower_right + self.screen_offset,
+                0,
+                st_start,
+                32.0 * d_st,
+            );
+        } else {
+            let color = sprite.tint.unwrap_or(0xffffffff);
+            let corners = sprite
+                .corners()
+                .map(|(pos, uv)| (pos + self.screen_offset, uv * texture_size));
+
+            // Clip before push_triangle clamps the vertices, that would distort the texture
+            let screen_size = vec2(
+                self.cache.video_mode.width() as f32,
+                self.cache.video_mode.height() as f32,
+            );
+            let (verts, len) = clip_polygon(&corners, Vec2::ZERO, screen_size);
+
+            let (p0, uv0) = verts[0];
+            for i in 2..len {
+                let (p1, uv1) = verts[i - 1];
+                let (p2, uv2) = verts[i];
+
+                self.push_triangle(
+                    [p0.extend(0.0), p1.extend(0.0), p2.extend(0.0)],
+                    [color; 3],
+                    Some([uv0, uv1, uv2]),
+                    false,
+                );
+            }
+        }
+    }
+
+    // Debug primitives are moved by the screen offset like everything else
+    pub fn add_debug_line(&mut self, start: Vec2, end: Vec2, color: u32) -> &mut Self {
+        let offset = self.screen_offset;
+        debug_draw::push_line(
+            &mut self.cache.debug_lines,
+            start + offset,
+            end + offset,
+            color,
+        );
+        self
+    }
+
+    pub fn add_debug_rect(&mut self, upper_left: Vec2, lower_right: Vec2, color: u32) -> &mut Self {
+        let offset = self.screen_offset;
+        debug_draw::push_rect(
+            &mut self.cache.debug_lines,
+            upper_left + offset,
+            lower_right + offset,
+            color,
+        );
+        self
+    }
+
+    pub fn add_debug_circle(&mut self, center: Vec2, radius: f32, color: u32) -> &mut Self {
+        let offset = self.screen_offset;
+        debug_draw::push_circle(&mut self.cache.debug_lines, center + offset, radius, color);
+        self
+    }
+
+    pub fn add_debug_arrow(&mut self, start: Vec2, end: Vec2, color: u32) -> &mut Self {
+        let offset = self.screen_offset;
+        debug_draw::push_arrow(
+            &mut self.cache.debug_lines,
+            start + offset,
+            end + offset,
+            color,
         );
         self
     }
@@ -193,130 +423,316 @@ impl<'a> CommandBuffer<'a> {
         colors: &[u32],
         indices: &[[u8; 3]],
         transform: &[[f32; 4]; 4],
+        size: Option<Vec2>,
+    ) -> &mut Self {
+        if self.mesh_is_culled(transform, size) {
+            return self;
+        }
+
+        self.mesh_count += 1;
+        self.add_triangles(verts, colors, indices, transform);
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_lit_mesh_indexed(
+        &mut self,
+        verts: &[[f32; 3]],
+        _uvs: &[[f32; 2]],
+        colors: &[u32],
+        normals: &[[f32; 3]],
+        indices: &[[u8; 3]],
+        transform: &[[f32; 4]; 4],
+        normal_transform: &[[f32; 4]; 4],
+        size: Option<Vec2>,
     ) -> &mut Self {
+        if self.mesh_is_culled(transform, size) {
+            return self;
+        }
+
         self.mesh_count += 1;
 
+        let mut lit_colors = core::mem::take(&mut self.cache.lit_colors);
+        self.cache.lighting.light_colors(
+            &self.lights,
+            &Mat4::from_cols_array_2d(normal_transform),
+            colors,
+            normals,
+            &mut lit_colors,
+        );
+
+        self.add_triangles(verts, &lit_colors, indices, transform);
+
+        self.cache.lit_colors = lit_colors;
+
+        self
+    }
+
+    fn mesh_is_culled(&mut self, transform: &[[f32; 4]; 4], size: Option<Vec2>) -> bool {
+        if let Some(size) = size {
+            if mesh_is_outside_screen(
+                &Mat4::from_cols_array_2d(transform),
+                size,
+                self.cache.video_mode,
+            ) {
+                self.culled_mesh_count += 1;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn add_triangles(
+        &mut self,
+        verts: &[[f32; 3]],
+        colors: &[u32],
+        indices: &[[u8; 3]],
+        transform: &[[f32; 4]; 4],
+    ) {
         let transform = Mat4::from_cols_array_2d(transform);
+        let offset = self.screen_offset.extend(0.0);
+        let project = |index: u8| {
+            truncate_to_pixel(transform.project_point3(Vec3::from(verts[index as usize])) + offset)
+        };
 
         self.cache.vertex_cache_generation = self.cache.vertex_cache_generation.wrapping_add(1);
 
         for triangle in indices {
-            let mut v0 = self.cache.get(triangle[0], || {
-                truncate_to_pixel(transform.project_point3(Vec3::from(verts[triangle[0] as usize])))
-            });
-            let mut v1 = self.cache.get(triangle[1], || {
-                truncate_to_pixel(transform.project_point3(Vec3::from(verts[triangle[1] as usize])))
-            });
-            let mut v2 = self.cache.get(triangle[2], || {
-                truncate_to_pixel(transform.project_point3(Vec3::from(verts[triangle[2] as usize])))
-            });
-
-            let x_limit = self.cache.video_mode.width() as f32;
-            let y_limit = self.cache.video_mode.height() as f32;
-
-            v0.x = libm::fmaxf(libm::fminf(v0.x, x_limit), 0.0);
-            v1.x = libm::fmaxf(libm::fminf(v1.x, x_limit), 0.0);
-            v2.x = libm::fmaxf(libm::fminf(v2.x, x_limit), 0.0);
-            v0.y = libm::fmaxf(libm::fminf(v0.y, y_limit), 0.0);
-            v1.y = libm::fmaxf(libm::fminf(v1.y, y_limit), 0.0);
-            v2.y = libm::fmaxf(libm::fminf(v2.y, y_limit), 0.0);
-
-            if triangle_is_too_small(v0, v1, v2) {
+            let v0 = self.cache.get(triangle[0], || project(triangle[0]));
+            let v1 = self.cache.get(triangle[1], || project(triangle[1]));
+            let v2 = self.cache.get(triangle[2], || project(triangle[2]));
+
+            if triangle_is_culled(self.cull_mode, v0, v1, v2) {
+                self.culled_triangle_count += 1;
                 continue;
             }
-            // Vh is the highest point (smallest y value)
-            // Vl is the lowest point (largest y value)
-            let (vh, vm, vl) = sorted_triangle(v0, v1, v2);
 
-            // TODO SEND TRIANGLE
+            self.push_triangle(
+                [v0, v1, v2],
+                [
+                    colors[triangle[0] as usize],
+                    colors[triangle[1] as usize],
+                    colors[triangle[2] as usize],
+                ],
+                None,
+                true,
+            );
+        }
+    }
 
-            let (l_int, l_frac) = slope_y_next_subpixel_intersection(vm, vl);
-            let (m_int, m_frac) = slope_y_prev_scanline_intersection(vh, vm);
-            let (h_int, h_frac) = slope_y_prev_scanline_intersection(vh, vl);
+    // Texture coordinates are in texels
+    fn push_triangle(
+        &mut self,
+        verts: [Vec3; 3],
+        colors: [u32; 3],
+        tex_coords: Option<[Vec2; 3]>,
+        is_z_buffered: bool,
+    ) {
+        let [mut v0, mut v1, mut v2] = verts;
+
+        let x_limit = self.cache.video_mode.width() as f32;
+        let y_limit = self.cache.video_mode.height() as f32;
+
+        v0.x = libm::fmaxf(libm::fminf(v0.x, x_limit), 0.0);
+        v1.x = libm::fmaxf(libm::fminf(v1.x, x_limit), 0.0);
+        v2.x = libm::fmaxf(libm::fminf(v2.x, x_limit), 0.0);
+        v0.y = libm::fmaxf(libm::fminf(v0.y, y_limit), 0.0);
+        v1.y = libm::fmaxf(libm::fminf(v1.y, y_limit), 0.0);
+        v2.y = libm::fmaxf(libm::fminf(v2.y, y_limit), 0.0);
+
+        if triangle_is_too_small(v0, v1, v2) {
+            return;
+        }
+        // Vh is the highest point (smallest y value)
+        // Vl is the lowest point (largest y value)
+        let (vh, vm, vl) = sorted_triangle(v0, v1, v2);
 
-            let l_slope = edge_slope(vl, vm);
-            let m_slope = edge_slope(vm, vh);
-            let h_slope = edge_slope(vl, vh);
+        // TODO SEND TRIANGLE
 
-            let right_major = is_triangle_right_major(vh, vm, vl);
+        let (l_int, l_frac) = slope_y_next_subpixel_intersection(vm, vl);
+        let (m_int, m_frac) = slope_y_prev_scanline_intersection(vh, vm);
+        let (h_int, h_frac) = slope_y_prev_scanline_intersection(vh, vl);
 
-            let is_shaded = true;
-            let is_texured = false;
-            let is_z_buffered = true;
+        let l_slope = edge_slope(vl, vm);
+        let m_slope = edge_slope(vm, vh);
+        let h_slope = edge_slope(vl, vh);
 
-            self.cache.rdp.edge_coefficients(
-                is_shaded,
-                is_texured,
-                is_z_buffered,
-                right_major,
-                0,
-                0,
-                vl.y,
-                vm.y,
-                vh.y,
-                l_int,
-                l_frac,
-                m_int,
-                m_frac,
-                h_int,
-                h_frac,
-                l_slope,
-                m_slope,
-                h_slope,
+        let right_major = is_triangle_right_major(vh, vm, vl);
+
+        let is_shaded = true;
+        let is_texured = tex_coords.is_some();
+
+        self.cache.rdp.edge_coefficients(
+            is_shaded,
+            is_texured,
+            is_z_buffered,
+            right_major,
+            0,
+            0,
+            vl.y,
+            vm.y,
+            vh.y,
+            l_int,
+            l_frac,
+            m_int,
+            m_frac,
+            h_int,
+            h_frac,
+            l_slope,
+            m_slope,
+            h_slope,
+        );
+
+        let (vhi, vmi, vli) = sorted_triangle_indices(v0, v1, v2);
+
+        if is_shaded {
+            let color_h = color_to_i32(colors[vhi as usize]);
+            let color_m = color_to_i32(colors[vmi as usize]);
+            let color_l = color_to_i32(colors[vli as usize]);
+
+            let (r_dx, r_dy, r_de, _r_off) = shaded_triangle_coeff(
+                vh,
+                vm,
+                vl,
+                color_h[0] as f32,
+                color_m[0] as f32,
+                color_l[0] as f32,
+            );
+            let (g_dx, g_dy, g_de, _g_off) = shaded_triangle_coeff(
+                vh,
+                vm,
+                vl,
+                color_h[1] as f32,
+                color_m[1] as f32,
+                color_l[1] as f32,
+            );
+            let (b_dx, b_dy, b_de, _b_off) = shaded_triangle_coeff(
+                vh,
+                vm,
+                vl,
+                color_h[2] as f32,
+                color_m[2] as f32,
+                color_l[2] as f32,
             );
+            let red = color_h[0] << 16; // r_off;
+            let green = color_h[1] << 16; // g_off;
+            let blue = color_h[2] << 16; // b_off;
+
+            self.cache.rdp.shade_coefficients(
+                red, green, blue, 0, // Color
+                r_dx, g_dx, b_dx, 0, // Delta color X
+                r_de, g_de, b_de, 0, // Delta color Edge
+                r_dy, g_dy, b_dy, 0, // Delta color y
+            );
+        }
 
-            if is_shaded {
-                let (vhi, vmi, vli) = sorted_triangle_indices(v0, v1, v2);
+        if let Some(tex_coords) = tex_coords {
+            // S and T are s10.5 texel coordinates
+            let st_h = 32.0 * tex_coords[vhi as usize];
+            let st_m = 32.0 * tex_coords[vmi as usize];
+            let st_l = 32.0 * tex_coords[vli as usize];
+
+            let (s_dx, s_dy, s_de, _s_off) =
+                shaded_triangle_coeff(vh, vm, vl, st_h.x, st_m.x, st_l.x);
+            let (t_dx, t_dy, t_de, _t_off) =
+                shaded_triangle_coeff(vh, vm, vl, st_h.y, st_m.y, st_l.y);
+
+            let s = safe_cast_i32(st_h.x * 65536.0);
+            let t = safe_cast_i32(st_h.y * 65536.0);
+
+            self.cache.rdp.texture_coefficients(
+                s, t, 0, // Texture
+                s_dx, t_dx, 0, // Delta texture X
+                s_de, t_de, 0, // Delta texture Edge
+                s_dy, t_dy, 0, // Delta texture y
+            );
+        }
 
-                let color_h = color_to_i32(colors[triangle[vhi as usize] as usize]);
-                let color_m = color_to_i32(colors[triangle[vmi as usize] as usize]);
-                let color_l = color_to_i32(colors[triangle[vli as usize] as usize]);
+        if is_z_buffered {
+            let (z, dx, de, dy) = z_triangle_coeff(vh, vm, vl);
+            self.cache.rdp.z_buffer_coefficients(z, dx, de, dy);
+        }
+    }
 
-                let (r_dx, r_dy, r_de, _r_off) = shaded_triangle_coeff(
-                    vh,
-                    vm,
-                    vl,
-                    color_h[0] as f32,
-                    color_m[0] as f32,
-                    color_l[0] as f32,
-                );
-                let (g_dx, g_dy, g_de, _g_off) = shaded_triangle_coeff(
-                    vh,
-                    vm,
-                    vl,
-                    color_h[1] as f32,
-                    color_m[1] as f32,
-                    color_l[1] as f32,
-                );
-                let (b_dx, b_dy, b_de, _b_off) = shaded_triangle_coeff(
-                    vh,
-                    vm,
-                    vl,
-                    color_h[2] as f32,
-                    color_m[2] as f32,
-                    color_l[2] as f32,
-                );
-                let red = color_h[0] << 16; // r_off;
-                let green = color_h[1] << 16; // g_off;
-                let blue = color_h[2] << 16; // b_off;
-
-                self.cache.rdp.shade_coefficients(
-                    red, green, blue, 0, // Color
-                    r_dx, g_dx, b_dx, 0, // Delta color X
-                    r_de, g_de, b_de, 0, // Delta color Edge
-                    r_dy, g_dy, b_dy, 0, // Delta color y
-                );
-            }
+    fn draw_post_effects(&mut self) {
+        let screen_size = vec2(
+            self.cache.video_mode.width() as f32,
+            self.cache.video_mode.height() as f32,
+        );
+        let [c0, c1, c2, c3] = screen_corners(screen_size).map(|c| c.extend(0.0));
+
+        let effects = core::mem::take(&mut self.cache.post_effects);
 
-            if is_z_buffered {
-                let (z, dx, de, dy) = z_triangle_coeff(vh, vm, vl);
-                self.cache.rdp.z_buffer_coefficients(z, dx, de, dy);
+        for effect in effects.iter() {
+            match *effect {
+                PostEffect::Fade { color, amount } => {
+                    self.set_pipeline(
+                        &POST_EFFECT_PIPELINE
+                            .with_prim_color(Some(fade_overlay_color(color, amount))),
+                    );
+
+                    self.push_triangle([c0, c1, c2], [0; 3], None, false);
+                    self.push_triangle([c0, c2, c3], [0; 3], None, false);
+                }
+                PostEffect::Desaturate { amount } => self.desaturate(amount),
             }
         }
-        self
+
+        self.cache.post_effects = effects;
+    }
+
+    fn desaturate(&mut self, amount: f32) {
+        let width = self.cache.video_mode.width();
+        let height = self.cache.video_mode.height();
+        let rows = rdp_state::desaturate_rows(width);
+
+        rdp_state::apply_desaturate(
+            &mut self.cache.rdp,
+            &mut self.current_state,
+            self.out_tex.0 as *const u16,
+            width,
+            amount_to_alpha(amount),
+        );
+
+        for y in (0..height).step_by(rows as usize) {
+            let rows = rows.min(height - y);
+
+            rdp_state::load_framebuffer_rows(&mut self.cache.rdp, width, y, rows);
+
+            self.cache.rdp.texture_rectangle(
+                vec2(0.0, y as f32),
+                vec2(width as f32, (y + rows) as f32),
+                0,
+                vec2(1.0, 0.0),
+                vec2(64.0, 32.0),
+            );
+        }
     }
 
-    pub fn submit(self, graphics: &mut Graphics, step: bool) -> (i32, i32, i32, i32) {
+    fn draw_debug_lines(&mut self) {
+        if self.cache.debug_lines.is_empty() {
+            return;
+        }
+
+        self.set_pipeline(&DEBUG_DRAW_PIPELINE);
+
+        let lines = core::mem::take(&mut self.cache.debug_lines);
+
+        for line in lines.iter() {
+            let [c0, c1, c2, c3] = line.corners().map(|c| c.extend(0.0));
+
+            self.push_triangle([c0, c1, c2], [line.color; 3], None, false);
+            self.push_triangle([c0, c2, c3], [line.color; 3], None, false);
+        }
+
+        self.cache.debug_lines = lines;
+    }
+
+    pub fn submit(mut self, graphics: &mut Graphics, step: bool) -> (i32, i32, i32, i32, i32, i32) {
+        self.draw_post_effects();
+        self.draw_debug_lines();
+
         self.cache.rdp.sync_full();
 
         let use_single_step = false;
@@ -398,6 +814,8 @@ impl<'a> CommandBuffer<'a> {
             self.colored_rect_count as i32,
             self.textured_rect_count as i32,
             self.mesh_count as i32,
+            self.culled_mesh_count as i32,
+            self.culled_triangle_count as i32,
             graphics.rdp_clock_count() as i32,
         )
     }
@@ -110,11 +110,14 @@ pub const COMMAND_SET_FILL_COLOR: u64 = 0xf7;
 pub const COMMAND_FILL_RECTANGLE: u64 = 0xf6;
 pub const COMMAND_SET_TILE: u64 = 0xf5;
 pub const COMMAND_LOAD_TILE: u64 = 0xf4;
+pub const COMMAND_SET_TILE_SIZE: u64 = 0xf2;
+pub const COMMAND_LOAD_TLUT: u64 = 0xf0;
 pub const COMMAND_SET_OTHER_MODE: u64 = 0xef;
 pub const COMMAND_SET_SCISSOR: u64 = 0xed;
 pub const COMMAND_SYNC_FULL: u64 = 0xe9;
 pub const COMMAND_SYNC_TILE: u64 = 0xe8;
 pub const COMMAND_SYNC_PIPE: u64 = 0xe7;
+pub const COMMAND_SYNC_LOAD: u64 = 0xe6;
 pub const COMMAND_TEXTURE_RECTANGLE: u64 = 0xe4;
 pub const COMMAND_EDGE_COEFFICIENTS: u64 = 0xc8;
 
@@ -332,6 +335,35 @@ impl RdpCommandBuilder {
         self
     }
 
+    #[inline]
+    pub fn set_tile_size(
+        &mut self,
+        top_left: Vec2,
+        bottom_right: Vec2,
+        tile_index: u8,
+    ) -> &mut RdpCommandBuilder {
+        self.push(RdpCommand(
+            (COMMAND_SET_TILE_SIZE << 56)
+                | (to_fixpoint_10_2_as_integer(top_left.x) << (32 + 12))
+                | (to_fixpoint_10_2_as_integer(top_left.y) << 32)
+                | ((tile_index as u64) << 24)
+                | (to_fixpoint_10_2_as_integer(bottom_right.x) << 12)
+                | (to_fixpoint_10_2_as_integer(bottom_right.y)),
+        ));
+        self
+    }
+
+    // Loads count 16 bit colors from the texture image to the tmem address of the tile
+    #[inline]
+    pub fn load_tlut(&mut self, count: u16, tile_index: u8) -> &mut RdpCommandBuilder {
+        self.push(RdpCommand(
+            (COMMAND_LOAD_TLUT << 56)
+                | ((tile_index as u64) << 24)
+                | (to_fixpoint_10_2_as_integer((count - 1) as f32) << 12),
+        ));
+        self
+    }
+
     #[inline]
     pub fn set_other_modes(&mut self, flags: u64) -> &mut RdpCommandBuilder {
         self.push(RdpCommand(
@@ -365,6 +397,12 @@ impl RdpCommandBuilder {
         self
     }
 
+    #[inline]
+    pub fn sync_load(&mut self) -> &mut RdpCommandBuilder {
+        self.push(RdpCommand(COMMAND_SYNC_LOAD << 56));
+        self
+    }
+
     #[inline]
     pub fn sync_pipe(&mut self) -> &mut RdpCommandBuilder {
         self.push(RdpCommand(COMMAND_SYNC_PIPE << 56));
@@ -513,6 +551,75 @@ impl RdpCommandBuilder {
         self
     }
 
+    #[inline]
+    pub fn texture_coefficients(
+        &mut self,
+        s: i32,
+        t: i32,
+        w: i32,
+        ds_dx: i32,
+        dt_dx: i32,
+        dw_dx: i32,
+        ds_de: i32,
+        dt_de: i32,
+        dw_de: i32,
+        ds_dy: i32,
+        dt_dy: i32,
+        dw_dy: i32,
+    ) -> &mut RdpCommandBuilder {
+        // Texture
+        // Delta Texture X
+        // Texture fraction
+        // Delta Texture X fraction
+        self.push(RdpCommand(
+            ((s >> 16) as u16 as u64) << 48
+                | ((t >> 16) as u16 as u64) << 32
+                | ((w >> 16) as u16 as u64) << 16,
+        ));
+        self.push(RdpCommand(
+            ((ds_dx >> 16) as u16 as u64) << 48
+                | ((dt_dx >> 16) as u16 as u64) << 32
+                | ((dw_dx >> 16) as u16 as u64) << 16,
+        ));
+        self.push(RdpCommand(
+            ((s & 0x0000ffff) as u16 as u64) << 48
+                | ((t & 0x0000ffff) as u16 as u64) << 32
+                | ((w & 0x0000ffff) as u16 as u64) << 16,
+        ));
+        self.push(RdpCommand(
+            ((ds_dx & 0x0000ffff) as u16 as u64) << 48
+                | ((dt_dx & 0x0000ffff) as u16 as u64) << 32
+                | ((dw_dx & 0x0000ffff) as u16 as u64) << 16,
+        ));
+
+        // Delta Texture Edge
+        // Delta Texture Y
+        // Delta Texture Edge fraction
+        // Delta Texture Y fraction
+        self.push(RdpCommand(
+            ((ds_de >> 16) as u16 as u64) << 48
+                | ((dt_de >> 16) as u16 as u64) << 32
+                | ((dw_de >> 16) as u16 as u64) << 16,
+        ));
+        self.push(RdpCommand(
+            ((ds_dy >> 16) as u16 as u64) << 48
+                | ((dt_dy >> 16) as u16 as u64) << 32
+                | ((dw_dy >> 16) as u16 as u64) << 16,
+        ));
+        self.push(RdpCommand(
+            ((ds_de & 0x0000ffff) as u16 as u64) << 48
+                | ((dt_de & 0x0000ffff) as u16 as u64) << 32
+                | ((dw_de & 0x0000ffff) as u16 as u64) << 16,
+        ));
+        self.push(RdpCommand(
+            ((ds_dy & 0x0000ffff) as u16 as u64) << 48
+                | ((dt_dy & 0x0000ffff) as u16 as u64) << 32
+                | ((dw_dy & 0x0000ffff) as u16 as u64) << 16,
+        ));
+
+        self
+    }
+
     #[inline]
     pub fn z_buffer_coefficients(
         &mut self,
@@ -551,13 +658,14 @@ impl RdpCommandBuilder {
         let mut st_l = st_top_left.x;
         let mut st_t = st_top_left.y;
 
+        // d_xy_d_st is in s5.10 passed as s10.5, 32.0 is one texel per pixel
         if l < 0.0 {
-            st_l -= l;
+            st_l -= l * d_xy_d_st.x / 32.0;
             l = 0.0;
         }
 
         if t < 0.0 {
-            st_t -= t;
+            st_t -= t * d_xy_d_st.y / 32.0;
             t = 0.0;
         }
 
@@ -1,4 +1,4 @@
-use n64_math::{vec3, Vec3};
+use n64_math::{vec3, Vec2, Vec3};
 
 pub fn to_fixpoint_10_2_as_integer(val: f32) -> u64 {
     (((val as i16) * (1 << 2)) & 0xffc) as u64
@@ -18,7 +18,7 @@ pub fn to_fixpoint_s_11_2(val: f32) -> u64 {
 }
 
 pub fn to_fixpoint_s_10_5(val: f32) -> u64 {
-    ((val * (1 << 5) as f32) as i16) as u64
+    ((val * (1 << 5) as f32) as i16) as u16 as u64
 }
 
 pub fn fixed_16_16_to_f32(fixed_point: i32) -> f32 {
@@ -245,3 +245,91 @@ pub fn safe_cast_i32(val: f32) -> i32 {
         val as i32
     }
 }
+
+// A convex quad gains at most one vertex per clipped edge
+pub const MAX_CLIPPED_VERTS: usize = 8;
+
+// Clips a convex polygon of (position, texture coordinate) pairs against a rectangle,
+// texture coordinates are interpolated along the clipped edges
+pub fn clip_polygon(
+    polygon: &[(Vec2, Vec2)],
+    min: Vec2,
+    max: Vec2,
+) -> ([(Vec2, Vec2); MAX_CLIPPED_VERTS], usize) {
+    let mut verts = [(Vec2::ZERO, Vec2::ZERO); MAX_CLIPPED_VERTS];
+    let mut len = polygon.len().min(MAX_CLIPPED_VERTS);
+    verts[..len].copy_from_slice(&polygon[..len]);
+
+    let edges: [&dyn Fn(Vec2) -> f32; 4] =
+        [&|p| p.x - min.x, &|p| max.x - p.x, &|p| p.y - min.y, &|p| {
+            max.y - p.y
+        }];
+
+    for distance in edges {
+        let input = verts;
+        let input_len = len;
+        len = 0;
+
+        for i in 0..input_len {
+            let current = input[i];
+            let next = input[(i + 1) % input_len];
+
+            let current_distance = distance(current.0);
+            let next_distance = distance(next.0);
+
+            if current_distance >= 0.0 && len < MAX_CLIPPED_VERTS {
+                verts[len] = current;
+                len += 1;
+            }
+
+            if current_distance * next_distance < 0.0 && len < MAX_CLIPPED_VERTS {
+                let t = current_distance / (current_distance - next_distance);
+                verts[len] = (current.0.lerp(next.0, t), current.1.lerp(next.1, t));
+                len += 1;
+            }
+        }
+    }
+
+    (verts, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use n64_math::vec2;
+
+    #[test]
+    fn clipping_interpolates_texture_coordinates() {
+        let quad = [
+            (vec2(-10.0, 0.0), vec2(0.0, 0.0)),
+            (vec2(10.0, 0.0), vec2(20.0, 0.0)),
+            (vec2(10.0, 10.0), vec2(20.0, 10.0)),
+            (vec2(-10.0, 10.0), vec2(0.0, 10.0)),
+        ];
+
+        let (verts, len) = clip_polygon(&quad, Vec2::ZERO, vec2(320.0, 240.0));
+
+        assert_eq!(len, 4);
+        for (pos, uv) in &verts[..len] {
+            assert!(pos.x >= 0.0);
+            assert_eq!(uv.x, pos.x + 10.0);
+            assert_eq!(uv.y, pos.y);
+        }
+    }
+
+    #[test]
+    fn clipping_a_corner_adds_a_vertex() {
+        let triangle = [
+            (vec2(-5.0, -5.0), Vec2::ZERO),
+            (vec2(10.0, 2.0), Vec2::ZERO),
+            (vec2(2.0, 10.0), Vec2::ZERO),
+        ];
+
+        let (_, len) = clip_polygon(&triangle, Vec2::ZERO, vec2(320.0, 240.0));
+        assert_eq!(len, 5);
+
+        let outside = [(vec2(-10.0, -10.0), Vec2::ZERO); 3];
+        let (_, len) = clip_polygon(&outside, Vec2::ZERO, vec2(320.0, 240.0));
+        assert_eq!(len, 0);
+    }
+}
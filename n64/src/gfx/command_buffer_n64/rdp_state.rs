@@ -1,6 +1,13 @@
 use super::rdp_command_builder::*;
-use crate::gfx::{CycleType, FillPipeline, Pipeline, ZMode, ZSrc};
+use crate::gfx::{
+    blend_mode::{self, BlendMode, PMCycleOne, PMCycleTwo},
+    color_combiner_mode::{
+        AAlphaSrc, ASrc, BAlphaSrc, BSrc, CAlphaSrc, CSrc, ColorCombinerMode, DAlphaSrc, DSrc,
+    },
+    CycleType, FillPipeline, Pipeline, Texture, TextureRect, ZMode, ZSrc,
+};
 use n64_math::{vec2, Color};
+use n64_types::{tmem_line_width, tmem_page_rows, TMEM_SIZE};
 
 #[derive(Copy, Clone, Default)]
 pub struct RdpState {
@@ -11,6 +18,30 @@ pub struct RdpState {
     env_color: u32,
     blend_color: u32,
     texture: usize,
+    texture_rect: TextureRect,
+}
+
+pub fn texture_rect_fits_in_tmem(rect: &TextureRect) -> bool {
+    2 * tmem_line_width(rect.width) * rect.height <= TMEM_SIZE
+}
+
+// The tmem page of the texture that rect is on. Textures that fit in tmem are a single page and
+// rects that cross pages are loaded on their own
+pub fn tmem_page(texture: &Texture, rect: TextureRect) -> TextureRect {
+    let rows = tmem_page_rows(texture.width);
+
+    if rows == 0 {
+        return rect;
+    }
+
+    let top = rect.y / rows * rows;
+    let page = TextureRect::new(0, top, texture.width, rows.min(texture.height - top));
+
+    if page.contains(&rect) {
+        page
+    } else {
+        rect
+    }
 }
 
 fn apply_sync_if_first_change(rdp: &mut RdpCommandBuilder, emitted_sync: &mut bool) {
@@ -62,6 +93,14 @@ pub fn apply_fill_pipeline(
     }
 }
 
+// Primitive color does not require a sync
+pub fn apply_prim_color(rdp: &mut RdpCommandBuilder, state: &mut RdpState, prim_color: u32) {
+    if prim_color != state.prim_color {
+        rdp.set_prim_color(prim_color);
+        state.prim_color = prim_color;
+    }
+}
+
 pub fn apply_pipeline(rdp: &mut RdpCommandBuilder, state: &mut RdpState, pipeline: &Pipeline) {
     let mut emitted_sync = false;
 
@@ -147,35 +186,255 @@ pub fn apply_pipeline(rdp: &mut RdpCommandBuilder, state: &mut RdpState, pipelin
     }
 
     if let Some(texture) = pipeline.texture {
-        if state.texture != texture.data.as_ptr() as usize {
-            rdp.sync_tile()
-                .set_texture_image(
-                    FORMAT_RGBA,
-                    SIZE_OF_PIXEL_16B,
-                    texture.width as u16,
-                    texture.data.as_ptr() as *const u16,
-                )
-                .set_tile(
-                    FORMAT_RGBA,
-                    SIZE_OF_PIXEL_16B,
-                    texture.width as u16,
-                    0,
-                    0,
-                    0,
-                    0,
-                    0,
-                    0,
-                    0,
-                    0,
-                    0,
-                    0,
-                )
-                .load_tile(
-                    vec2((texture.width) as f32, (texture.height) as f32),
-                    vec2(0.0, 0.0),
-                    0,
-                );
-            state.texture = texture.data.as_ptr() as usize;
+        let rect = TextureRect::full(&texture);
+
+        // Atlases larger than tmem are loaded one sub rect at a time when drawn
+        if texture_rect_fits_in_tmem(&rect) {
+            apply_texture(rdp, state, &texture, rect);
+        }
+    }
+}
+
+// Loads rect of texture into tmem unless it is already loaded. Texture coordinates
+// stay relative to the whole texture since load_tile sets the tile origin to the rect.
+// Returns false when the rect does not fit in tmem
+pub fn apply_texture(
+    rdp: &mut RdpCommandBuilder,
+    state: &mut RdpState,
+    texture: &Texture,
+    rect: TextureRect,
+) -> bool {
+    if state.texture == texture.data.as_ptr() as usize && state.texture_rect.contains(&rect) {
+        return true;
+    }
+
+    if !texture_rect_fits_in_tmem(&rect) {
+        return false;
+    }
+
+    rdp.sync_tile()
+        .set_texture_image(
+            FORMAT_RGBA,
+            SIZE_OF_PIXEL_16B,
+            texture.width as u16,
+            texture.data.as_ptr() as *const u16,
+        )
+        .set_tile(
+            FORMAT_RGBA,
+            SIZE_OF_PIXEL_16B,
+            tmem_line_width(rect.width) as u16,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        )
+        .load_tile(
+            vec2((rect.x + rect.width) as f32, (rect.y + rect.height) as f32),
+            vec2(rect.x as f32, rect.y as f32),
+            0,
+        );
+
+    state.texture = texture.data.as_ptr() as usize;
+    state.texture_rect = rect;
+
+    true
+}
+
+// Rows of the frame buffer that fit in the lower half of tmem, the upper half holds the tlut
+pub fn desaturate_rows(width: i32) -> i32 {
+    TMEM_SIZE / 2 / (2 * width)
+}
+
+// The luminance of a rgba 5551 pixel split over its two bytes. Read as 8 bit color indices the
+// high byte looks up intensity, red and the top of green, and the low byte looks up alpha, the
+// rest of green and blue
+#[repr(C, align(8))]
+struct LuminanceTlut([u16; 256]);
+
+static LUMINANCE_TLUT: LuminanceTlut = LuminanceTlut(luminance_tlut());
+
+const fn luminance_tlut() -> [u16; 256] {
+    let mut tlut = [0; 256];
+    let mut byte = 0u32;
+
+    while byte < 256 {
+        // Rec. 601 weights in thousandths of 5 bit channels, rounded to 8 bits
+        let high = 299 * (byte >> 3) + 587 * ((byte & 0x7) << 2);
+        let low = 587 * (byte >> 6) + 114 * ((byte >> 1) & 0x1f);
+
+        let high = (high * 255 + 15500) / 31000;
+        let low = (low * 255 + 15500) / 31000;
+
+        tlut[byte as usize] = ((high << 8) | low) as u16;
+        byte += 1;
+    }
+
+    tlut
+}
+
+const DESATURATE_COMBINER: ColorCombinerMode = ColorCombinerMode {
+    a_0: ASrc::One,
+    b_0: BSrc::Zero,
+    c_0: CSrc::Texel1Alpha,
+    d_0: DSrc::Texel,
+
+    a_alpha_0: AAlphaSrc::Zero,
+    b_alpha_0: BAlphaSrc::Zero,
+    c_alpha_0: CAlphaSrc::Zero,
+    d_alpha_0: DAlphaSrc::PrimitiveAlpha,
+
+    a_1: ASrc::Zero,
+    b_1: BSrc::Zero,
+    c_1: CSrc::Zero,
+    d_1: DSrc::Combined,
+
+    a_alpha_1: AAlphaSrc::Zero,
+    b_alpha_1: BAlphaSrc::Zero,
+    c_alpha_1: CAlphaSrc::Zero,
+    d_alpha_1: DAlphaSrc::CombinedAlpha,
+};
+
+const DESATURATE_BLEND_MODE: BlendMode = BlendMode::two(
+    PMCycleOne::ColorCombinerRgb,
+    blend_mode::ASrc::Zero,
+    PMCycleOne::ColorCombinerRgb,
+    blend_mode::BSrc::One,
+    PMCycleTwo::FirstCycleNumerator,
+    blend_mode::ASrc::ColorCombinerAlpha,
+    PMCycleTwo::Memory,
+    blend_mode::BSrc::OneMinusA,
+);
+
+const TLUT_TILE: u8 = 7;
+const LOAD_TILE: u8 = 6;
+const TLUT_TMEM_ADDRESS: u16 = (TMEM_SIZE / 2 / 8) as u16;
+
+// Sets up drawing the frame buffer over itself in two cycle mode. Tile 0 samples the high and
+// tile 1 the low byte of every pixel, the combiner adds their luminance lookups and the blender
+// lerps the frame towards it by alpha
+pub fn apply_desaturate(
+    rdp: &mut RdpCommandBuilder,
+    state: &mut RdpState,
+    framebuffer: *const u16,
+    width: i32,
+    alpha: u32,
+) {
+    let other_modes = OTHER_MODE_CYCLE_TYPE_2_CYCLE
+        | OTHER_MODE_BI_LERP_0
+        | OTHER_MODE_BI_LERP_1
+        | OTHER_MODE_EN_TLUT
+        | OTHER_MODE_TLUT_TYPE
+        | OTHER_MODE_FORCE_BLEND
+        | OTHER_MODE_IMAGE_READ_EN
+        | DESATURATE_BLEND_MODE.to_command();
+    let color_combiner_mode = DESATURATE_COMBINER.to_command();
+    let bytes = 2 * width;
+    let rows = desaturate_rows(width);
+
+    rdp.sync_pipe()
+        .sync_load()
+        .set_other_modes(other_modes)
+        .set_combine_mode(color_combiner_mode)
+        .set_prim_color(alpha)
+        .set_texture_image(
+            FORMAT_RGBA,
+            SIZE_OF_PIXEL_16B,
+            LUMINANCE_TLUT.0.len() as u16,
+            LUMINANCE_TLUT.0.as_ptr(),
+        )
+        .set_tile(
+            FORMAT_I,
+            SIZE_OF_PIXEL_4B,
+            0,
+            TLUT_TMEM_ADDRESS,
+            TLUT_TILE,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        )
+        .load_tlut(LUMINANCE_TLUT.0.len() as u16, TLUT_TILE)
+        .sync_tile()
+        .set_texture_image(FORMAT_COLOR_INDX, SIZE_OF_PIXEL_8B, bytes as u16, framebuffer);
+
+    // Tile widths are given in 16 bit texels
+    for tile in [LOAD_TILE, 0, 1] {
+        rdp.set_tile(
+            FORMAT_COLOR_INDX,
+            SIZE_OF_PIXEL_8B,
+            width as u16,
+            0,
+            tile,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        );
+    }
+
+    // Rectangles start at s = 1 and step two texels per pixel, so tile 0 with its origin at 1
+    // lands on the even (high) bytes and tile 1 on the odd (low) ones
+    rdp.set_tile_size(vec2(1.0, 0.0), vec2(bytes as f32, (rows - 1) as f32), 0)
+        .set_tile_size(vec2(0.0, 0.0), vec2((bytes - 1) as f32, (rows - 1) as f32), 1);
+
+    state.other_modes = other_modes;
+    state.color_combiner_mode = color_combiner_mode;
+    state.prim_color = alpha;
+    state.texture = 0;
+    state.texture_rect = TextureRect::default();
+}
+
+// Loads rows of the frame buffer to the top of tmem, drawn with texture coordinates starting at
+// (1, 0) after apply_desaturate
+pub fn load_framebuffer_rows(rdp: &mut RdpCommandBuilder, width: i32, y: i32, rows: i32) {
+    rdp.sync_load().load_tile(
+        vec2((2 * width - 1) as f32, (y + rows - 1) as f32),
+        vec2(0.0, y as f32),
+        LOAD_TILE,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luminance_tlut_adds_up_to_luminance() {
+        let tlut = luminance_tlut();
+
+        for pixel in 0..=u16::MAX {
+            let [high, low] = pixel.to_be_bytes();
+            let luminance = (tlut[high as usize] >> 8) + (tlut[low as usize] & 0xff);
+
+            let r = ((pixel >> 11) & 0x1f) as f32;
+            let g = ((pixel >> 6) & 0x1f) as f32;
+            let b = ((pixel >> 1) & 0x1f) as f32;
+            let expected = (0.299 * r + 0.587 * g + 0.114 * b) * 255.0 / 31.0;
+
+            assert!((luminance as f32 - expected).abs() <= 1.0, "{pixel:#06x}");
+        }
+    }
+
+    #[test]
+    fn desaturate_rows_fit_below_tlut() {
+        for width in [256, 320, 640] {
+            let rows = desaturate_rows(width);
+            assert!(rows > 0);
+            assert!(2 * width * rows <= 8 * TLUT_TMEM_ADDRESS as i32);
         }
     }
 }
@@ -0,0 +1,145 @@
+use crate::{
+    gfx::{lights::color_to_vec3, Lights, MAX_DIRECTIONAL_LIGHTS},
+    include_bytes_align_as,
+};
+use alloc::{boxed::Box, vec::Vec};
+use n64_macros::debugln;
+use n64_math::{Mat4, Vec3};
+use n64_sys::rsp;
+use zerocopy::{AsBytes, FromBytes};
+
+static CODE: &[u8] = include_bytes_align_as!(u64, "../../../../n64-sys/rsp/light.bin");
+
+const GROUP_SIZE: usize = 8;
+const MAX_GROUPS: usize = 32;
+const HEADER_SIZE: usize = 0x80;
+const RSP_TIMEOUT_US: u32 = 100_000;
+
+// Eight vertices as s16 lanes, see rsp/light.asm
+#[repr(C, align(16))]
+#[derive(AsBytes, FromBytes, Copy, Clone)]
+struct Group {
+    normals: [[i16; GROUP_SIZE]; 3],
+    colors: [[i16; GROUP_SIZE]; 3],
+}
+
+#[repr(C, align(16))]
+#[derive(AsBytes, FromBytes)]
+struct LightingDmem {
+    group_count: u32,
+    light_count: u32,
+    padding: [u32; 2],
+    ambient: [i16; 8],
+    lights: [[i16; 8]; MAX_DIRECTIONAL_LIGHTS],
+    padding_lights: [[i16; 8]; 6 - MAX_DIRECTIONAL_LIGHTS],
+    groups: [Group; MAX_GROUPS],
+}
+
+pub struct RspLighting {
+    dmem: Box<LightingDmem>,
+}
+
+impl RspLighting {
+    pub fn new() -> Self {
+        Self {
+            dmem: Box::new(LightingDmem::new_zeroed()),
+        }
+    }
+
+    // Lights the vertex colors on the rsp. The lights are moved into mesh space so the rsp only
+    // has to do the dot products, which is exact as long as the normal transform is a rotation.
+    pub fn light_colors(
+        &mut self,
+        lights: &Lights,
+        normal_transform: &Mat4,
+        colors: &[u32],
+        normals: &[[f32; 3]],
+        lit_colors: &mut Vec<u32>,
+    ) {
+        let dmem = &mut *self.dmem;
+        let to_mesh_space = normal_transform.transpose();
+
+        dmem.ambient = channels_to_fixed(color_to_vec3(lights.ambient_color));
+        dmem.light_count = 0;
+
+        for light in lights.directional.iter().flatten() {
+            let to_light = to_mesh_space
+                .transform_vector3(-light.direction.normalize_or_zero())
+                .normalize_or_zero();
+            let direction = to_fixed_vec(to_light);
+            let color = to_fixed_vec(color_to_vec3(light.color));
+
+            dmem.lights[dmem.light_count as usize] = [
+                direction[0],
+                direction[1],
+                direction[2],
+                color[0],
+                color[1],
+                color[2],
+                0,
+                0,
+            ];
+            dmem.light_count += 1;
+        }
+
+        lit_colors.clear();
+
+        for (colors, normals) in colors
+            .chunks(GROUP_SIZE * MAX_GROUPS)
+            .zip(normals.chunks(GROUP_SIZE * MAX_GROUPS))
+        {
+            let group_count = (colors.len() + GROUP_SIZE - 1) / GROUP_SIZE;
+            dmem.group_count = group_count as u32;
+
+            for (index, (color, normal)) in colors.iter().zip(normals).enumerate() {
+                let group = &mut dmem.groups[index / GROUP_SIZE];
+                let lane = index % GROUP_SIZE;
+                let normal = to_fixed_vec(Vec3::from(*normal).normalize_or_zero());
+
+                for channel in 0..3 {
+                    group.normals[channel][lane] = normal[channel];
+                    group.colors[channel][lane] = ((color >> (24 - 8 * channel)) & 0xff) as i16;
+                }
+            }
+
+            let size = HEADER_SIZE + group_count * core::mem::size_of::<Group>();
+
+            rsp::run(CODE, Some(&dmem.as_bytes()[..size]), false);
+
+            let (done, status) = rsp::wait(RSP_TIMEOUT_US);
+
+            if !done {
+                debugln!("RSP LIGHTING TIMEOUT! {:032b} pc {:08x}", status, rsp::pc());
+                lit_colors.extend_from_slice(colors);
+                continue;
+            }
+
+            rsp::read_dmem(&mut dmem.as_bytes_mut()[..size]);
+
+            lit_colors.extend(colors.iter().enumerate().map(|(index, color)| {
+                let group = &dmem.groups[index / GROUP_SIZE];
+                let lane = index % GROUP_SIZE;
+
+                let r = (group.colors[0][lane] as u32) & 0xff;
+                let g = (group.colors[1][lane] as u32) & 0xff;
+                let b = (group.colors[2][lane] as u32) & 0xff;
+
+                (r << 24) | (g << 16) | (b << 8) | (color & 0xff)
+            }));
+        }
+    }
+}
+
+// s1.15, which is what vmulf works in
+fn to_fixed(value: f32) -> i16 {
+    (value.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+fn to_fixed_vec(v: Vec3) -> [i16; 3] {
+    [to_fixed(v.x), to_fixed(v.y), to_fixed(v.z)]
+}
+
+fn channels_to_fixed(v: Vec3) -> [i16; 8] {
+    let [r, g, b] = to_fixed_vec(v);
+    [r, g, b, 0, 0, 0, 0, 0]
+}
@@ -0,0 +1,47 @@
+use super::CullMode;
+use n64_math::{vec3, Mat4, Vec2, Vec3};
+use n64_types::VideoMode;
+
+// Signed screen space area is positive for counter clockwise (front facing) triangles
+pub(crate) fn triangle_is_culled(cull_mode: CullMode, v0: Vec3, v1: Vec3, v2: Vec3) -> bool {
+    let area = (v1.x - v0.x) * (v2.y - v0.y) - (v1.y - v0.y) * (v2.x - v0.x);
+
+    match cull_mode {
+        CullMode::None => false,
+        CullMode::Front => area > 0.0,
+        CullMode::Back => area < 0.0,
+    }
+}
+
+// Conservative test of a box around the model origin with the x and y extents of size.
+// The z extent is unknown so the largest of the two is used.
+pub(crate) fn mesh_is_outside_screen(transform: &Mat4, size: Vec2, video_mode: VideoMode) -> bool {
+    let half_size = 0.5 * vec3(size.x, size.y, libm::fmaxf(size.x, size.y));
+
+    let mut min = Vec3::splat(f32::MAX);
+    let mut max = Vec3::splat(f32::MIN);
+
+    for i in 0..8 {
+        let sign = |bit: i32| if i & bit == 0 { -1.0 } else { 1.0 };
+        let corner = half_size * vec3(sign(1), sign(2), sign(4));
+
+        let clip = *transform * corner.extend(1.0);
+
+        // Behind the camera, projected coordinates can not be trusted
+        if clip.w <= 0.0 {
+            return false;
+        }
+
+        let p = clip.truncate() / clip.w;
+
+        min = min.min(p);
+        max = max.max(p);
+    }
+
+    max.x < 0.0
+        || max.y < 0.0
+        || min.x > video_mode.width() as f32
+        || min.y > video_mode.height() as f32
+        || max.z < -1.0
+        || min.z > 1.0
+}
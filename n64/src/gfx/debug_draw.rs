@@ -0,0 +1,103 @@
+use super::{
+    color_combiner_mode::{ColorCombinerMode, DSrc},
+    Pipeline,
+};
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use n64_math::{vec2, Vec2};
+
+const LINE_WIDTH: f32 = 1.0;
+const CIRCLE_SEGMENTS: usize = 16;
+const ARROW_HEAD_SIZE: f32 = 6.0;
+
+static DEBUG_DRAW_ENABLED: AtomicBool = AtomicBool::new(false);
+
+// Debug primitives are drawn on top of everything else when the command buffer is submitted
+pub(crate) static DEBUG_DRAW_PIPELINE: Pipeline = Pipeline {
+    color_combiner_mode: ColorCombinerMode::single(DSrc::Shade),
+    blend: true,
+    ..Pipeline::default()
+};
+
+pub fn set_debug_draw_enabled(enabled: bool) {
+    DEBUG_DRAW_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn debug_draw_enabled() -> bool {
+    DEBUG_DRAW_ENABLED.load(Ordering::Relaxed)
+}
+
+#[derive(Copy, Clone)]
+pub(crate) struct DebugLine {
+    pub(crate) start: Vec2,
+    pub(crate) end: Vec2,
+    pub(crate) color: u32,
+}
+
+impl DebugLine {
+    // Quad one pixel wide around the line, in the same order as sprite corners
+    pub(crate) fn corners(&self) -> [Vec2; 4] {
+        let dir = (self.end - self.start).try_normalize().unwrap_or(Vec2::X);
+
+        let along = 0.5 * LINE_WIDTH * dir;
+        let across = 0.5 * LINE_WIDTH * vec2(-dir.y, dir.x);
+
+        [
+            self.start - along - across,
+            self.end + along - across,
+            self.end + along + across,
+            self.start - along + across,
+        ]
+    }
+}
+
+pub(crate) fn push_line(lines: &mut Vec<DebugLine>, start: Vec2, end: Vec2, color: u32) {
+    if debug_draw_enabled() {
+        lines.push(DebugLine { start, end, color });
+    }
+}
+
+pub(crate) fn push_rect(
+    lines: &mut Vec<DebugLine>,
+    upper_left: Vec2,
+    lower_right: Vec2,
+    color: u32,
+) {
+    let upper_right = vec2(lower_right.x, upper_left.y);
+    let lower_left = vec2(upper_left.x, lower_right.y);
+
+    push_line(lines, upper_left, upper_right, color);
+    push_line(lines, upper_right, lower_right, color);
+    push_line(lines, lower_right, lower_left, color);
+    push_line(lines, lower_left, upper_left, color);
+}
+
+pub(crate) fn push_circle(lines: &mut Vec<DebugLine>, center: Vec2, radius: f32, color: u32) {
+    let point = |i: usize| {
+        let angle = 2.0 * core::f32::consts::PI * i as f32 / CIRCLE_SEGMENTS as f32;
+        center + radius * vec2(libm::cosf(angle), libm::sinf(angle))
+    };
+
+    for i in 0..CIRCLE_SEGMENTS {
+        push_line(lines, point(i), point(i + 1), color);
+    }
+}
+
+pub(crate) fn push_arrow(lines: &mut Vec<DebugLine>, start: Vec2, end: Vec2, color: u32) {
+    let dir = (end - start).normalize_or_zero();
+    let side = vec2(-dir.y, dir.x);
+
+    push_line(lines, start, end, color);
+    push_line(
+        lines,
+        end,
+        end - ARROW_HEAD_SIZE * (dir + 0.5 * side),
+        color,
+    );
+    push_line(
+        lines,
+        end,
+        end - ARROW_HEAD_SIZE * (dir - 0.5 * side),
+        color,
+    );
+}
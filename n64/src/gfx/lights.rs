@@ -0,0 +1,55 @@
+use n64_math::Vec3;
+
+pub const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+
+#[derive(Copy, Clone)]
+pub struct DirectionalLight {
+    // Direction the light travels in, in the same space as the normal transform
+    pub direction: Vec3,
+    pub color: u32,
+}
+
+#[derive(Copy, Clone)]
+pub struct Lights {
+    pub ambient_color: u32,
+    pub directional: [Option<DirectionalLight>; MAX_DIRECTIONAL_LIGHTS],
+}
+
+impl Lights {
+    pub const fn default() -> Self {
+        Self {
+            ambient_color: 0xffffffff,
+            directional: [None; MAX_DIRECTIONAL_LIGHTS],
+        }
+    }
+
+    pub fn with_ambient_color(&self, ambient_color: u32) -> Self {
+        let mut res = *self;
+        res.ambient_color = ambient_color;
+        res
+    }
+
+    pub fn with_directional_light(&self, index: usize, light: Option<DirectionalLight>) -> Self {
+        let mut res = *self;
+        res.directional[index] = light;
+        res
+    }
+
+    pub fn directional_count(&self) -> usize {
+        self.directional.iter().flatten().count()
+    }
+}
+
+impl Default for Lights {
+    fn default() -> Self {
+        Self::default()
+    }
+}
+
+pub(crate) fn color_to_vec3(color: u32) -> Vec3 {
+    Vec3::new(
+        ((color >> 24) & 0xff) as f32 / 255.0,
+        ((color >> 16) & 0xff) as f32 / 255.0,
+        ((color >> 8) & 0xff) as f32 / 255.0,
+    )
+}
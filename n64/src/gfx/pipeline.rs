@@ -59,6 +59,14 @@ pub enum CycleType {
     Two,
 }
 
+#[repr(u8)]
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum CullMode {
+    None,
+    Front,
+    Back,
+}
+
 #[derive(Copy, Clone)]
 pub struct Pipeline {
     pub cycle_type: CycleType,
@@ -77,6 +85,8 @@ pub struct Pipeline {
     pub z_src: ZSrc,
     pub z_update: bool,
     pub z_compare: bool,
+
+    pub cull_mode: CullMode,
 }
 
 impl Pipeline {
@@ -95,6 +105,7 @@ impl Pipeline {
             z_src: ZSrc::Pixel,
             z_update: false,
             z_compare: false,
+            cull_mode: CullMode::None,
         }
     }
 
@@ -169,6 +180,12 @@ impl Pipeline {
         res.z_compare = z_compare;
         res
     }
+
+    pub fn with_cull_mode(&self, cull_mode: CullMode) -> Self {
+        let mut res = *self;
+        res.cull_mode = cull_mode;
+        res
+    }
 }
 
 impl Default for Pipeline {
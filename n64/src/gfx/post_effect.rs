@@ -0,0 +1,48 @@
+use super::{
+    color_combiner_mode::{ColorCombinerMode, DSrc},
+    Pipeline,
+};
+use n64_math::{vec2, Vec2};
+
+// Post effects cover the whole screen and are drawn when the command buffer is submitted
+pub(crate) static POST_EFFECT_PIPELINE: Pipeline = Pipeline {
+    color_combiner_mode: ColorCombinerMode::single(DSrc::Primitive),
+    blend: true,
+    ..Pipeline::default()
+};
+
+#[derive(Copy, Clone)]
+pub enum PostEffect {
+    // Amount 0.0 leaves the frame as is, 1.0 covers it with the color. Used for fades and flashes
+    Fade { color: u32, amount: f32 },
+    // Blends the frame towards its luminance, 1.0 is fully grey
+    Desaturate { amount: f32 },
+}
+
+impl PostEffect {
+    pub(crate) fn is_visible(&self) -> bool {
+        match *self {
+            PostEffect::Fade { amount, .. } | PostEffect::Desaturate { amount } => {
+                amount_to_alpha(amount) != 0
+            }
+        }
+    }
+}
+
+// Fades are drawn as a blended overlay
+pub(crate) fn fade_overlay_color(color: u32, amount: f32) -> u32 {
+    (color & 0xffffff00) | amount_to_alpha(amount)
+}
+
+pub(crate) fn amount_to_alpha(amount: f32) -> u32 {
+    (255.0 * amount.clamp(0.0, 1.0)) as u32
+}
+
+pub(crate) fn screen_corners(screen_size: Vec2) -> [Vec2; 4] {
+    [
+        Vec2::ZERO,
+        vec2(screen_size.x, 0.0),
+        screen_size,
+        vec2(0.0, screen_size.y),
+    ]
+}
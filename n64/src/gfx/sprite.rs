@@ -0,0 +1,117 @@
+use n64_math::{vec2, Vec2};
+
+#[derive(Copy, Clone)]
+pub struct Sprite {
+    // Center of the sprite in pixels
+    pub pos: Vec2,
+    // Size in pixels before scale is applied
+    pub size: Vec2,
+    // Radians, clockwise on screen
+    pub rotation: f32,
+    pub scale: Vec2,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    // Normalized sub rect of the pipeline texture
+    pub uv_upper_left: Vec2,
+    pub uv_lower_right: Vec2,
+    // Written to the primitive color, the color combiner decides how it is used
+    pub tint: Option<u32>,
+}
+
+impl Sprite {
+    pub const fn new(pos: Vec2, size: Vec2) -> Self {
+        Self {
+            pos,
+            size,
+            rotation: 0.0,
+            scale: Vec2::ONE,
+            flip_x: false,
+            flip_y: false,
+            uv_upper_left: Vec2::ZERO,
+            uv_lower_right: Vec2::ONE,
+            tint: None,
+        }
+    }
+
+    pub fn with_rotation(&self, rotation: f32) -> Self {
+        let mut res = *self;
+        res.rotation = rotation;
+        res
+    }
+
+    pub fn with_scale(&self, scale: Vec2) -> Self {
+        let mut res = *self;
+        res.scale = scale;
+        res
+    }
+
+    pub fn with_flip(&self, flip_x: bool, flip_y: bool) -> Self {
+        let mut res = *self;
+        res.flip_x = flip_x;
+        res.flip_y = flip_y;
+        res
+    }
+
+    pub fn with_uv(&self, uv_upper_left: Vec2, uv_lower_right: Vec2) -> Self {
+        let mut res = *self;
+        res.uv_upper_left = uv_upper_left;
+        res.uv_lower_right = uv_lower_right;
+        res
+    }
+
+    pub fn with_tint(&self, tint: Option<u32>) -> Self {
+        let mut res = *self;
+        res.tint = tint;
+        res
+    }
+
+    // Rotated sprites can not be drawn as texture rectangles
+    pub(crate) fn is_axis_aligned(&self) -> bool {
+        self.rotation == 0.0
+    }
+
+    pub(crate) fn screen_rect(&self) -> (Vec2, Vec2) {
+        let half_size = 0.5 * (self.size * self.scale).abs();
+        (self.pos - half_size, self.pos + half_size)
+    }
+
+    // Uv at the upper left and lower right corner on screen, negative scale counts as a flip
+    pub(crate) fn uv_rect(&self) -> (Vec2, Vec2) {
+        let mut start = self.uv_upper_left;
+        let mut end = self.uv_lower_right;
+
+        if self.flip_x != (self.scale.x < 0.0) {
+            core::mem::swap(&mut start.x, &mut end.x);
+        }
+
+        if self.flip_y != (self.scale.y < 0.0) {
+            core::mem::swap(&mut start.y, &mut end.y);
+        }
+
+        (start, end)
+    }
+
+    // Upper left, upper right, lower right, lower left
+    pub(crate) fn corners(&self) -> [(Vec2, Vec2); 4] {
+        let half_size = 0.5 * (self.size * self.scale).abs();
+        let (uv_start, uv_end) = self.uv_rect();
+
+        let sin = libm::sinf(self.rotation);
+        let cos = libm::cosf(self.rotation);
+
+        let rotate = |p: Vec2| self.pos + vec2(cos * p.x - sin * p.y, sin * p.x + cos * p.y);
+
+        [
+            (rotate(vec2(-half_size.x, -half_size.y)), uv_start),
+            (
+                rotate(vec2(half_size.x, -half_size.y)),
+                vec2(uv_end.x, uv_start.y),
+            ),
+            (rotate(vec2(half_size.x, half_size.y)), uv_end),
+            (
+                rotate(vec2(-half_size.x, half_size.y)),
+                vec2(uv_start.x, uv_end.y),
+            ),
+        ]
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{ArchiveError, Asset, StaticData};
 use n64_math::Color;
 use zerocopy::LayoutVerified;
 
@@ -53,7 +54,7 @@ pub struct StaticTexture {
     pub width: i32,
     pub height: i32,
     // Should be 8 byte aligned
-    pub data: &'static [u8],
+    pub data: StaticData,
 }
 
 impl StaticTexture {
@@ -62,20 +63,63 @@ impl StaticTexture {
         Self {
             width,
             height,
-            data,
+            data: StaticData::Static(data),
         }
     }
 
     #[inline]
-    pub fn as_texture(self) -> Texture<'static> {
-        let data = LayoutVerified::<_, [Color]>::new_slice_unaligned(self.data)
-            .unwrap()
+    pub const fn from_asset(width: i32, height: i32, asset: &'static Asset) -> Self {
+        Self {
+            width,
+            height,
+            data: StaticData::Asset(asset),
+        }
+    }
+
+    #[inline]
+    pub fn as_texture(self) -> Result<Texture<'static>, ArchiveError> {
+        let data = LayoutVerified::<_, [Color]>::new_slice_unaligned(self.data.get()?)
+            .ok_or(ArchiveError::Corrupt)?
             .into_slice();
 
-        Texture {
+        Ok(Texture {
             width: self.width,
             height: self.height,
             data,
+        })
+    }
+}
+
+// Sub rectangle of a texture in texels, used to draw parts of an atlas
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct TextureRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl TextureRect {
+    #[inline]
+    pub const fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
         }
     }
+
+    #[inline]
+    pub const fn full(texture: &Texture) -> Self {
+        Self::new(0, 0, texture.width, texture.height)
+    }
+
+    #[inline]
+    pub fn contains(&self, other: &TextureRect) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.x + other.width <= self.x + self.width
+            && other.y + other.height <= self.y + self.height
+    }
 }
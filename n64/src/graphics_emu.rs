@@ -1,9 +1,13 @@
 use crate::{current_time_us, framebuffer::Framebuffer, VideoMode};
+use capture::Recording;
 use colored_rect::ColoredRect;
 use copy_tex::CopyTex;
+use desaturate::Desaturate;
 use mesh::Mesh;
 use std::collections::HashSet;
+use std::io;
 use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
@@ -20,8 +24,10 @@ use winit::{
 };
 use zerocopy::{AsBytes, FromBytes};
 
+pub(crate) mod capture;
 pub(crate) mod colored_rect;
 pub(crate) mod copy_tex;
+pub(crate) mod desaturate;
 pub(crate) mod dst_texture;
 pub(crate) mod mesh;
 pub(crate) mod textured_rect;
@@ -83,11 +89,16 @@ pub struct Graphics {
     pub(crate) colored_rect: ColoredRect,
     pub(crate) textured_rect: TexturedRect,
     pub(crate) mesh: Mesh,
+    pub(crate) desaturate: Desaturate,
 
     pub(crate) device_poll_thread_run: Arc<AtomicBool>,
     pub(crate) device_poll_thread: Option<thread::JoinHandle<()>>,
 
     frame_counter: usize,
+
+    last_frame: Vec<u8>,
+    screenshot_requested: bool,
+    recording: Option<Recording>,
 }
 
 impl Graphics {
@@ -183,6 +194,12 @@ impl Graphics {
             dst_texture::TEXUTRE_FORMAT,
             dst_texture::DEPTH_FORMAT,
         );
+        let desaturate = Desaturate::new(
+            &device,
+            dst_texture::TEXUTRE_FORMAT,
+            dst_texture::DEPTH_FORMAT,
+            video_mode,
+        );
 
         window.set_visible(true);
 
@@ -219,11 +236,16 @@ impl Graphics {
             colored_rect,
             textured_rect,
             mesh,
+            desaturate,
 
             device_poll_thread_run,
             device_poll_thread,
 
             frame_counter: 0,
+
+            last_frame: vec![0; (4 * video_mode.width() * video_mode.height()) as usize],
+            screenshot_requested: false,
+            recording: Recording::from_env(),
         }
     }
 
@@ -265,7 +287,9 @@ impl Graphics {
                                     },
                                 ..
                             } => {
-                                self.keys_down.insert(keycode);
+                                if self.keys_down.insert(keycode) {
+                                    self.handle_capture_key(keycode);
+                                }
                             }
                             WindowEvent::KeyboardInput {
                                 input:
@@ -289,6 +313,84 @@ impl Graphics {
         });
     }
 
+    fn handle_capture_key(&mut self, keycode: VirtualKeyCode) {
+        match keycode {
+            VirtualKeyCode::F12 => {
+                self.screenshot_requested = true;
+            }
+            VirtualKeyCode::F11 if self.recording.is_none() => {
+                let dir = Path::new(capture::RECORDING_DIR)
+                    .join(format!("recording_{:06}", self.frame_counter));
+
+                if let Err(err) = self.record_frames(dir, capture::RECORDING_KEY_FRAMES) {
+                    println!("Recording Error: {}", err);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Called by the command buffer with the rgba pixels read back from the DstTexture
+    pub(crate) fn frame_rendered(&mut self, rgba: &[u8]) {
+        for (dst, src) in self.last_frame.chunks_mut(4).zip(rgba.chunks(4)) {
+            dst[0..3].copy_from_slice(&src[0..3]);
+            dst[3] = 0xff;
+        }
+
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+
+            let path = Path::new(capture::SCREENSHOT_DIR)
+                .join(format!("screenshot_{:06}.png", self.frame_counter));
+
+            if let Err(err) = self.save_screenshot(path) {
+                println!("Screenshot Error: {}", err);
+            }
+        }
+
+        if let Some(recording) = &mut self.recording {
+            if !recording.write_frame(
+                self.video_mode.width(),
+                self.video_mode.height(),
+                &self.last_frame,
+            ) {
+                self.recording = None;
+            }
+        }
+    }
+
+    // Rgba pixels of the last frame rendered with a command buffer
+    pub fn read_frame(&self) -> &[u8] {
+        &self.last_frame
+    }
+
+    pub fn save_screenshot<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        capture::write_png(
+            path.as_ref(),
+            self.video_mode.width(),
+            self.video_mode.height(),
+            &self.last_frame,
+        )
+    }
+
+    // Writes the next frame_count rendered frames as numbered pngs in dir, zero stops recording
+    pub fn record_frames<P: Into<PathBuf>>(
+        &mut self,
+        dir: P,
+        frame_count: usize,
+    ) -> io::Result<()> {
+        self.recording = if frame_count > 0 {
+            Some(Recording::new(dir.into(), frame_count)?)
+        } else {
+            None
+        };
+        Ok(())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
     pub(crate) fn render_cpu_buffer(&mut self, framebuffer: &mut Framebuffer) -> i64 {
         let fb = framebuffer.gpu_buffer();
 
@@ -0,0 +1,77 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+
+pub(crate) const SCREENSHOT_DIR: &str = "screenshots";
+pub(crate) const RECORDING_DIR: &str = "recordings";
+pub(crate) const RECORDING_KEY_FRAMES: usize = 300;
+
+// Set both to record frames from the start, useful for golden tests. Zero frames records nothing
+const RECORD_DIR_ENV: &str = "N64_RECORD_DIR";
+const RECORD_FRAMES_ENV: &str = "N64_RECORD_FRAMES";
+
+pub(crate) struct Recording {
+    dir: PathBuf,
+    frame_index: usize,
+    frame_count: usize,
+}
+
+impl Recording {
+    pub(crate) fn new(dir: PathBuf, frame_count: usize) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir,
+            frame_index: 0,
+            frame_count,
+        })
+    }
+
+    pub(crate) fn from_env() -> Option<Self> {
+        let dir = std::env::var_os(RECORD_DIR_ENV)?;
+        let frame_count = std::env::var(RECORD_FRAMES_ENV)
+            .ok()?
+            .parse::<usize>()
+            .ok()
+            .filter(|frame_count| *frame_count > 0)?;
+
+        Self::new(dir.into(), frame_count)
+            .map_err(|err| println!("Recording Error: {}", err))
+            .ok()
+    }
+
+    // Returns false when all frames have been written
+    pub(crate) fn write_frame(&mut self, width: i32, height: i32, rgba: &[u8]) -> bool {
+        if self.frame_index >= self.frame_count {
+            return false;
+        }
+
+        let path = self.dir.join(format!("frame_{:05}.png", self.frame_index));
+
+        if let Err(err) = write_png(&path, width, height, rgba) {
+            println!("Recording Error: {}", err);
+        }
+
+        self.frame_index += 1;
+        self.frame_index < self.frame_count
+    }
+}
+
+pub(crate) fn write_png(path: &Path, width: i32, height: i32, rgba: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let file = BufWriter::new(File::create(path)?);
+
+    let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(rgba))
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))
+}
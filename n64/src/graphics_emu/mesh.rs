@@ -1,6 +1,9 @@
 #![allow(clippy::inconsistent_digit_grouping)]
 
-use crate::{gfx::Texture, graphics_emu::shader};
+use crate::{
+    gfx::{Texture, MAX_DIRECTIONAL_LIGHTS},
+    graphics_emu::shader,
+};
 use n64_math::Color;
 use std::{collections::HashMap, mem, num::NonZeroU32};
 use zerocopy::{AsBytes, FromBytes};
@@ -11,6 +14,7 @@ pub const MAX_MESHES: u64 = 4096;
 #[derive(Clone, Copy, Debug, AsBytes, FromBytes)]
 pub(crate) struct MeshUniforms {
     pub transform: [[f32; 4]; 4],
+    pub normal_transform: [[f32; 4]; 4],
     pub screen_size_and_pad: [f32; 4],
     pub combine_mode: [u32; 2],
     pub blend_mode: [u32; 2],
@@ -18,6 +22,10 @@ pub(crate) struct MeshUniforms {
     pub env_color: [f32; 4],
     pub blend_color: [f32; 4],
     pub fog_color: [f32; 4],
+    pub ambient_color: [f32; 4],
+    pub light_directions: [[f32; 4]; MAX_DIRECTIONAL_LIGHTS],
+    pub light_colors: [[f32; 4]; MAX_DIRECTIONAL_LIGHTS],
+    pub lighting: [u32; 4],
 }
 
 pub(crate) struct UploadedTexture {
@@ -119,7 +127,7 @@ impl Mesh {
                 module: &vs_module,
                 entry_point: "main",
                 buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: 9 * mem::size_of::<f32>() as wgpu::BufferAddress,
+                    array_stride: 12 * mem::size_of::<f32>() as wgpu::BufferAddress,
                     step_mode: wgpu::VertexStepMode::Vertex,
                     attributes: &[
                         wgpu::VertexAttribute {
@@ -137,6 +145,11 @@ impl Mesh {
                             offset: 5 * mem::size_of::<f32>() as u64,
                             shader_location: 2,
                         },
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x3,
+                            offset: 9 * mem::size_of::<f32>() as u64,
+                            shader_location: 3,
+                        },
                     ],
                 }],
             },
@@ -0,0 +1,22 @@
+#version 460
+
+layout(location = 0) in flat uint v_instance_id;
+layout(location = 0) out vec4 o_color;
+
+struct Uniforms {
+    vec4 u_amount;
+};
+
+layout(std430, set = 0, binding = 0) readonly buffer Locals {
+    Uniforms uniforms[];
+};
+
+layout(set = 0, binding = 1) uniform texture2D t_src;
+layout(set = 0, binding = 2) uniform sampler s_src;
+
+void main() {
+    vec4 color = texelFetch(sampler2D(t_src, s_src), ivec2(gl_FragCoord.xy), 0);
+    float luminance = dot(color.rgb, vec3(0.299, 0.587, 0.114));
+
+    o_color = vec4(mix(color.rgb, vec3(luminance), uniforms[v_instance_id].u_amount.x), color.a);
+}
@@ -0,0 +1,9 @@
+#version 460
+
+layout(location = 0) in vec3 a_pos;
+layout(location = 0) out flat uint v_instance_id;
+
+void main() {
+    v_instance_id = gl_InstanceIndex;
+    gl_Position = vec4(a_pos, 1.0);
+}
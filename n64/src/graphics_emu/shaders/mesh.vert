@@ -3,6 +3,7 @@
 layout(location = 0) in vec3 a_pos;
 layout(location = 1) in vec2 a_tex_coord;
 layout(location = 2) in vec4 a_color;
+layout(location = 3) in vec3 a_normal;
 
 layout(location = 0) out vec2 v_tex_coord;
 layout(location = 1) out vec4 v_color;
@@ -13,8 +14,11 @@ layout(location = 5) out flat vec4 v_env_color;
 layout(location = 6) out flat vec4 v_blend_color;
 layout(location = 7) out flat vec4 v_fog_color;
 
+#define MAX_DIRECTIONAL_LIGHTS 4
+
 struct Uniforms {
     mat4 u_transform;
+    mat4 u_normal_transform;
     vec4 u_screen_size_and_pad;
     uvec2 u_color_combiner_mode;
     uvec2 u_blend_mode;
@@ -22,15 +26,35 @@ struct Uniforms {
     vec4 u_env_color;
     vec4 u_blend_color;
     vec4 u_fog_color;
+    vec4 u_ambient_color;
+    vec4 u_light_directions[MAX_DIRECTIONAL_LIGHTS];
+    vec4 u_light_colors[MAX_DIRECTIONAL_LIGHTS];
+    uvec4 u_lighting; // x: enabled, y: directional light count
 };
 
 layout(std430, set = 0, binding = 0) readonly buffer Locals {
     Uniforms uniforms[];
 };
 
+vec4 lit_color(vec4 color) {
+    vec3 normal = normalize((uniforms[gl_InstanceIndex].u_normal_transform * vec4(a_normal, 0.0)).xyz);
+    vec3 intensity = uniforms[gl_InstanceIndex].u_ambient_color.rgb;
+
+    for (uint i = 0; i < uniforms[gl_InstanceIndex].u_lighting.y; ++i) {
+        vec3 light_dir = uniforms[gl_InstanceIndex].u_light_directions[i].xyz;
+        intensity += max(0.0, dot(normal, -light_dir)) * uniforms[gl_InstanceIndex].u_light_colors[i].rgb;
+    }
+
+    return vec4(color.rgb * min(intensity, vec3(1.0)), color.a);
+}
+
 void main() {
     v_tex_coord = a_tex_coord;
-    v_color = a_color;
+    if (uniforms[gl_InstanceIndex].u_lighting.x != 0) {
+        v_color = lit_color(a_color);
+    } else {
+        v_color = a_color;
+    }
     v_color_combiner_mode = uniforms[gl_InstanceIndex].u_color_combiner_mode;
     v_blend_mode = uniforms[gl_InstanceIndex].u_blend_mode;
     v_prim_color = uniforms[gl_InstanceIndex].u_prim_color;
@@ -14,6 +14,7 @@ layout(location = 7) out flat vec4 v_fog_color;
 
 struct Uniforms {
     vec4 u_offset_and_scale;
+    vec4 u_uv_offset_and_scale;
     uvec2 u_color_combiner_mode;
     uvec2 u_blend_mode;
     vec4 u_prim_color;
@@ -27,7 +28,8 @@ layout(std430, set = 0, binding = 0) readonly buffer Locals {
 };
 
 void main() {
-    v_tex_coord = a_tex_coord;
+    v_tex_coord = uniforms[gl_InstanceIndex].u_uv_offset_and_scale.xy
+        + uniforms[gl_InstanceIndex].u_uv_offset_and_scale.zw * a_tex_coord;
     v_color = vec4(0.0);
     v_color_combiner_mode = uniforms[gl_InstanceIndex].u_color_combiner_mode;
     v_blend_mode = uniforms[gl_InstanceIndex].u_blend_mode;
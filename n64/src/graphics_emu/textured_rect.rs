@@ -13,6 +13,8 @@ pub const MAX_TEXTURED_RECTS: u64 = 4096;
 pub(crate) struct TexturedRectUniforms {
     pub offset: [f32; 2],
     pub scale: [f32; 2],
+    pub uv_offset: [f32; 2],
+    pub uv_scale: [f32; 2],
     pub combine_mode: [u32; 2],
     pub blend_mode: [u32; 2],
     pub prim_color: [f32; 4],
@@ -0,0 +1,27 @@
+use crate::framebuffer::Framebuffer;
+use n64_types::Command;
+
+// There is no deploy tool connected to the emulator
+pub struct Host;
+
+impl Host {
+    #[inline]
+    pub fn new() -> Host {
+        Host
+    }
+
+    #[inline]
+    pub fn poll(&mut self) -> Option<Command> {
+        None
+    }
+
+    // Screenshots are taken with F12 on the emulator
+    #[inline]
+    pub fn send_screenshot(&mut self, _framebuffer: &Framebuffer) {}
+}
+
+impl Default for Host {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,107 @@
+#![cfg_attr(not(target_vendor = "nintendo64"), allow(unused))]
+
+use crate::framebuffer::Framebuffer;
+use core::{mem::size_of, slice};
+use n64_macros::debugln;
+use n64_sys::{
+    ed::{self, USB_BLOCK_SIZE},
+    sys::uncached_addr,
+};
+use n64_types::{
+    Command, FrameDecoder, MessageType, ScreenshotMessageHeader, FRAME_MAX_PAYLOAD,
+    SCREENSHOT_PIXELS_PER_MESSAGE,
+};
+use zerocopy::AsBytes;
+
+#[repr(C, align(16))]
+struct Block([u8; USB_BLOCK_SIZE]);
+
+// Commands from the deploy tool arrive over the EverDrive USB in whole blocks, a block can hold
+// several frames and a frame can span several blocks
+pub struct Host {
+    decoder: FrameDecoder,
+    block: Block,
+    cursor: usize,
+}
+
+impl Host {
+    #[inline]
+    pub fn new() -> Host {
+        Host {
+            decoder: FrameDecoder::new(),
+            block: Block([0; USB_BLOCK_SIZE]),
+            cursor: USB_BLOCK_SIZE,
+        }
+    }
+
+    // Returns the next command, or None when nothing more has arrived
+    pub fn poll(&mut self) -> Option<Command> {
+        loop {
+            if self.cursor == USB_BLOCK_SIZE {
+                if !ed::can_read() || !ed::usb_read(&mut self.block.0) {
+                    return None;
+                }
+
+                self.cursor = 0;
+            }
+
+            while self.cursor < USB_BLOCK_SIZE {
+                let byte = self.block.0[self.cursor];
+                self.cursor += 1;
+
+                if let Some(Ok(frame)) = self.decoder.push(byte) {
+                    if let Some(command) = Command::decode(&frame) {
+                        return Some(command);
+                    }
+                }
+            }
+        }
+    }
+
+    // Streams the frame the vi is showing to the deploy tool, which saves it as a png. The pixels
+    // are read uncached since the rdp wrote them. Stops at the first message that can't be sent
+    pub fn send_screenshot(&mut self, framebuffer: &Framebuffer) {
+        let width = framebuffer.video_mode.width();
+        let height = framebuffer.video_mode.height();
+
+        let pixels = unsafe {
+            slice::from_raw_parts(
+                uncached_addr(framebuffer.vi_buffer.0.as_ptr()),
+                framebuffer.vi_buffer.0.len(),
+            )
+        };
+
+        let mut payload = [0; FRAME_MAX_PAYLOAD];
+
+        for (i, chunk) in pixels.chunks(SCREENSHOT_PIXELS_PER_MESSAGE).enumerate() {
+            let offset = i * SCREENSHOT_PIXELS_PER_MESSAGE;
+            let header = ScreenshotMessageHeader {
+                width: (width as u16).to_be(),
+                height: (height as u16).to_be(),
+                offset: (offset as u32).to_be(),
+            };
+
+            let len = size_of::<ScreenshotMessageHeader>() + chunk.as_bytes().len();
+
+            payload[..size_of::<ScreenshotMessageHeader>()].copy_from_slice(header.as_bytes());
+            payload[size_of::<ScreenshotMessageHeader>()..len].copy_from_slice(chunk.as_bytes());
+
+            if !ed::send(MessageType::Screenshot, &payload[..len]) {
+                debugln!(
+                    "Screenshot stopped after {} of {} pixels",
+                    offset,
+                    pixels.len()
+                );
+                return;
+            }
+
+            ed::wait_for_usb();
+        }
+    }
+}
+
+impl Default for Host {
+    fn default() -> Self {
+        Self::new()
+    }
+}
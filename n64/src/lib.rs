@@ -1,11 +1,19 @@
 #![cfg_attr(target_vendor = "nintendo64", no_std)]
+#![cfg_attr(target_vendor = "nintendo64", feature(asm_experimental_arch))]
+#![cfg_attr(target_vendor = "nintendo64", feature(panic_info_message))]
 
 extern crate alloc;
 
+pub use archive::{Archive, ArchiveError, Asset, AssetBuffer, StaticData};
 pub use audio::Audio;
+pub use controller::{Controller, MAX_PORTS};
 pub use controllers::Controllers;
+pub use eeprom::Eeprom;
+pub use flashram::FlashRam;
 pub use framebuffer::Framebuffer;
 pub use graphics::Graphics;
+pub use host::Host;
+pub use sram::Sram;
 
 pub use n64_macros::*;
 pub use n64_profiler::*;
@@ -17,40 +25,85 @@ pub use n64_alloc::*;
 
 pub mod gfx;
 pub mod ipl3font;
+pub mod mempak;
+pub mod save;
 pub mod utils;
 
+#[cfg(target_vendor = "nintendo64")]
+pub mod crash;
+
+mod archive;
+mod controller;
 mod framebuffer;
 
+mod archive_n64;
 mod audio_n64;
 mod controllers_n64;
+mod eeprom_n64;
+mod flashram_n64;
 mod graphics_n64;
+mod host_n64;
+mod sram_n64;
 
+#[cfg(not(target_vendor = "nintendo64"))]
+mod archive_emu;
 #[cfg(not(target_vendor = "nintendo64"))]
 pub mod audio_emu;
 #[cfg(not(target_vendor = "nintendo64"))]
 pub mod controllers_emu;
 #[cfg(not(target_vendor = "nintendo64"))]
+mod eeprom_emu;
+#[cfg(not(target_vendor = "nintendo64"))]
+mod flashram_emu;
+#[cfg(not(target_vendor = "nintendo64"))]
 pub mod graphics_emu;
+#[cfg(not(target_vendor = "nintendo64"))]
+mod host_emu;
+#[cfg(not(target_vendor = "nintendo64"))]
+mod save_file_emu;
+#[cfg(not(target_vendor = "nintendo64"))]
+mod sram_emu;
 
+#[cfg(target_vendor = "nintendo64")]
+use archive_n64 as archive_io;
 #[cfg(target_vendor = "nintendo64")]
 use audio_n64 as audio;
 #[cfg(target_vendor = "nintendo64")]
 use controllers_n64 as controllers;
 #[cfg(target_vendor = "nintendo64")]
+use eeprom_n64 as eeprom;
+#[cfg(target_vendor = "nintendo64")]
+use flashram_n64 as flashram;
+#[cfg(target_vendor = "nintendo64")]
 use graphics_n64 as graphics;
+#[cfg(target_vendor = "nintendo64")]
+use host_n64 as host;
+#[cfg(target_vendor = "nintendo64")]
+use sram_n64 as sram;
 
+#[cfg(not(target_vendor = "nintendo64"))]
+use archive_emu as archive_io;
 #[cfg(not(target_vendor = "nintendo64"))]
 use audio_emu as audio;
 #[cfg(not(target_vendor = "nintendo64"))]
 use controllers_emu as controllers;
 #[cfg(not(target_vendor = "nintendo64"))]
+use eeprom_emu as eeprom;
+#[cfg(not(target_vendor = "nintendo64"))]
+use flashram_emu as flashram;
+#[cfg(not(target_vendor = "nintendo64"))]
 use graphics_emu as graphics;
+#[cfg(not(target_vendor = "nintendo64"))]
+use host_emu as host;
+#[cfg(not(target_vendor = "nintendo64"))]
+use sram_emu as sram;
 
 pub struct N64 {
     pub audio: Audio,
     pub framebuffer: Framebuffer,
     pub graphics: Graphics,
     pub controllers: Controllers,
+    pub host: Host,
 }
 
 impl N64 {
@@ -60,10 +113,20 @@ impl N64 {
         let mut framebuffer = Framebuffer::new(video_mode);
         let graphics = Graphics::new(video_mode, &mut framebuffer);
         let controllers = Controllers::new();
+        let host = Host::new();
+
+        #[cfg(target_vendor = "nintendo64")]
+        crash::init(video_mode);
+
+        #[cfg(target_vendor = "nintendo64")]
+        n64_sys::mi::init();
 
         #[cfg(target_vendor = "nintendo64")]
         n64_sys::pi::init();
 
+        #[cfg(target_vendor = "nintendo64")]
+        n64_sys::pi_queue::init();
+
         #[cfg(target_vendor = "nintendo64")]
         n64_sys::ed::init();
 
@@ -72,6 +135,7 @@ impl N64 {
             framebuffer,
             graphics,
             controllers,
+            host,
         }
     }
 }
@@ -0,0 +1,496 @@
+use alloc::{string::String, vec::Vec};
+use n64_sys::{
+    pak::{self, PakError, PAK_BLOCK_SIZE},
+    si::Pif,
+};
+
+// The Controller Pak is 128 blocks of 256 bytes. Block 0 holds the id, 1 and 2 the inode table
+// and its backup, 3 and 4 the note table and the rest is file data.
+const BLOCK_SIZE: usize = 256;
+const BLOCK_COUNT: usize = 128;
+const ID_BLOCK: usize = 0;
+const INODE_BLOCK: usize = 1;
+const INODE_BACKUP_BLOCK: usize = 2;
+const NOTE_BLOCK: usize = 3;
+const FIRST_DATA_BLOCK: usize = 5;
+
+const NOTE_COUNT: usize = 16;
+const NOTE_SIZE: usize = 32;
+
+const INODE_LAST: u8 = 0x01;
+const INODE_FREE: u8 = 0x03;
+
+const NOTE_OCCUPIED: u8 = 0x02;
+
+const NOTE_NAME_LEN: usize = 16;
+const NOTE_EXTENSION_LEN: usize = 4;
+
+// Characters 0x34 and up in the note name encoding
+const NOTE_PUNCTUATION: &[u8] = b"!\"#'*+,-./:=?@";
+
+const ID_SECTOR_OFFSETS: [usize; 4] = [0x20, 0x60, 0x80, 0xc0];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MempakError {
+    Pak(PakError),
+    NotFormatted,
+    Corrupt,
+    NoSpace,
+    NoFreeNote,
+    NoteNotFound,
+    InvalidName,
+}
+
+impl From<PakError> for MempakError {
+    fn from(err: PakError) -> Self {
+        MempakError::Pak(err)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Note {
+    pub index: usize,
+    pub game_code: u32,
+    pub publisher_code: u16,
+    pub name: String,
+    pub extension: String,
+    pub blocks: usize,
+}
+
+fn encode_char(ch: u8) -> Option<u8> {
+    match ch.to_ascii_uppercase() {
+        b' ' => Some(0x0f),
+        ch @ b'0'..=b'9' => Some(ch - b'0' + 0x10),
+        ch @ b'A'..=b'Z' => Some(ch - b'A' + 0x1a),
+        ch => NOTE_PUNCTUATION
+            .iter()
+            .position(|p| *p == ch)
+            .map(|i| i as u8 + 0x34),
+    }
+}
+
+fn decode_char(ch: u8) -> Option<char> {
+    match ch {
+        0x0f => Some(' '),
+        0x10..=0x19 => Some((ch - 0x10 + b'0') as char),
+        0x1a..=0x33 => Some((ch - 0x1a + b'A') as char),
+        0x34..=0x41 => Some(NOTE_PUNCTUATION[(ch - 0x34) as usize] as char),
+        _ => None,
+    }
+}
+
+fn encode_str(s: &str, out: &mut [u8]) -> Result<(), MempakError> {
+    if s.len() > out.len() {
+        return Err(MempakError::InvalidName);
+    }
+
+    out.fill(0);
+
+    for (out, ch) in out.iter_mut().zip(s.bytes()) {
+        *out = encode_char(ch).ok_or(MempakError::InvalidName)?;
+    }
+
+    Ok(())
+}
+
+fn decode_str(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|ch| **ch != 0)
+        .map(|ch| decode_char(*ch).unwrap_or('?'))
+        .collect()
+}
+
+fn inode_checksum(inodes: &[u8; BLOCK_SIZE]) -> u8 {
+    (FIRST_DATA_BLOCK..BLOCK_COUNT).fold(0u8, |sum, block| sum.wrapping_add(inodes[block * 2 + 1]))
+}
+
+fn inodes_valid(inodes: &[u8; BLOCK_SIZE]) -> bool {
+    inodes[1] == inode_checksum(inodes)
+        && (FIRST_DATA_BLOCK..BLOCK_COUNT).all(|block| {
+            let next = inodes[block * 2 + 1] as usize;
+            next == INODE_LAST as usize
+                || next == INODE_FREE as usize
+                || (FIRST_DATA_BLOCK..BLOCK_COUNT).contains(&next)
+        })
+}
+
+fn id_sector() -> [u8; PAK_BLOCK_SIZE] {
+    let mut sector = [0; PAK_BLOCK_SIZE];
+
+    // Device id 1 and a single bank
+    sector[0x19] = 0x01;
+    sector[0x1a] = 0x01;
+
+    let sum = sector[..0x1c].chunks_exact(2).fold(0u16, |sum, word| {
+        sum.wrapping_add(u16::from_be_bytes([word[0], word[1]]))
+    });
+
+    sector[0x1c..0x1e].copy_from_slice(&sum.to_be_bytes());
+    sector[0x1e..0x20].copy_from_slice(&0xfff2u16.wrapping_sub(sum).to_be_bytes());
+
+    sector
+}
+
+// File system on a Controller Pak, the inode and note tables are cached and written back on change
+pub struct Mempak<P: Pif> {
+    pif: P,
+    port: usize,
+    inodes: [u8; BLOCK_SIZE],
+    notes: [u8; NOTE_COUNT * NOTE_SIZE],
+}
+
+impl<P: Pif> Mempak<P> {
+    pub fn open(pif: P, port: usize) -> Result<Self, MempakError> {
+        let mut mempak = Mempak {
+            pif,
+            port,
+            inodes: [0; BLOCK_SIZE],
+            notes: [0; NOTE_COUNT * NOTE_SIZE],
+        };
+
+        let mut inodes = [0; BLOCK_SIZE];
+        mempak.read_bytes(INODE_BLOCK * BLOCK_SIZE, &mut inodes)?;
+
+        if !inodes_valid(&inodes) {
+            mempak.read_bytes(INODE_BACKUP_BLOCK * BLOCK_SIZE, &mut inodes)?;
+
+            if !inodes_valid(&inodes) {
+                return Err(MempakError::NotFormatted);
+            }
+        }
+
+        mempak.inodes = inodes;
+
+        let mut notes = [0; NOTE_COUNT * NOTE_SIZE];
+        mempak.read_bytes(NOTE_BLOCK * BLOCK_SIZE, &mut notes)?;
+        mempak.notes = notes;
+
+        for index in 0..NOTE_COUNT {
+            if mempak.is_occupied(index) {
+                mempak.chain(mempak.start_block(index))?;
+            }
+        }
+
+        Ok(mempak)
+    }
+
+    // Erases everything on the pak
+    pub fn format(pif: P, port: usize) -> Result<Self, MempakError> {
+        let mut mempak = Mempak {
+            pif,
+            port,
+            inodes: [0; BLOCK_SIZE],
+            notes: [0; NOTE_COUNT * NOTE_SIZE],
+        };
+
+        let mut id_block = [0; BLOCK_SIZE];
+        let id_sector = id_sector();
+
+        for offset in ID_SECTOR_OFFSETS {
+            id_block[offset..offset + PAK_BLOCK_SIZE].copy_from_slice(&id_sector);
+        }
+
+        mempak.write_bytes(ID_BLOCK * BLOCK_SIZE, &id_block)?;
+
+        for block in FIRST_DATA_BLOCK..BLOCK_COUNT {
+            mempak.inodes[block * 2 + 1] = INODE_FREE;
+        }
+
+        mempak.commit()?;
+
+        Ok(mempak)
+    }
+
+    pub fn into_pif(self) -> P {
+        self.pif
+    }
+
+    pub fn free_blocks(&self) -> usize {
+        (FIRST_DATA_BLOCK..BLOCK_COUNT)
+            .filter(|block| self.inodes[block * 2 + 1] == INODE_FREE)
+            .count()
+    }
+
+    pub fn notes(&self) -> impl Iterator<Item = Note> + '_ {
+        (0..NOTE_COUNT).filter_map(|index| self.note(index))
+    }
+
+    pub fn find(&self, name: &str, extension: &str) -> Option<Note> {
+        self.notes().find(|note| {
+            note.name.eq_ignore_ascii_case(name) && note.extension.eq_ignore_ascii_case(extension)
+        })
+    }
+
+    pub fn create(
+        &mut self,
+        game_code: u32,
+        publisher_code: u16,
+        name: &str,
+        extension: &str,
+        data: &[u8],
+    ) -> Result<Note, MempakError> {
+        let mut entry = [0; NOTE_SIZE];
+        entry[0..4].copy_from_slice(&game_code.to_be_bytes());
+        entry[4..6].copy_from_slice(&publisher_code.to_be_bytes());
+        entry[8] = NOTE_OCCUPIED;
+        encode_str(extension, &mut entry[12..12 + NOTE_EXTENSION_LEN])?;
+        encode_str(name, &mut entry[16..16 + NOTE_NAME_LEN])?;
+
+        let index = (0..NOTE_COUNT)
+            .find(|index| !self.is_occupied(*index))
+            .ok_or(MempakError::NoFreeNote)?;
+
+        let start = self.allocate(data)?;
+        entry[6..8].copy_from_slice(&(start as u16).to_be_bytes());
+
+        self.notes[index * NOTE_SIZE..(index + 1) * NOTE_SIZE].copy_from_slice(&entry);
+        self.commit()?;
+
+        self.note(index).ok_or(MempakError::Corrupt)
+    }
+
+    // Returns the whole allocation, data is padded to full blocks
+    pub fn read(&mut self, note: &Note) -> Result<Vec<u8>, MempakError> {
+        if !self.is_occupied(note.index) {
+            return Err(MempakError::NoteNotFound);
+        }
+
+        let chain = self.chain(self.start_block(note.index))?;
+        let mut data = alloc::vec![0; chain.len() * BLOCK_SIZE];
+
+        for (block, data) in chain.iter().zip(data.chunks_exact_mut(BLOCK_SIZE)) {
+            self.read_bytes(block * BLOCK_SIZE, data)?;
+        }
+
+        Ok(data)
+    }
+
+    // Replaces the contents of the note, growing or shrinking its allocation
+    pub fn write(&mut self, note: &Note, data: &[u8]) -> Result<Note, MempakError> {
+        if !self.is_occupied(note.index) {
+            return Err(MempakError::NoteNotFound);
+        }
+
+        let old_chain = self.chain(self.start_block(note.index))?;
+
+        if Self::blocks_needed(data) > self.free_blocks() + old_chain.len() {
+            return Err(MempakError::NoSpace);
+        }
+
+        for block in old_chain {
+            self.inodes[block * 2 + 1] = INODE_FREE;
+        }
+
+        let start = self.allocate(data)?;
+        self.notes[note.index * NOTE_SIZE + 6..note.index * NOTE_SIZE + 8]
+            .copy_from_slice(&(start as u16).to_be_bytes());
+
+        self.commit()?;
+
+        self.note(note.index).ok_or(MempakError::Corrupt)
+    }
+
+    pub fn delete(&mut self, note: &Note) -> Result<(), MempakError> {
+        if !self.is_occupied(note.index) {
+            return Err(MempakError::NoteNotFound);
+        }
+
+        for block in self.chain(self.start_block(note.index))? {
+            self.inodes[block * 2 + 1] = INODE_FREE;
+        }
+
+        self.notes[note.index * NOTE_SIZE..(note.index + 1) * NOTE_SIZE].fill(0);
+
+        self.commit()
+    }
+
+    fn blocks_needed(data: &[u8]) -> usize {
+        ((data.len() + BLOCK_SIZE - 1) / BLOCK_SIZE).max(1)
+    }
+
+    fn is_occupied(&self, index: usize) -> bool {
+        self.notes[index * NOTE_SIZE + 8] & NOTE_OCCUPIED != 0
+    }
+
+    fn start_block(&self, index: usize) -> usize {
+        self.notes[index * NOTE_SIZE + 7] as usize
+    }
+
+    fn note(&self, index: usize) -> Option<Note> {
+        if !self.is_occupied(index) {
+            return None;
+        }
+
+        let entry = &self.notes[index * NOTE_SIZE..(index + 1) * NOTE_SIZE];
+
+        Some(Note {
+            index,
+            game_code: u32::from_be_bytes(entry[0..4].try_into().unwrap()),
+            publisher_code: u16::from_be_bytes([entry[4], entry[5]]),
+            name: decode_str(&entry[16..16 + NOTE_NAME_LEN]),
+            extension: decode_str(&entry[12..12 + NOTE_EXTENSION_LEN]),
+            blocks: self.chain(self.start_block(index)).ok()?.len(),
+        })
+    }
+
+    fn chain(&self, start: usize) -> Result<Vec<usize>, MempakError> {
+        let mut chain = Vec::new();
+        let mut block = start;
+
+        loop {
+            if !(FIRST_DATA_BLOCK..BLOCK_COUNT).contains(&block)
+                || chain.len() == BLOCK_COUNT - FIRST_DATA_BLOCK
+            {
+                return Err(MempakError::Corrupt);
+            }
+
+            chain.push(block);
+
+            match self.inodes[block * 2 + 1] {
+                INODE_LAST => return Ok(chain),
+                next => block = next as usize,
+            }
+        }
+    }
+
+    // Writes the data to free blocks and links them, returns the first block
+    fn allocate(&mut self, data: &[u8]) -> Result<usize, MempakError> {
+        let chain = (FIRST_DATA_BLOCK..BLOCK_COUNT)
+            .filter(|block| self.inodes[block * 2 + 1] == INODE_FREE)
+            .take(Self::blocks_needed(data))
+            .collect::<Vec<_>>();
+
+        if chain.len() < Self::blocks_needed(data) {
+            return Err(MempakError::NoSpace);
+        }
+
+        for (i, block) in chain.iter().enumerate() {
+            let mut contents = [0; BLOCK_SIZE];
+            let chunk = data.chunks(BLOCK_SIZE).nth(i).unwrap_or(&[]);
+            contents[..chunk.len()].copy_from_slice(chunk);
+
+            self.write_bytes(block * BLOCK_SIZE, &contents)?;
+
+            self.inodes[block * 2] = 0;
+            self.inodes[block * 2 + 1] = chain
+                .get(i + 1)
+                .map(|next| *next as u8)
+                .unwrap_or(INODE_LAST);
+        }
+
+        Ok(chain[0])
+    }
+
+    fn commit(&mut self) -> Result<(), MempakError> {
+        self.inodes[1] = inode_checksum(&self.inodes);
+
+        let inodes = self.inodes;
+        let notes = self.notes;
+
+        self.write_bytes(INODE_BLOCK * BLOCK_SIZE, &inodes)?;
+        self.write_bytes(INODE_BACKUP_BLOCK * BLOCK_SIZE, &inodes)?;
+        self.write_bytes(NOTE_BLOCK * BLOCK_SIZE, &notes)
+    }
+
+    fn read_bytes(&mut self, address: usize, data: &mut [u8]) -> Result<(), MempakError> {
+        for (i, chunk) in data.chunks_exact_mut(PAK_BLOCK_SIZE).enumerate() {
+            let address = (address + i * PAK_BLOCK_SIZE) as u16;
+            pak::read(&mut self.pif, self.port, address, chunk.try_into().unwrap())?;
+        }
+
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, address: usize, data: &[u8]) -> Result<(), MempakError> {
+        for (i, chunk) in data.chunks_exact(PAK_BLOCK_SIZE).enumerate() {
+            let address = (address + i * PAK_BLOCK_SIZE) as u16;
+            pak::write(&mut self.pif, self.port, address, chunk.try_into().unwrap())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_vendor = "nintendo64")))]
+mod tests {
+    use super::*;
+    use n64_sys::mock_pif::MockPif;
+
+    #[test]
+    fn mempak_create_read_write_delete() {
+        let mut pif = MockPif::new();
+        pif.insert_pak(0);
+
+        let mut mempak = Mempak::format(pif, 0).unwrap();
+        let free = mempak.free_blocks();
+        assert_eq!(free, BLOCK_COUNT - FIRST_DATA_BLOCK);
+
+        let save = (0..600).map(|i| i as u8).collect::<Vec<_>>();
+        let note = mempak
+            .create(0x4e4c4b45, 0x3031, "Loka save", "A", &save)
+            .unwrap();
+
+        assert_eq!(note.name, "LOKA SAVE");
+        assert_eq!(note.extension, "A");
+        assert_eq!(note.blocks, 3);
+        assert_eq!(mempak.free_blocks(), free - 3);
+        assert_eq!(&mempak.read(&note).unwrap()[..save.len()], &save[..]);
+
+        let note = mempak.write(&note, &[0xaa; 100]).unwrap();
+        assert_eq!(note.blocks, 1);
+        assert_eq!(mempak.free_blocks(), free - 1);
+
+        // Everything survives reopening the pak
+        let mut mempak = Mempak::open(mempak.into_pif(), 0).unwrap();
+        let note = mempak.find("loka save", "a").unwrap();
+        assert_eq!(mempak.read(&note).unwrap()[..100], [0xaa; 100]);
+
+        mempak.delete(&note).unwrap();
+        assert_eq!(mempak.notes().count(), 0);
+        assert_eq!(mempak.free_blocks(), free);
+        assert_eq!(mempak.read(&note), Err(MempakError::NoteNotFound));
+    }
+
+    #[test]
+    fn mempak_errors() {
+        let mut pif = MockPif::new();
+        pif.insert_pak(1);
+        pif.set_connected(2, false);
+
+        assert_eq!(
+            Mempak::open(&mut pif, 0).err(),
+            Some(MempakError::Pak(PakError::NoPak))
+        );
+        assert_eq!(
+            Mempak::open(&mut pif, 1).err(),
+            Some(MempakError::NotFormatted)
+        );
+        assert_eq!(
+            Mempak::open(&mut pif, 2).err(),
+            Some(MempakError::Pak(PakError::NoController))
+        );
+
+        let mut pif = MockPif::new();
+        pif.insert_pak(0);
+        let mut mempak = Mempak::format(pif, 0).unwrap();
+
+        assert_eq!(
+            mempak.create(0, 0, "{bad}", "", &[]).err(),
+            Some(MempakError::InvalidName)
+        );
+        assert_eq!(
+            mempak.create(0, 0, "BIG", "", &[0; 124 * 256]).err(),
+            Some(MempakError::NoSpace)
+        );
+
+        for i in 0..NOTE_COUNT {
+            mempak.create(0, 0, "SMALL", "", &[i as u8]).unwrap();
+        }
+
+        assert_eq!(
+            mempak.create(0, 0, "ONE MORE", "", &[]).err(),
+            Some(MempakError::NoFreeNote)
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use crate::{Eeprom, FlashRam, Sram};
+use alloc::vec;
+use core::mem::size_of;
+use n64_sys::{
+    eeprom::{EepromError, EEPROM_BLOCK_SIZE},
+    flashram::{FlashRamError, FLASHRAM_PAGE_SIZE},
+};
+use zerocopy::{AsBytes, FromBytes};
+
+// Saves start with a header of magic, version, data size and data checksum
+const SAVE_MAGIC: [u8; 4] = *b"LSAV";
+const HEADER_SIZE: usize = 16;
+
+// Bump the version when the layout of the save data changes
+pub trait SaveData: AsBytes + FromBytes {
+    const VERSION: u16;
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SaveError {
+    Eeprom(EepromError),
+    FlashRam(FlashRamError),
+    TooLarge,
+    Empty,
+    Corrupt,
+    Version(u16),
+}
+
+impl From<EepromError> for SaveError {
+    fn from(err: EepromError) -> Self {
+        SaveError::Eeprom(err)
+    }
+}
+
+impl From<FlashRamError> for SaveError {
+    fn from(err: FlashRamError) -> Self {
+        SaveError::FlashRam(err)
+    }
+}
+
+// Cartridge save memory, offsets and lengths have to be multiples of the block size
+pub trait SaveStorage {
+    fn size(&self) -> usize;
+    fn block_size(&self) -> usize;
+    fn read(&mut self, offset: usize, data: &mut [u8]) -> Result<(), SaveError>;
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError>;
+}
+
+impl SaveStorage for Eeprom {
+    #[inline]
+    fn size(&self) -> usize {
+        Eeprom::size(self)
+    }
+
+    #[inline]
+    fn block_size(&self) -> usize {
+        EEPROM_BLOCK_SIZE
+    }
+
+    #[inline]
+    fn read(&mut self, offset: usize, data: &mut [u8]) -> Result<(), SaveError> {
+        Ok(Eeprom::read(self, offset, data)?)
+    }
+
+    #[inline]
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError> {
+        Ok(Eeprom::write(self, offset, data)?)
+    }
+}
+
+impl SaveStorage for Sram {
+    #[inline]
+    fn size(&self) -> usize {
+        Sram::size(self)
+    }
+
+    #[inline]
+    fn block_size(&self) -> usize {
+        2
+    }
+
+    #[inline]
+    fn read(&mut self, offset: usize, data: &mut [u8]) -> Result<(), SaveError> {
+        Sram::read(self, offset, data);
+        Ok(())
+    }
+
+    #[inline]
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError> {
+        Sram::write(self, offset, data);
+        Ok(())
+    }
+}
+
+impl SaveStorage for FlashRam {
+    #[inline]
+    fn size(&self) -> usize {
+        FlashRam::size(self)
+    }
+
+    #[inline]
+    fn block_size(&self) -> usize {
+        FLASHRAM_PAGE_SIZE
+    }
+
+    #[inline]
+    fn read(&mut self, offset: usize, data: &mut [u8]) -> Result<(), SaveError> {
+        FlashRam::read(self, offset, data);
+        Ok(())
+    }
+
+    #[inline]
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError> {
+        Ok(FlashRam::write(self, offset, data)?)
+    }
+}
+
+// 32 bit FNV-1a
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn padded_len(len: usize, block_size: usize) -> usize {
+    (len + block_size - 1) / block_size * block_size
+}
+
+// The header is padded to a whole number of blocks in front of the data
+fn header_len(block_size: usize) -> usize {
+    padded_len(HEADER_SIZE, block_size)
+}
+
+pub fn store<T: SaveData>(storage: &mut impl SaveStorage, value: &T) -> Result<(), SaveError> {
+    let block_size = storage.block_size();
+    let header_len = header_len(block_size);
+
+    let payload = value.as_bytes();
+    let mut data = vec![0; header_len + padded_len(payload.len(), block_size)];
+
+    if data.len() > storage.size() {
+        return Err(SaveError::TooLarge);
+    }
+
+    data[0..4].copy_from_slice(&SAVE_MAGIC);
+    data[4..6].copy_from_slice(&T::VERSION.to_be_bytes());
+    data[8..12].copy_from_slice(&(payload.len() as u32).to_be_bytes());
+    data[12..16].copy_from_slice(&checksum(payload).to_be_bytes());
+    data[header_len..header_len + payload.len()].copy_from_slice(payload);
+
+    storage.write(0, &data)
+}
+
+pub fn load<T: SaveData>(storage: &mut impl SaveStorage) -> Result<T, SaveError> {
+    let block_size = storage.block_size();
+    let header_len = header_len(block_size);
+
+    let mut header = vec![0; header_len];
+    storage.read(0, &mut header)?;
+
+    if header[0..4] != SAVE_MAGIC {
+        return Err(SaveError::Empty);
+    }
+
+    let version = u16::from_be_bytes([header[4], header[5]]);
+
+    if version != T::VERSION {
+        return Err(SaveError::Version(version));
+    }
+
+    let len = u32::from_be_bytes(header[8..12].try_into().unwrap()) as usize;
+
+    if len != size_of::<T>() || header_len + padded_len(len, block_size) > storage.size() {
+        return Err(SaveError::Corrupt);
+    }
+
+    let mut payload = vec![0; padded_len(len, block_size)];
+    storage.read(header_len, &mut payload)?;
+
+    if checksum(&payload[..len]) != u32::from_be_bytes(header[12..16].try_into().unwrap()) {
+        return Err(SaveError::Corrupt);
+    }
+
+    T::read_from(&payload[..len]).ok_or(SaveError::Corrupt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[repr(C)]
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, AsBytes, FromBytes)]
+    struct TestSave {
+        score: u32,
+        level: u16,
+        lives: u16,
+    }
+
+    impl SaveData for TestSave {
+        const VERSION: u16 = 3;
+    }
+
+    const TEST_SAVE: TestSave = TestSave {
+        score: 123456,
+        level: 7,
+        lives: 2,
+    };
+
+    struct MemoryStorage {
+        data: Vec<u8>,
+        block_size: usize,
+    }
+
+    impl MemoryStorage {
+        fn new(size: usize, block_size: usize, fill: u8) -> Self {
+            Self {
+                data: vec![fill; size],
+                block_size,
+            }
+        }
+    }
+
+    impl SaveStorage for MemoryStorage {
+        fn size(&self) -> usize {
+            self.data.len()
+        }
+
+        fn block_size(&self) -> usize {
+            self.block_size
+        }
+
+        fn read(&mut self, offset: usize, data: &mut [u8]) -> Result<(), SaveError> {
+            assert!(offset % self.block_size == 0 && data.len() % self.block_size == 0);
+            data.copy_from_slice(&self.data[offset..offset + data.len()]);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError> {
+            assert!(offset % self.block_size == 0 && data.len() % self.block_size == 0);
+            self.data[offset..offset + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn store_load_roundtrip() {
+        for block_size in [2, EEPROM_BLOCK_SIZE, FLASHRAM_PAGE_SIZE] {
+            let mut storage = MemoryStorage::new(512, block_size, 0);
+
+            store(&mut storage, &TEST_SAVE).unwrap();
+
+            assert_eq!(load::<TestSave>(&mut storage), Ok(TEST_SAVE));
+        }
+    }
+
+    #[test]
+    fn load_detects_bad_checksum() {
+        let mut storage = MemoryStorage::new(512, EEPROM_BLOCK_SIZE, 0);
+
+        store(&mut storage, &TEST_SAVE).unwrap();
+        storage.data[header_len(EEPROM_BLOCK_SIZE)] ^= 0x01;
+
+        assert_eq!(load::<TestSave>(&mut storage), Err(SaveError::Corrupt));
+    }
+
+    #[test]
+    fn load_uninitialised_storage() {
+        // Fresh EEPROM and SRAM read zeros and erased FlashRAM reads ones
+        for fill in [0x00, 0xff] {
+            let mut storage = MemoryStorage::new(512, EEPROM_BLOCK_SIZE, fill);
+
+            assert_eq!(load::<TestSave>(&mut storage), Err(SaveError::Empty));
+        }
+    }
+
+    #[test]
+    fn load_rejects_other_version() {
+        #[repr(C)]
+        #[derive(AsBytes, FromBytes)]
+        struct OldSave {
+            score: u32,
+            level: u16,
+            lives: u16,
+        }
+
+        impl SaveData for OldSave {
+            const VERSION: u16 = 2;
+        }
+
+        let mut storage = MemoryStorage::new(512, EEPROM_BLOCK_SIZE, 0);
+
+        store(
+            &mut storage,
+            &OldSave {
+                score: 1,
+                level: 1,
+                lives: 1,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(load::<TestSave>(&mut storage), Err(SaveError::Version(2)));
+    }
+
+    #[test]
+    fn store_rejects_too_large() {
+        let mut storage = MemoryStorage::new(16, EEPROM_BLOCK_SIZE, 0);
+
+        assert_eq!(store(&mut storage, &TEST_SAVE), Err(SaveError::TooLarge));
+    }
+}
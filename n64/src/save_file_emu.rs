@@ -0,0 +1,34 @@
+use std::{env, fs, path::PathBuf};
+
+// Save memory kept in memory and written through to a file
+pub(crate) struct SaveFile {
+    path: PathBuf,
+    data: Vec<u8>,
+}
+
+impl SaveFile {
+    // New or short files are filled up with the erased value of the memory
+    pub(crate) fn open(path_env: &str, default_path: &str, size: usize, erased: u8) -> SaveFile {
+        let path = env::var_os(path_env)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(default_path));
+
+        let mut data = fs::read(&path).unwrap_or_default();
+        data.resize(size, erased);
+
+        SaveFile { path, data }
+    }
+
+    #[inline]
+    pub(crate) fn read(&self, offset: usize, data: &mut [u8]) {
+        data.copy_from_slice(&self.data[offset..offset + data.len()]);
+    }
+
+    pub(crate) fn write(&mut self, offset: usize, data: &[u8]) {
+        self.data[offset..offset + data.len()].copy_from_slice(data);
+
+        if let Err(err) = fs::write(&self.path, &self.data) {
+            println!("Save File Error: {}: {}", self.path.display(), err);
+        }
+    }
+}
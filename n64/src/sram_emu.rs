@@ -0,0 +1,36 @@
+use crate::save_file_emu::SaveFile;
+use n64_sys::sram::SRAM_SIZE;
+
+const SRAM_PATH_ENV: &str = "N64_SRAM_PATH";
+const DEFAULT_SRAM_PATH: &str = "save.sram";
+
+pub struct Sram {
+    file: SaveFile,
+}
+
+impl Sram {
+    #[inline]
+    pub fn new() -> Option<Sram> {
+        Some(Sram {
+            file: SaveFile::open(SRAM_PATH_ENV, DEFAULT_SRAM_PATH, SRAM_SIZE, 0),
+        })
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        SRAM_SIZE
+    }
+
+    // Offset and length have to be even
+    pub fn read(&mut self, offset: usize, data: &mut [u8]) {
+        assert!(offset % 2 == 0 && data.len() % 2 == 0);
+
+        self.file.read(offset, data);
+    }
+
+    pub fn write(&mut self, offset: usize, data: &[u8]) {
+        assert!(offset % 2 == 0 && data.len() % 2 == 0);
+
+        self.file.write(offset, data);
+    }
+}
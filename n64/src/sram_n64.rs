@@ -0,0 +1,33 @@
+#![cfg_attr(not(target_vendor = "nintendo64"), allow(unused))]
+
+use n64_sys::sram::{self, SRAM_SIZE};
+
+pub struct Sram {}
+
+impl Sram {
+    // None when the cartridge has no SRAM
+    #[inline]
+    pub fn new() -> Option<Sram> {
+        if sram::detect() {
+            Some(Sram {})
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        SRAM_SIZE
+    }
+
+    // Offset and length have to be even
+    #[inline]
+    pub fn read(&mut self, offset: usize, data: &mut [u8]) {
+        sram::read(offset, data);
+    }
+
+    #[inline]
+    pub fn write(&mut self, offset: usize, data: &[u8]) {
+        sram::write(offset, data);
+    }
+}